@@ -7,24 +7,122 @@
 use {
     crate::{
         error::{BindError, CommandError, KillError, QueryError, ServeError, ShutdownError},
-        launch_process, FSConfig,
+        launch_process, launch_process_with_options, FSConfig,
     },
     anyhow::{anyhow, Error},
     cstr::cstr,
-    fdio::SpawnAction,
+    fdio::{SpawnAction, SpawnOptions},
     fidl::encoding::Decodable,
     fidl::endpoints::{ClientEnd, ServerEnd},
     fidl_fuchsia_component as fcomponent, fidl_fuchsia_component_decl as fdecl,
-    fidl_fuchsia_fs_startup::{CheckOptions, FormatOptions, StartOptions, StartupMarker},
-    fidl_fuchsia_io as fio,
-    fuchsia_async::OnSignals,
+    fidl_fuchsia_fs_startup::{
+        CheckOptions, FormatOptions as StartupFormatOptions, StartOptions, StartupMarker,
+    },
+    fidl_fuchsia_io as fio, fidl_fuchsia_update_verify as fverify,
+    fuchsia_async::{self as fasync, OnSignals, TimeoutExt},
     fuchsia_component::client::{connect_to_protocol, connect_to_protocol_at_dir_root},
     fuchsia_runtime::{HandleInfo, HandleType},
-    fuchsia_zircon::{Channel, Handle, Process, Signals, Status, Task},
+    fuchsia_zircon::{
+        Channel, Duration, Handle, Process, Signals, Socket, SocketOpts, Status, Task,
+    },
+    futures::{io::AsyncReadExt, FutureExt},
     log::warn,
-    std::sync::Mutex,
+    std::{ffi::CString, io::Write as _, sync::Mutex},
 };
 
+/// Options for [`Filesystem::format_with_options()`].
+#[derive(Clone, Debug, Default)]
+pub struct FormatOptions {
+    /// The number of inodes to allocate space for in the formatted filesystem, rather than the
+    /// filesystem's default.  Only filesystems whose [`FSConfig::supports_format_inodes()`]
+    /// returns true support this option.  It is passed as `--num_inodes` to the filesystem binary,
+    /// or as `num_inodes` in the `fuchsia.fs.startup.FormatOptions` of filesystems running as
+    /// components.
+    pub inodes: Option<u64>,
+}
+
+/// Options for [`Filesystem::fsck_with_options()`].  There are none yet: the arguments passed to
+/// the filesystem, such as `--verbose`, are controlled by its [`FSConfig`].
+#[derive(Clone, Debug, Default)]
+pub struct FsckOptions {}
+
+/// The outcome of [`Filesystem::fsck_with_options()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FsckResult {
+    /// The filesystem was found to be inconsistent.  The filesystem process reports this by
+    /// exiting with a non-zero return code, and filesystems running as components by failing the
+    /// check with `ZX_ERR_IO_DATA_INTEGRITY`.  The checks never repair the filesystem.
+    pub errors_found: bool,
+    /// Everything the filesystem process wrote to stdout and stderr.  Filesystems running as
+    /// components log elsewhere, so for them this only describes the error returned by the check,
+    /// if any.
+    pub log: String,
+}
+
+/// Options for [`Filesystem::serve_with_options()`].
+#[derive(Debug, Default)]
+pub struct ServeOptions {
+    /// Serves the filesystem read-only.  The filesystem is asked not to modify the block device,
+    /// and the root directory is opened without write rights, so writes through it fail with
+    /// `ZX_ERR_ACCESS_DENIED`.
+    ///
+    /// A configuration with its own `readonly` field set, such as [`crate::Minfs::readonly`], is
+    /// always served read-only; this option makes a configuration that is not read-only be served
+    /// read-only for one serve.
+    pub readonly: bool,
+
+    /// Gives the caller the opportunity to migrate data before the filesystem is served.  The
+    /// filesystem serves its root directory on this channel first, and only finishes starting once
+    /// the channel, and any other channels opened through it, are closed.  Only filesystems
+    /// running as components support this option; serving other filesystems with it set fails
+    /// with [`ServeError::MigrateRootNotSupported`].
+    pub migrate_root: Option<ServerEnd<fio::DirectoryMarker>>,
+}
+
+/// Information about a serving filesystem, returned by [`ServingFilesystem::query()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilesystemInfo {
+    /// The total number of bytes the filesystem can hold.
+    pub total_bytes: u64,
+    /// The number of bytes used by the filesystem.
+    pub used_bytes: u64,
+    /// The total number of nodes the filesystem can hold.
+    pub total_nodes: u64,
+    /// The number of nodes used by the filesystem.
+    pub used_nodes: u64,
+    /// The number of bytes the filesystem could grow by, from a pool shared with other volumes.
+    pub free_shared_pool_bytes: u64,
+    /// A unique identifier of the filesystem instance.
+    pub fs_id: u64,
+    /// The block size of the filesystem, in bytes.
+    pub block_size: u32,
+    /// The maximum length of a name in the filesystem.
+    pub max_filename_size: u32,
+    /// The type of the filesystem, one of the constants in [`crate::vfs_type`].
+    pub fs_type: u32,
+    /// The name of the filesystem, such as "minfs".
+    pub name: String,
+}
+
+impl From<&fio::FilesystemInfo> for FilesystemInfo {
+    fn from(info: &fio::FilesystemInfo) -> Self {
+        // The name is a NUL-padded C string.
+        let name = info.name.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect::<Vec<_>>();
+        FilesystemInfo {
+            total_bytes: info.total_bytes,
+            used_bytes: info.used_bytes,
+            total_nodes: info.total_nodes,
+            used_nodes: info.used_nodes,
+            free_shared_pool_bytes: info.free_shared_pool_bytes,
+            fs_id: info.fs_id,
+            block_size: info.block_size,
+            max_filename_size: info.max_filename_size,
+            fs_type: info.fs_type,
+            name: String::from_utf8_lossy(&name).into_owned(),
+        }
+    }
+}
+
 /// Asynchronously manages a block device for filesystem operations.
 pub struct Filesystem<FSC> {
     config: FSC,
@@ -60,30 +158,64 @@ impl<FSC: FSConfig> Filesystem<FSC> {
     ///
     /// Returns [`Err`] if the filesystem process failed to launch or returned a non-zero exit code.
     pub async fn format(&self) -> Result<(), Error> {
+        self.format_with_options(FormatOptions::default()).await
+    }
+
+    /// Runs `mkfs`, same as [`Filesystem::format()`], configured by `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the filesystem process failed to launch or returned a non-zero exit code.
+    /// Returns [`CommandError::InodesNotSupported`], without launching the filesystem process, if
+    /// `options.inodes` is set for a filesystem that does not support it.
+    pub async fn format_with_options(&self, options: FormatOptions) -> Result<(), Error> {
+        if options.inodes.is_some() && !self.config.supports_format_inodes() {
+            return Err(CommandError::InodesNotSupported.into());
+        }
+
         if self.config.component_name().is_some() {
             let proxy = connect_to_protocol::<StartupMarker>()?;
-            let mut options = FormatOptions::new_empty();
-            options.crypt = self.config.crypt_client().map(|c| c.into());
+            let mut startup_options = StartupFormatOptions::new_empty();
+            startup_options.crypt = self.config.crypt_client().map(|c| c.into());
+            if let Some(inodes) = options.inodes {
+                startup_options.num_inodes = inodes;
+            }
             proxy
-                .format(self.get_block_handle()?.into(), &mut options)
+                .format(self.get_block_handle()?.into(), &mut startup_options)
                 .await?
                 .map_err(Status::from_raw)?;
         } else {
-            // SpawnAction is not Send, so make sure it is dropped before any `await`s.
-            let process = {
+            let inodes = options.inodes.map(|inodes| CString::new(inodes.to_string()).unwrap());
+            let (log, process) = {
+                let (log, log_remote) = create_log_socket()?;
+
+                // SpawnAction is not Send, so make sure it is dropped before any `await`s.
                 let mut args = vec![self.config.binary_path(), cstr!("mkfs")];
                 args.append(&mut self.config.generic_args());
                 args.append(&mut self.config.format_args());
+                if let Some(inodes) = &inodes {
+                    args.push(cstr!("--num_inodes"));
+                    args.push(inodes);
+                }
                 let actions = vec![
                     // device handle is passed in as a PA_USER0 handle at argument 1
                     SpawnAction::add_handle(
                         HandleInfo::new(HandleType::User0, 1),
                         self.get_block_handle()?,
                     ),
+                    SpawnAction::clone_fd(&log_remote, 1),
+                    SpawnAction::clone_fd(&log_remote, 2),
                 ];
-                launch_process(&args, actions)?
+                let process = launch_process_with_options(
+                    &args,
+                    actions,
+                    SpawnOptions::CLONE_ALL - SpawnOptions::CLONE_STDIO,
+                )?;
+                (log, process)
             };
-            wait_for_successful_exit(process).await?;
+
+            let log = read_log(log).await?;
+            wait_for_successful_exit(process, &log).await?;
         }
         Ok(())
     }
@@ -98,34 +230,86 @@ impl<FSC: FSConfig> Filesystem<FSC> {
     /// # Errors
     ///
     /// Returns [`Err`] if the filesystem process failed to launch or returned a non-zero exit code.
+    /// Use [`Filesystem::fsck_with_options()`] to tell an inconsistent filesystem from a failure
+    /// to run the check.
     pub async fn fsck(&self) -> Result<(), Error> {
         if self.config.component_name().is_some() {
-            let proxy = connect_to_protocol::<StartupMarker>()?;
-            let mut options = CheckOptions::new_empty();
-            options.crypt = self.config.crypt_client().map(|c| c.into());
-            proxy
-                .check(self.get_block_handle()?.into(), &mut options)
-                .await?
-                .map_err(Status::from_raw)?;
+            self.check_component().await?.map_err(Status::from_raw)?;
         } else {
-            // SpawnAction is not Send, so make sure it is dropped before any `await`s.
-            let process = {
-                let mut args = vec![self.config.binary_path(), cstr!("fsck")];
-                args.append(&mut self.config.generic_args());
-                let actions = vec![
-                    // device handle is passed in as a PA_USER0 handle at argument 1
-                    SpawnAction::add_handle(
-                        HandleInfo::new(HandleType::User0, 1),
-                        self.get_block_handle()?,
-                    ),
-                ];
-                launch_process(&args, actions)?
-            };
-            wait_for_successful_exit(process).await?;
+            let (_log, exit) = self.run_fsck().await?;
+            exit?;
         }
         Ok(())
     }
 
+    /// Runs `fsck`, same as [`Filesystem::fsck()`], but reports the outcome of the check as an
+    /// [`FsckResult`], including the output of the filesystem process.  An inconsistent filesystem
+    /// is not an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the filesystem process failed to launch or could not be waited on, or if
+    /// a filesystem running as a component failed the check for a reason other than finding
+    /// inconsistencies.
+    pub async fn fsck_with_options(&self, _options: FsckOptions) -> Result<FsckResult, Error> {
+        if self.config.component_name().is_some() {
+            return match self.check_component().await?.map_err(Status::from_raw) {
+                Ok(()) => Ok(FsckResult::default()),
+                Err(status @ Status::IO_DATA_INTEGRITY) => {
+                    Ok(FsckResult { errors_found: true, log: format!("check failed: {}", status) })
+                }
+                Err(status) => Err(status.into()),
+            };
+        }
+
+        let (log, exit) = self.run_fsck().await?;
+        let errors_found = match exit {
+            Ok(()) => false,
+            Err(e) if e.return_code().is_some() => true,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(FsckResult { errors_found, log })
+    }
+
+    /// Asks a filesystem running as a component to check the block device.
+    async fn check_component(&self) -> Result<Result<(), i32>, Error> {
+        let proxy = connect_to_protocol::<StartupMarker>()?;
+        let mut options = CheckOptions::new_empty();
+        options.crypt = self.config.crypt_client().map(|c| c.into());
+        Ok(proxy.check(self.get_block_handle()?.into(), &mut options).await?)
+    }
+
+    /// Runs the `fsck` command of the filesystem binary.  Returns the output of the process, and
+    /// how it exited.
+    async fn run_fsck(&self) -> Result<(String, Result<(), CommandError>), Error> {
+        let (log, process) = {
+            let (log, log_remote) = create_log_socket()?;
+
+            // SpawnAction is not Send, so make sure it is dropped before any `await`s.
+            let mut args = vec![self.config.binary_path(), cstr!("fsck")];
+            args.append(&mut self.config.generic_args());
+            let actions = vec![
+                // device handle is passed in as a PA_USER0 handle at argument 1
+                SpawnAction::add_handle(
+                    HandleInfo::new(HandleType::User0, 1),
+                    self.get_block_handle()?,
+                ),
+                SpawnAction::clone_fd(&log_remote, 1),
+                SpawnAction::clone_fd(&log_remote, 2),
+            ];
+            let process = launch_process_with_options(
+                &args,
+                actions,
+                SpawnOptions::CLONE_ALL - SpawnOptions::CLONE_STDIO,
+            )?;
+            (log, process)
+        };
+
+        let log = read_log(log).await?;
+        let exit = wait_for_successful_exit(process, &log).await;
+        Ok((log, exit))
+    }
+
     /// Serves the filesystem on the block device and returns a [`ServingFilesystem`] representing
     /// the running filesystem process.
     ///
@@ -133,14 +317,35 @@ impl<FSC: FSConfig> Filesystem<FSC> {
     ///
     /// Returns [`Err`] if serving the filesystem failed.
     pub async fn serve(&self) -> Result<ServingFilesystem, Error> {
+        self.serve_with_options(ServeOptions::default()).await
+    }
+
+    /// Serves the filesystem on the block device, same as [`Filesystem::serve()`], configured by
+    /// `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if serving the filesystem failed, [`ServeError::ReadOnlyNotSupported`] if
+    /// `options.readonly` is set but the filesystem can not be served read-only, or
+    /// [`ServeError::MigrateRootNotSupported`] if `options.migrate_root` is set but the filesystem
+    /// is not running as a component.
+    pub async fn serve_with_options(
+        &self,
+        options: ServeOptions,
+    ) -> Result<ServingFilesystem, Error> {
+        let mut options =
+            ServeOptions { readonly: options.readonly || self.config.readonly(), ..options };
+
         // If the filesystem is a component, the startup service must be routed to this component.
         // For now, only one filesystem instance is supported.
         if let Some(component_name) = self.config.component_name() {
             let proxy = connect_to_protocol::<StartupMarker>()?;
-            let mut options = StartOptions::new_empty();
-            options.crypt = self.config.crypt_client().map(|c| c.into());
+            let mut start_options = StartOptions::new_empty();
+            start_options.read_only = options.readonly;
+            start_options.crypt = self.config.crypt_client().map(|c| c.into());
+            start_options.migrate_root = options.migrate_root.take();
             proxy
-                .start(self.get_block_handle()?.into(), &mut options)
+                .start(self.get_block_handle()?.into(), &mut start_options)
                 .await?
                 .map_err(Status::from_raw)?;
 
@@ -155,21 +360,15 @@ impl<FSC: FSConfig> Filesystem<FSC> {
                 .await?
                 .map_err(|e| anyhow!("OpenExposedDir error: {:?}", e))?;
             let (root_dir, server_end) = fidl::endpoints::create_endpoints::<fio::NodeMarker>()?;
-            exposed_dir.open(
-                fio::OpenFlags::RIGHT_READABLE
-                    | fio::OpenFlags::POSIX_EXECUTABLE
-                    | fio::OpenFlags::POSIX_WRITABLE,
-                0,
-                "root",
-                server_end,
-            )?;
+            exposed_dir.open(root_flags(&options), 0, "root", server_end)?;
 
             Ok(ServingFilesystem {
                 process: None,
                 exposed_dir,
                 root_dir: ClientEnd::<fio::DirectoryMarker>::new(root_dir.into_channel())
                     .into_proxy()?,
-                binding: Mutex::new(None),
+                bindings: Mutex::new(Vec::new()),
+                supports_online_verify: self.config.supports_online_verify(),
             })
         } else {
             // do_serve is returning the outgoing directory for the process which is different from
@@ -178,19 +377,26 @@ impl<FSC: FSConfig> Filesystem<FSC> {
             // root rather than via the usual /svc directory, so we can treat them as the same.
             // This is fine for now since this mechanism of mounting filesystems will go away at
             // some point.
-            let (process, export_root, root_dir) = self.do_serve().await?;
+            let (process, export_root, root_dir) = self.do_serve(&options).await?;
             Ok(ServingFilesystem {
                 process: Some(process),
                 exposed_dir: export_root,
                 root_dir,
-                binding: Mutex::new(None),
+                bindings: Mutex::new(Vec::new()),
+                supports_online_verify: self.config.supports_online_verify(),
             })
         }
     }
 
     async fn do_serve(
         &self,
+        options: &ServeOptions,
     ) -> Result<(Process, fio::DirectoryProxy, fio::DirectoryProxy), ServeError> {
+        // The filesystem binaries only receive a root to migrate through the startup protocol.
+        if options.migrate_root.is_some() {
+            return Err(ServeError::MigrateRootNotSupported);
+        }
+
         let (export_root, server_end) = fidl::endpoints::create_proxy::<fio::DirectoryMarker>()?;
 
         // SpawnAction is not Send, so make sure it is dropped before any `await`s.
@@ -198,6 +404,12 @@ impl<FSC: FSConfig> Filesystem<FSC> {
             let mut args = vec![self.config.binary_path(), cstr!("mount")];
             args.append(&mut self.config.generic_args());
             args.append(&mut self.config.mount_args());
+            // A read-only configuration already includes the read-only arguments in `mount_args`.
+            if options.readonly && !self.config.readonly() {
+                let mut readonly_args =
+                    self.config.readonly_mount_args().ok_or(ServeError::ReadOnlyNotSupported)?;
+                args.append(&mut readonly_args);
+            }
             let actions = vec![
                 // export root handle is passed in as a PA_DIRECTORY_REQUEST handle at argument 0
                 SpawnAction::add_handle(
@@ -216,19 +428,21 @@ impl<FSC: FSConfig> Filesystem<FSC> {
 
         // Wait until the filesystem is ready to take incoming requests.
         let (root_dir, server_end) = fidl::endpoints::create_proxy::<fio::DirectoryMarker>()?;
-        export_root.open(
-            fio::OpenFlags::RIGHT_READABLE
-                | fio::OpenFlags::POSIX_EXECUTABLE
-                | fio::OpenFlags::POSIX_WRITABLE,
-            0,
-            "root",
-            server_end.into_channel().into(),
-        )?;
+        export_root.open(root_flags(options), 0, "root", server_end.into_channel().into())?;
         let _ = root_dir.describe().await?;
         Ok((process, export_root, root_dir))
     }
 }
 
+/// Returns the flags used to open the root directory of a filesystem served with `options`.
+fn root_flags(options: &ServeOptions) -> fio::OpenFlags {
+    let mut flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::POSIX_EXECUTABLE;
+    if !options.readonly {
+        flags |= fio::OpenFlags::POSIX_WRITABLE;
+    }
+    flags
+}
+
 /// Asynchronously manages a serving filesystem. Created from [`Filesystem::serve()`].
 pub struct ServingFilesystem {
     // If the filesystem is running as a component, there will be no process and no export root.
@@ -236,8 +450,11 @@ pub struct ServingFilesystem {
     exposed_dir: fio::DirectoryProxy,
     root_dir: fio::DirectoryProxy,
 
-    // The path in the local namespace that this filesystem is bound to (optional).
-    binding: Mutex<Option<String>>,
+    // The paths in the local namespace that this filesystem is bound to.
+    bindings: Mutex<Vec<String>>,
+
+    // Whether the filesystem serves `fuchsia.update.verify.BlobfsVerifier`.
+    supports_online_verify: bool,
 }
 
 impl ServingFilesystem {
@@ -246,19 +463,41 @@ impl ServingFilesystem {
         &self.root_dir
     }
 
-    /// Binds the root directory being served by this filesystem to a path in the local namespace.
-    /// The path must be absolute, containing no "." nor ".." entries.  The binding will be dropped
-    /// when self is dropped.  Only one binding is supported.
+    /// Binds the root directory being served by this filesystem to `path` in the local namespace.
+    /// The path must be absolute, containing no "." nor ".." entries.  The filesystem can be bound
+    /// at several paths, and other filesystems can be bound at other paths of the same namespace.
+    /// All the bindings of this filesystem will be dropped when self is dropped.
     ///
     /// # Errors
     ///
-    /// Returns [`Err`] if binding failed.
+    /// Returns [`BindError::Bind`] if `path` is already bound in the local namespace, or can not
+    /// be bound for any other reason.
     pub fn bind_to_path<'a>(&self, path: &str) -> Result<(), BindError> {
         let (client_end, server_end) = Channel::create().map_err(fidl::Error::ChannelPairCreate)?;
         self.root_dir.clone(fio::OpenFlags::CLONE_SAME_RIGHTS, ServerEnd::new(server_end))?;
         let namespace = fdio::Namespace::installed().map_err(BindError::LocalNamespace)?;
         namespace.bind(path, client_end).map_err(BindError::Bind)?;
-        *self.binding.lock().unwrap() = Some(path.to_string());
+        self.bindings.lock().unwrap().push(path.to_string());
+        Ok(())
+    }
+
+    /// Removes the binding of the root directory at `path` from the local namespace, installed by
+    /// [`ServingFilesystem::bind_to_path()`].  The filesystem keeps serving, and can be bound
+    /// again, at the same or at a different path.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BindError::Unbind`] with `ZX_ERR_NOT_FOUND` if this filesystem is not bound at
+    /// `path`, or with the error reported by the local namespace if removing the binding failed.
+    pub fn unbind(&self, path: &str) -> Result<(), BindError> {
+        let mut bindings = self.bindings.lock().unwrap();
+        let index = bindings
+            .iter()
+            .position(|binding| binding == path)
+            .ok_or(BindError::Unbind(Status::NOT_FOUND))?;
+        let namespace = fdio::Namespace::installed().map_err(BindError::LocalNamespace)?;
+        namespace.unbind(path).map_err(BindError::Unbind)?;
+        bindings.remove(index);
         Ok(())
     }
 
@@ -298,15 +537,94 @@ impl ServingFilesystem {
         Ok(())
     }
 
+    /// Attempts to shutdown the filesystem, same as [`ServingFilesystem::shutdown()`], but if the
+    /// filesystem process does not terminate within `timeout`, kills it instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the shutdown failed, or the filesystem process could not be killed after
+    /// the timeout elapsed.  Returns [`ShutdownError::TimedOut`] if the filesystem runs as a
+    /// component and did not shut down within `timeout`.
+    pub async fn shutdown_or_kill(mut self, timeout: Duration) -> Result<(), ShutdownError> {
+        let deadline = fasync::Time::after(timeout);
+        let admin =
+            connect_to_protocol_at_dir_root::<fidl_fuchsia_fs::AdminMarker>(&self.exposed_dir)?;
+        let process = match self.process.take() {
+            Some(process) => process,
+            None => {
+                return admin
+                    .shutdown()
+                    .map(|result| result.map_err(ShutdownError::from))
+                    .on_timeout(deadline, || Err(ShutdownError::TimedOut))
+                    .await;
+            }
+        };
+
+        let terminated = async {
+            admin.shutdown().await?;
+            OnSignals::new(&process, Signals::PROCESS_TERMINATED)
+                .await
+                .map_err(ShutdownError::ProcessTerminatedSignal)?;
+            Ok::<(), ShutdownError>(())
+        };
+        match terminated.map(Some).on_timeout(deadline, || None).await {
+            Some(Ok(())) => {
+                let info = process.info().map_err(ShutdownError::GetProcessReturnCode)?;
+                if info.return_code != 0 {
+                    warn!(
+                        "process returned non-zero exit code ({}) after shutdown",
+                        info.return_code
+                    );
+                }
+                Ok(())
+            }
+            Some(Err(e)) => {
+                if process.kill().is_ok() {
+                    let _ = OnSignals::new(&process, Signals::PROCESS_TERMINATED).await;
+                }
+                Err(e)
+            }
+            None => {
+                warn!("filesystem did not shut down within {:?}, killing it", timeout);
+                process.kill().map_err(KillError::TaskKill)?;
+                let _ = OnSignals::new(&process, Signals::PROCESS_TERMINATED)
+                    .await
+                    .map_err(KillError::ProcessTerminatedSignal)?;
+                Ok(())
+            }
+        }
+    }
+
     /// Returns a [`FilesystemInfo`] object containing information about the serving filesystem.
     ///
     /// # Errors
     ///
     /// Returns [`Err`] if querying the filesystem failed.
-    pub async fn query(&self) -> Result<Box<fio::FilesystemInfo>, QueryError> {
+    pub async fn query(&self) -> Result<FilesystemInfo, QueryError> {
         let (status, info) = self.root_dir.query_filesystem().await?;
         Status::ok(status).map_err(QueryError::DirectoryQuery)?;
-        info.ok_or(QueryError::DirectoryEmptyResult)
+        info.map(|info| FilesystemInfo::from(&*info)).ok_or(QueryError::DirectoryEmptyResult)
+    }
+
+    /// Checks the consistency of the filesystem while it keeps serving, using the
+    /// `fuchsia.update.verify.BlobfsVerifier` protocol of the filesystem.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CommandError::Verify`] with `ZX_ERR_NOT_SUPPORTED`, without contacting the
+    /// filesystem, if the filesystem does not support online verification, and with
+    /// `ZX_ERR_IO_DATA_INTEGRITY` if the filesystem was found to be inconsistent.
+    pub async fn verify(&self) -> Result<(), CommandError> {
+        if !self.supports_online_verify {
+            return Err(CommandError::Verify(Status::NOT_SUPPORTED));
+        }
+        let verifier =
+            connect_to_protocol_at_dir_root::<fverify::BlobfsVerifierMarker>(&self.exposed_dir)
+                .map_err(|_| CommandError::Verify(Status::UNAVAILABLE))?;
+        verifier
+            .verify(fverify::VerifyOptions::EMPTY)
+            .await?
+            .map_err(|_: fverify::VerifyError| CommandError::Verify(Status::IO_DATA_INTEGRITY))
     }
 
     /// Attempts to kill the filesystem process and waits for the process to terminate.
@@ -335,15 +653,50 @@ impl Drop for ServingFilesystem {
         if let Some(process) = self.process.take() {
             let _ = process.kill();
         }
-        if let Some(path) = self.binding.lock().unwrap().as_ref() {
+        let bindings = std::mem::take(&mut *self.bindings.lock().unwrap());
+        if !bindings.is_empty() {
             if let Ok(namespace) = fdio::Namespace::installed() {
-                let _ = namespace.unbind(path);
+                for path in &bindings {
+                    let _ = namespace.unbind(path);
+                }
             }
         }
     }
 }
 
-async fn wait_for_successful_exit(process: Process) -> Result<(), CommandError> {
+/// Number of the last lines of the process output included in
+/// [`CommandError::ProcessNonZeroReturnCodeWithOutput`].
+const ERROR_OUTPUT_LINES: usize = 20;
+
+/// Creates a socket to capture the stdout and stderr of a filesystem process.  Returns our end of
+/// the socket, and a file descriptor for the remote end, to be passed to the process with
+/// [`SpawnAction::clone_fd()`].
+fn create_log_socket() -> Result<(Socket, std::fs::File), Error> {
+    let (log, log_remote) = Socket::create(SocketOpts::STREAM)?;
+    let log_remote = fdio::create_fd(log_remote.into())?;
+    Ok((log, log_remote))
+}
+
+/// Reads everything a process writes into the socket created by [`create_log_socket()`].  The
+/// output is also passed on to our own stderr as it arrives, so the output of processes that
+/// succeed still shows up where it did when the process shared our stdio.
+async fn read_log(log: Socket) -> Result<String, Error> {
+    let mut log = fasync::Socket::from_socket(log)?;
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        // Our end of the socket is closed once the process terminates.
+        let read = log.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        let _ = std::io::stderr().write_all(&chunk[..read]);
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+async fn wait_for_successful_exit(process: Process, log: &str) -> Result<(), CommandError> {
     let _ = OnSignals::new(&process, Signals::PROCESS_TERMINATED)
         .await
         .map_err(CommandError::ProcessTerminatedSignal)?;
@@ -352,7 +705,12 @@ async fn wait_for_successful_exit(process: Process) -> Result<(), CommandError>
     if info.return_code == 0 {
         Ok(())
     } else {
-        Err(CommandError::ProcessNonZeroReturnCode(info.return_code))
+        let lines: Vec<_> = log.lines().collect();
+        let output = lines[lines.len().saturating_sub(ERROR_OUTPUT_LINES)..].join("\n");
+        Err(CommandError::ProcessNonZeroReturnCodeWithOutput {
+            return_code: info.return_code,
+            output,
+        })
     }
 }
 
@@ -364,8 +722,8 @@ mod tests {
         super::*,
         crate::{BlobCompression, BlobEvictionPolicy, Blobfs, Factoryfs, Minfs},
         fidl_fuchsia_io as fio,
-        fuchsia_zircon::HandleBased,
-        ramdevice_client::RamdiskClient,
+        fuchsia_zircon::{AsHandleRef, HandleBased},
+        ramdevice_client::{ErrorInjection, RamdiskClient},
         std::io::{Seek, Write},
     };
 
@@ -382,6 +740,49 @@ mod tests {
         Filesystem::from_channel(ramdisk.open().unwrap(), config).unwrap()
     }
 
+    // pre-generated merkle test fixture data
+    const TEST_BLOB_MERKLE: &str =
+        "be901a14ec42ee0a8ee220eb119294cdd40d26d573139ee3d51e4430e7d08c28";
+    const TEST_BLOB_CONTENT: &[u8] = b"test content";
+
+    /// Writes [`TEST_BLOB_CONTENT`] to the blobfs served at `root`, as blob [`TEST_BLOB_MERKLE`].
+    async fn write_test_blob(root: &fio::DirectoryProxy) {
+        let test_file = fuchsia_fs::directory::open_file(
+            root,
+            TEST_BLOB_MERKLE,
+            fio::OpenFlags::CREATE | fio::OpenFlags::RIGHT_WRITABLE,
+        )
+        .await
+        .expect("failed to create test file");
+        let () = test_file
+            .resize(TEST_BLOB_CONTENT.len() as u64)
+            .await
+            .expect("failed to send resize FIDL")
+            .map_err(Status::from_raw)
+            .expect("failed to resize file");
+        let _: u64 = test_file
+            .write(TEST_BLOB_CONTENT)
+            .await
+            .expect("failed to write to test file")
+            .map_err(Status::from_raw)
+            .expect("write error");
+    }
+
+    /// Checks that blob [`TEST_BLOB_MERKLE`] in the blobfs served at `root` holds
+    /// [`TEST_BLOB_CONTENT`].
+    async fn read_test_blob(root: &fio::DirectoryProxy) {
+        let test_file = fuchsia_fs::directory::open_file(
+            root,
+            TEST_BLOB_MERKLE,
+            fio::OpenFlags::RIGHT_READABLE,
+        )
+        .await
+        .expect("failed to open test file");
+        let read_content =
+            fuchsia_fs::file::read(&test_file).await.expect("failed to read from test file");
+        assert_eq!(read_content, TEST_BLOB_CONTENT);
+    }
+
     #[fuchsia::test]
     async fn blobfs_custom_config() {
         let block_size = 512;
@@ -436,6 +837,139 @@ mod tests {
         ramdisk.destroy().expect("failed to destroy ramdisk");
     }
 
+    #[fuchsia::test]
+    async fn blobfs_verify() {
+        let block_size = 512;
+        let ramdisk = ramdisk(block_size);
+        let blobfs = new_fs(&ramdisk, Blobfs::default());
+
+        blobfs.format().await.expect("failed to format blobfs");
+        let serving = blobfs.serve().await.expect("failed to serve blobfs");
+
+        write_test_blob(serving.root()).await;
+
+        serving.verify().await.expect("verify failed");
+
+        serving.shutdown().await.expect("failed to shutdown blobfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_verify_not_supported() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let serving = minfs.serve().await.expect("failed to serve minfs");
+        assert!(matches!(serving.verify().await, Err(CommandError::Verify(Status::NOT_SUPPORTED))));
+
+        serving.shutdown().await.expect("failed to shutdown minfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn blobfs_serve_with_compression_write_read() {
+        let block_size = 512;
+        let ramdisk = ramdisk(block_size);
+        let config =
+            Blobfs { blob_compression: Some(BlobCompression::ZSTDChunked), ..Default::default() };
+        let blobfs = new_fs(&ramdisk, config);
+
+        blobfs.format().await.expect("failed to format blobfs");
+
+        let serving = blobfs.serve().await.expect("failed to serve blobfs");
+
+        write_test_blob(serving.root()).await;
+
+        read_test_blob(serving.root()).await;
+
+        serving.shutdown().await.expect("failed to shutdown blobfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn blobfs_format_with_inodes() {
+        let block_size = 512;
+        let ramdisk = ramdisk(block_size);
+        let blobfs = new_fs(&ramdisk, Blobfs::default());
+
+        blobfs.format().await.expect("failed to format blobfs");
+        let serving = blobfs.serve().await.expect("failed to serve blobfs");
+        let default_nodes = serving.query().await.expect("failed to query filesystem").total_nodes;
+        serving.shutdown().await.expect("failed to shutdown blobfs");
+
+        blobfs
+            .format_with_options(FormatOptions { inodes: Some(512), ..Default::default() })
+            .await
+            .expect("failed to format blobfs");
+        let serving = blobfs.serve().await.expect("failed to serve blobfs");
+        let total_nodes = serving.query().await.expect("failed to query filesystem").total_nodes;
+        assert!(total_nodes >= 512, "{} nodes available", total_nodes);
+        assert!(total_nodes < default_nodes, "{} >= {} nodes", total_nodes, default_nodes);
+        serving.shutdown().await.expect("failed to shutdown blobfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_format_with_inodes_runs_out_of_nodes() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs
+            .format_with_options(FormatOptions { inodes: Some(64), ..Default::default() })
+            .await
+            .expect("failed to format minfs");
+        let serving = minfs.serve().await.expect("failed to serve minfs");
+        let info = serving.query().await.expect("failed to query filesystem");
+        assert!(info.total_nodes < 1000, "{} nodes available", info.total_nodes);
+
+        let mut created = 0;
+        let status = loop {
+            match fuchsia_fs::directory::open_file(
+                serving.root(),
+                &format!("file-{}", created),
+                fio::OpenFlags::CREATE | fio::OpenFlags::RIGHT_WRITABLE,
+            )
+            .await
+            {
+                Ok(_) => created += 1,
+                Err(fuchsia_fs::node::OpenError::OpenError(status)) => break status,
+                Err(e) => panic!("unexpected error creating file-{}: {:?}", created, e),
+            }
+            assert!(created < 1000, "minfs did not run out of nodes");
+        };
+        assert_eq!(status, Status::NO_SPACE);
+        assert!(created > 0);
+
+        serving.shutdown().await.expect("failed to shutdown minfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn factoryfs_format_with_inodes_fails() {
+        let block_size = 512;
+        let ramdisk = ramdisk(block_size);
+        let factoryfs = new_fs(&ramdisk, Factoryfs::default());
+
+        let error = factoryfs
+            .format_with_options(FormatOptions { inodes: Some(64), ..Default::default() })
+            .await
+            .expect_err("format succeeded when it shouldn't have");
+        assert!(matches!(
+            error.downcast_ref::<CommandError>(),
+            Some(CommandError::InodesNotSupported)
+        ));
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
     #[fuchsia::test]
     async fn blobfs_format_serve_write_query_restart_read_shutdown() {
         let block_size = 512;
@@ -601,6 +1135,66 @@ mod tests {
         ramdisk.destroy().expect("failed to destroy ramdisk");
     }
 
+    #[fuchsia::test]
+    async fn minfs_fsck_with_options_clean() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let result = minfs.fsck_with_options(FsckOptions::default()).await.expect("fsck failed");
+        assert!(!result.errors_found, "unexpected errors: {}", result.log);
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_fsck_with_options_reports_errors() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        // Leave a partially written image behind by failing the writes midway through formatting.
+        ramdisk
+            .set_error_injection(ErrorInjection::FailAfter { blocks: 4 })
+            .expect("failed to inject errors");
+        minfs.format().await.expect_err("format succeeded with error injection enabled");
+        ramdisk.set_error_injection(ErrorInjection::Disabled).expect("failed to clear errors");
+
+        let result = minfs.fsck_with_options(FsckOptions::default()).await.expect("fsck failed");
+        assert!(result.errors_found);
+        assert!(!result.log.is_empty());
+        let error = minfs.fsck().await.expect_err("fsck succeeded when it shouldn't have");
+        assert!(matches!(
+            error.downcast_ref::<CommandError>(),
+            Some(CommandError::ProcessNonZeroReturnCodeWithOutput { .. })
+        ));
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_format_too_small_device_reports_output() {
+        ramdevice_client::wait_for_device(
+            "/dev/sys/platform/00:00:2d/ramctl",
+            std::time::Duration::from_secs(60),
+        )
+        .unwrap();
+        // Far too small to hold the minfs metadata.
+        let ramdisk = RamdiskClient::create(512, 4).unwrap();
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        let error = minfs.format().await.expect_err("format succeeded on a tiny device");
+        let output = match error.downcast_ref::<CommandError>() {
+            Some(CommandError::ProcessNonZeroReturnCodeWithOutput { output, .. }) => output.clone(),
+            _ => panic!("unexpected error: {:?}", error),
+        };
+        assert!(!output.is_empty());
+        assert!(error.to_string().contains(&output));
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
     #[fuchsia::test]
     async fn minfs_format_serve_write_query_restart_read_shutdown() {
         let block_size = 8192;
@@ -668,6 +1262,105 @@ mod tests {
         ramdisk.destroy().expect("failed to destroy ramdisk");
     }
 
+    #[fuchsia::test]
+    async fn minfs_shutdown_or_kill_kills_after_timeout() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let mut serving = minfs.serve().await.expect("failed to serve minfs");
+
+        // Replace the exposed directory with one that never answers, so the shutdown request hangs
+        // the same way it would for a filesystem that is slow to unmount.
+        let (exposed_dir, _exposed_dir_server) =
+            fidl::endpoints::create_proxy::<fio::DirectoryMarker>().unwrap();
+        serving.exposed_dir = exposed_dir;
+        let process = serving
+            .process
+            .as_ref()
+            .unwrap()
+            .duplicate_handle(fuchsia_zircon::Rights::SAME_RIGHTS)
+            .unwrap();
+
+        let timeout = Duration::from_millis(100);
+        let start = fasync::Time::now();
+        serving.shutdown_or_kill(timeout).await.expect("failed to kill minfs");
+        assert!(fasync::Time::now() - start >= timeout);
+
+        process
+            .wait_handle(Signals::PROCESS_TERMINATED, fuchsia_zircon::Time::INFINITE_PAST)
+            .expect("minfs process was not terminated");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_serve_readonly() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let serving = minfs
+            .serve_with_options(ServeOptions { readonly: true, ..ServeOptions::default() })
+            .await
+            .expect("failed to serve minfs read-only");
+        serving.bind_to_path("/test-minfs-readonly-path").expect("bind_to_path failed");
+
+        let error = std::fs::File::create("/test-minfs-readonly-path/test_file")
+            .expect_err("created a file on a read-only filesystem");
+        // ZX_ERR_ACCESS_DENIED is reported as EACCES.
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+
+        serving.shutdown().await.expect("failed to shutdown minfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_serve_with_migrate_root_fails() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let (_migrate_root, server_end) =
+            fidl::endpoints::create_endpoints::<fio::DirectoryMarker>().unwrap();
+        let error = minfs
+            .serve_with_options(ServeOptions {
+                migrate_root: Some(server_end),
+                ..ServeOptions::default()
+            })
+            .await
+            .expect_err("serve succeeded when it shouldn't have");
+        assert!(matches!(
+            error.downcast_ref::<ServeError>(),
+            Some(ServeError::MigrateRootNotSupported)
+        ));
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_readonly_config_serves_readonly() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs { readonly: true, ..Minfs::default() });
+
+        minfs.format().await.expect("failed to format minfs");
+        let serving = minfs.serve().await.expect("failed to serve minfs");
+        serving.bind_to_path("/test-minfs-readonly-config-path").expect("bind_to_path failed");
+
+        let error = std::fs::File::create("/test-minfs-readonly-config-path/test_file")
+            .expect_err("created a file on a read-only filesystem");
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+
+        serving.shutdown().await.expect("failed to shutdown minfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
     #[fuchsia::test]
     async fn minfs_bind_to_path() {
         let block_size = 8192;
@@ -697,6 +1390,114 @@ mod tests {
         std::fs::File::open(test_path).expect_err("test file was not unbound");
     }
 
+    #[fuchsia::test]
+    async fn minfs_query() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let serving = minfs.serve().await.expect("failed to serve minfs");
+
+        let fresh = serving.query().await.expect("failed to query fresh filesystem");
+        assert_eq!(fresh.name, "minfs");
+        assert_eq!(fresh.fs_type, crate::vfs_type::MINFS);
+        assert!(fresh.used_bytes <= fresh.total_bytes);
+        assert!(fresh.used_nodes <= fresh.total_nodes);
+
+        {
+            let test_file = fuchsia_fs::directory::open_file(
+                serving.root(),
+                "test_file",
+                fio::OpenFlags::CREATE | fio::OpenFlags::RIGHT_WRITABLE,
+            )
+            .await
+            .expect("failed to create test file");
+            let _: u64 = test_file
+                .write(b"test content")
+                .await
+                .expect("failed to write to test file")
+                .map_err(Status::from_raw)
+                .expect("write error");
+        }
+
+        let written = serving.query().await.expect("failed to query filesystem after write");
+        assert!(
+            written.used_bytes > fresh.used_bytes,
+            "used_bytes did not grow: {} -> {}",
+            fresh.used_bytes,
+            written.used_bytes
+        );
+        assert_eq!(written.used_nodes, fresh.used_nodes + 1);
+        assert_eq!(written.name, fresh.name);
+
+        serving.shutdown().await.expect("failed to shutdown minfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_bind_to_path_taken() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let serving = minfs.serve().await.expect("failed to serve minfs");
+        serving.bind_to_path("/test-mnt").expect("bind_to_path failed");
+        std::fs::create_dir("/test-mnt/dir").expect("failed to create directory");
+        std::fs::File::create("/test-mnt/file").expect("failed to create file");
+
+        let mut names = std::fs::read_dir("/test-mnt")
+            .expect("failed to read directory")
+            .map(|entry| entry.expect("failed to read entry").file_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["dir", "file"]);
+
+        // The path is taken now.
+        assert!(matches!(serving.bind_to_path("/test-mnt"), Err(BindError::Bind(_))));
+
+        drop(serving);
+        std::fs::read_dir("/test-mnt").expect_err("filesystem was not unbound");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
+    #[fuchsia::test]
+    async fn minfs_unbind() {
+        let block_size = 8192;
+        let ramdisk = ramdisk(block_size);
+        let minfs = new_fs(&ramdisk, Minfs::default());
+
+        minfs.format().await.expect("failed to format minfs");
+        let serving = minfs.serve().await.expect("failed to serve minfs");
+        serving.bind_to_path("/test-minfs-unbind").expect("bind_to_path failed");
+        std::fs::File::create("/test-minfs-unbind/file").expect("failed to create file");
+
+        serving.unbind("/test-minfs-unbind").expect("unbind failed");
+        std::fs::read_dir("/test-minfs-unbind").expect_err("path is still bound");
+        assert!(matches!(
+            serving.unbind("/test-minfs-unbind"),
+            Err(BindError::Unbind(Status::NOT_FOUND))
+        ));
+
+        // The filesystem is still serving, through the root directory and at a new path.
+        serving.query().await.expect("failed to query filesystem");
+        let (status, _) = serving
+            .root()
+            .get_attr()
+            .await
+            .expect("failed to get attributes of the root directory");
+        Status::ok(status).expect("get_attr failed");
+        serving.bind_to_path("/test-minfs-rebind").expect("failed to bind again");
+        std::fs::File::open("/test-minfs-rebind/file").expect("failed to open file");
+
+        serving.shutdown().await.expect("failed to shutdown minfs");
+
+        ramdisk.destroy().expect("failed to destroy ramdisk");
+    }
+
     #[fuchsia::test]
     async fn factoryfs_custom_config() {
         let block_size = 512;
@@ -22,6 +22,31 @@ pub enum CommandError {
     /// The filesystem process exited with a non-zero return code.
     #[error("process exited with non-zero return code: {0}")]
     ProcessNonZeroReturnCode(i64),
+    /// The filesystem process exited with a non-zero return code, same as
+    /// [`CommandError::ProcessNonZeroReturnCode`].  Returned by the commands that capture the
+    /// output of the process, with `output` holding the last lines it wrote to stdout and stderr.
+    #[error("process exited with non-zero return code: {return_code}\n{output}")]
+    ProcessNonZeroReturnCodeWithOutput { return_code: i64, output: String },
+    /// The number of inodes was requested when formatting a filesystem that does not support it.
+    #[error("filesystem does not support selecting the number of inodes when formatting")]
+    InodesNotSupported,
+    /// Online verification of a serving filesystem failed.  `ZX_ERR_NOT_SUPPORTED` means the
+    /// filesystem does not support it, and `ZX_ERR_IO_DATA_INTEGRITY` that the filesystem is
+    /// inconsistent.
+    #[error("online verification failed: {0}")]
+    Verify(#[source] Status),
+}
+
+impl CommandError {
+    /// Returns the return code of the filesystem process, if the error is that the process exited
+    /// with a non-zero one.
+    pub fn return_code(&self) -> Option<i64> {
+        match self {
+            Self::ProcessNonZeroReturnCode(return_code)
+            | Self::ProcessNonZeroReturnCodeWithOutput { return_code, .. } => Some(*return_code),
+            _ => None,
+        }
+    }
 }
 
 /// The error type representing a failure to launch the filesystem process.
@@ -47,6 +72,12 @@ pub enum ServeError {
     /// There was a problem launching the filesystem process.
     #[error("failed to launch filesystem process: {0}")]
     LaunchProcess(#[from] LaunchProcessError),
+    /// The filesystem was asked to be served read-only, which it does not support.
+    #[error("filesystem does not support being served read-only")]
+    ReadOnlyNotSupported,
+    /// A root to migrate was given, but the filesystem is not running as a component.
+    #[error("filesystem does not support migrating its root")]
+    MigrateRootNotSupported,
 }
 
 /// The error type used by the bind operation of a serving filesystem.
@@ -61,6 +92,9 @@ pub enum BindError {
     /// An error occurred binding the root directory to a path in the local namespace.
     #[error("failed to bind path to local namespace: {0}")]
     Bind(#[source] Status),
+    /// An error occurred removing a binding of the root directory from the local namespace.
+    #[error("failed to unbind path from local namespace: {0}")]
+    Unbind(#[source] Status),
 }
 
 /// The error type used by the shutdown operation of a serving filesystem.
@@ -78,6 +112,12 @@ pub enum ShutdownError {
     /// Failed to get the return code of the terminated filesystem process.
     #[error("failed to get return code of process: {0}")]
     GetProcessReturnCode(#[source] Status),
+    /// The filesystem did not shut down in time, and there is no process that could be killed.
+    #[error("timed out waiting for the filesystem to shut down")]
+    TimedOut,
+    /// The filesystem did not shut down in time, and killing the process failed.
+    #[error("failed to kill filesystem process: {0}")]
+    Kill(#[from] KillError),
 }
 
 /// The error type used by the query operation of a serving filesystem.
@@ -189,17 +189,18 @@ impl FSInstance {
 }
 
 fn launch_process(
+    args: &[&CStr],
+    actions: Vec<SpawnAction<'_>>,
+) -> Result<zx::Process, LaunchProcessError> {
+    launch_process_with_options(args, actions, SpawnOptions::CLONE_ALL)
+}
+
+fn launch_process_with_options(
     args: &[&CStr],
     mut actions: Vec<SpawnAction<'_>>,
+    options: SpawnOptions,
 ) -> Result<zx::Process, LaunchProcessError> {
-    match spawn_etc(
-        &zx::Handle::invalid().into(),
-        SpawnOptions::CLONE_ALL,
-        args[0],
-        args,
-        None,
-        &mut actions,
-    ) {
+    match spawn_etc(&zx::Handle::invalid().into(), options, args[0], args, None, &mut actions) {
         Ok(process) => Ok(process),
         Err((status, message)) => Err(LaunchProcessError {
             args: args.iter().map(|&a| a.to_owned()).collect(),
@@ -267,6 +268,33 @@ pub trait FSConfig {
         vec![]
     }
 
+    /// Arguments passed to the binary, in addition to `mount_args`, for mounting the filesystem
+    /// read-only.  Returns `None` if the filesystem can not be mounted read-only.
+    fn readonly_mount_args(&self) -> Option<Vec<&CStr>> {
+        None
+    }
+
+    /// Returns true if the configuration itself asks for the filesystem to be mounted read-only,
+    /// in which case `mount_args` already include `readonly_mount_args`.
+    fn readonly(&self) -> bool {
+        false
+    }
+
+    /// Returns true if the filesystem lets the number of inodes be selected when formatting.  The
+    /// binary then accepts `--num_inodes`, in addition to `format_args`, and a filesystem running
+    /// as a component honors `num_inodes` in its `fuchsia.fs.startup.FormatOptions`.
+    fn supports_format_inodes(&self) -> bool {
+        false
+    }
+
+    /// Returns true if the serving filesystem can check its consistency while mounted, through
+    /// the `fuchsia.update.verify.BlobfsVerifier` protocol.  See [`ServingFilesystem::verify()`].
+    ///
+    /// [`ServingFilesystem::verify()`]: asynchronous::ServingFilesystem::verify
+    fn supports_online_verify(&self) -> bool {
+        false
+    }
+
     /// Returns a handle for the crypt service (if any).
     fn crypt_client(&self) -> Option<zx::Channel> {
         // By default, filesystems don't need a crypt service.
@@ -507,6 +535,18 @@ impl FSConfig for Blobfs {
         }
         args
     }
+    fn readonly_mount_args(&self) -> Option<Vec<&CStr>> {
+        Some(vec![cstr!("--readonly")])
+    }
+    fn readonly(&self) -> bool {
+        self.readonly
+    }
+    fn supports_format_inodes(&self) -> bool {
+        true
+    }
+    fn supports_online_verify(&self) -> bool {
+        true
+    }
 }
 
 /// Minfs Filesystem Configuration
@@ -554,6 +594,15 @@ impl FSConfig for Minfs {
         }
         args
     }
+    fn readonly_mount_args(&self) -> Option<Vec<&CStr>> {
+        Some(vec![cstr!("--readonly")])
+    }
+    fn readonly(&self) -> bool {
+        self.readonly
+    }
+    fn supports_format_inodes(&self) -> bool {
+        true
+    }
 }
 
 type CryptClientFn = Arc<dyn Fn() -> zx::Channel + Send + Sync>;
@@ -598,6 +647,9 @@ impl FSConfig for Fxfs {
     fn crypt_client(&self) -> Option<zx::Channel> {
         Some((self.crypt_client_fn)())
     }
+    fn readonly(&self) -> bool {
+        self.readonly
+    }
 }
 
 /// Factoryfs Filesystem Configuration
@@ -625,6 +677,10 @@ impl FSConfig for Factoryfs {
     fn binary_path(&self) -> &CStr {
         cstr!("/pkg/bin/factoryfs")
     }
+    fn readonly_mount_args(&self) -> Option<Vec<&CStr>> {
+        // Factoryfs is always read-only.
+        Some(vec![])
+    }
     fn generic_args(&self) -> Vec<&CStr> {
         let mut args = vec![];
         if self.verbose {
@@ -0,0 +1,57 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+#include "src/lib/storage/ramdevice_client/c/fvm.h"
+
+#include <fuchsia/hardware/block/c/fidl.h>
+#include <lib/fdio/cpp/caller.h>
+#include <string.h>
+
+#include <memory>
+
+#include "src/lib/storage/block_client/cpp/remote_block_device.h"
+#include "src/storage/fvm/format.h"
+#include "src/storage/fvm/fvm.h"
+
+zx_status_t ramdisk_fvm_init(int fd, size_t slice_size) {
+  fuchsia_hardware_block_BlockInfo block_info;
+  fdio_cpp::UnownedFdioCaller disk_connection(fd);
+  zx_status_t status;
+  zx_status_t io_status =
+      fuchsia_hardware_block_BlockGetInfo(disk_connection.borrow_channel(), &status, &block_info);
+  if (io_status != ZX_OK) {
+    return io_status;
+  }
+  if (status != ZX_OK) {
+    return status;
+  }
+  if (slice_size == 0 || slice_size % block_info.block_size != 0 ||
+      slice_size % fvm::kBlockSize != 0) {
+    return ZX_ERR_INVALID_ARGS;
+  }
+
+  uint64_t volume_size = block_info.block_count * block_info.block_size;
+  fvm::Header header =
+      fvm::Header::FromDiskSize(fvm::kMaxUsablePartitions, volume_size, slice_size);
+  if (header.pslice_count == 0) {
+    return ZX_ERR_NO_SPACE;
+  }
+
+  // Both copies of the metadata are written, so no copy left over from a previous FVM volume on
+  // the device is picked up.
+  size_t metadata_allocated_bytes = header.GetMetadataAllocatedBytes();
+  std::unique_ptr<uint8_t[]> metadata(new uint8_t[metadata_allocated_bytes]);
+  memset(metadata.get(), 0, metadata_allocated_bytes);
+  memcpy(metadata.get(), &header, sizeof(fvm::Header));
+  fvm::UpdateHash(metadata.get(), header.GetMetadataUsedBytes());
+
+  for (auto type : {fvm::SuperblockType::kPrimary, fvm::SuperblockType::kSecondary}) {
+    if (zx_status_t write_status = block_client::SingleWriteBytes(
+            fd, metadata.get(), metadata_allocated_bytes, header.GetSuperblockOffset(type));
+        write_status != ZX_OK) {
+      return write_status;
+    }
+  }
+  return ZX_OK;
+}
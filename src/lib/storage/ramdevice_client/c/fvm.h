@@ -0,0 +1,18 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+#ifndef SRC_LIB_STORAGE_RAMDEVICE_CLIENT_C_FVM_H_
+#define SRC_LIB_STORAGE_RAMDEVICE_CLIENT_C_FVM_H_
+
+#include <stddef.h>
+#include <zircon/types.h>
+
+extern "C" {
+
+// Writes empty FVM metadata, with slices of |slice_size| bytes, to the block device |fd| refers
+// to.  The volume covers the whole device.  Any data on the device is lost.
+zx_status_t ramdisk_fvm_init(int fd, size_t slice_size);
+}
+
+#endif  // SRC_LIB_STORAGE_RAMDEVICE_CLIENT_C_FVM_H_
@@ -42,7 +42,9 @@ mod ramdevice_sys;
 
 use {
     anyhow::Error,
-    fdio, fuchsia_zircon as zx,
+    fdio, fidl_fuchsia_device as fdevice, fidl_fuchsia_hardware_block as fblock,
+    fidl_fuchsia_hardware_block_partition as fpartition,
+    fidl_fuchsia_hardware_block_volume as fvolume, fuchsia_zircon as zx,
     std::{
         ffi, fs,
         os::unix::io::{AsRawFd, RawFd},
@@ -50,6 +52,17 @@ use {
     },
     zx::HandleBased,
 };
+
+#[cfg(feature = "async")]
+use {
+    anyhow::format_err,
+    fuchsia_async::{self as fasync, TimeoutExt},
+    fuchsia_vfs_watcher::{WatchEvent, Watcher},
+    futures::{
+        future::{BoxFuture, FutureExt},
+        stream::TryStreamExt,
+    },
+};
 enum DevRoot {
     Provided(fs::File),
     Isolated,
@@ -160,12 +173,13 @@ pub struct RamdiskClientBuilder {
     block_count: u64,
     dev_root: Option<DevRoot>,
     guid: Option<[u8; 16]>,
+    type_guid: Option<[u8; 16]>,
 }
 
 impl RamdiskClientBuilder {
     /// Create a new ramdisk builder with the given block_size and block_count.
     pub fn new(block_size: u64, block_count: u64) -> Self {
-        Self { block_size, block_count, dev_root: None, guid: None }
+        Self { block_size, block_count, dev_root: None, guid: None, type_guid: None }
     }
 
     /// Use the given directory as "/dev" instead of opening "/dev" from the environment.
@@ -183,18 +197,40 @@ impl RamdiskClientBuilder {
     }
 
     /// Initialize the ramdisk with the given GUID, which can be queried from the ramdisk instance.
+    ///
+    /// The ramdisk driver only stores a single GUID, which it reports as the partition type GUID,
+    /// and does not support an instance GUID.  So this GUID is the one returned by
+    /// [`RamdiskClient::get_type_guid`], and [`RamdiskClient::get_instance_guid`] fails with
+    /// `ZX_ERR_NOT_SUPPORTED`.  If [`RamdiskClientBuilder::type_guid`] is set as well, both must
+    /// be equal, or [`RamdiskClientBuilder::build`] fails with `ZX_ERR_INVALID_ARGS`.
     pub fn guid(&mut self, guid: [u8; 16]) -> &mut Self {
         self.guid = Some(guid);
         self
     }
 
+    /// Initialize the ramdisk with the given partition type GUID, which can be queried with
+    /// [`RamdiskClient::get_type_guid`].  See [`RamdiskClientBuilder::guid`] for how this relates
+    /// to the instance GUID.
+    pub fn type_guid(&mut self, type_guid: [u8; 16]) -> &mut Self {
+        self.type_guid = Some(type_guid);
+        self
+    }
+
     /// Create the ramdisk.
     pub fn build(&mut self) -> Result<RamdiskClient, zx::Status> {
         let block_size = self.block_size;
         let block_count = self.block_count;
 
+        // The driver has a single GUID, used as the type GUID.  See `guid`.
+        let guid = match (self.guid, self.type_guid) {
+            (Some(guid), Some(type_guid)) if guid != type_guid => {
+                return Err(zx::Status::INVALID_ARGS)
+            }
+            (guid, type_guid) => type_guid.or(guid),
+        };
+
         let mut ramdisk: *mut ramdevice_sys::ramdisk_client_t = ptr::null_mut();
-        let status = match (&self.dev_root, &self.guid) {
+        let status = match (&self.dev_root, &guid) {
             (Some(dev_root), Some(guid)) => {
                 // If this statement needs to open the dev_root itself, hold onto the File to
                 // ensure dev_root_fd is valid for this block.
@@ -263,6 +299,31 @@ impl RamdiskClientBuilder {
     }
 }
 
+/// Describes which requests a ramdisk should fail. See [`RamdiskClient::set_error_injection`].
+///
+/// Error injection is implemented using the sleep mode of the ramdisk driver. The driver decides
+/// which requests fail by counting written blocks, and fails them with
+/// [`ErrorInjection::STATUS`], so neither a failure probability nor a different status can be
+/// configured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorInjection {
+    /// All requests are processed normally.
+    Disabled,
+    /// Every request fails.
+    FailAll,
+    /// Requests are processed normally until `blocks` more blocks have been written, after which
+    /// every request fails.
+    FailAfter {
+        /// The number of blocks that can still be written before requests start failing.
+        blocks: u64,
+    },
+}
+
+impl ErrorInjection {
+    /// The status that requests failed by error injection complete with.
+    pub const STATUS: zx::Status = zx::Status::UNAVAILABLE;
+}
+
 /// A client for managing a ramdisk. This can be created with the [`RamdiskClient::create`]
 /// function or through the type returned by [`RamdiskClient::builder`] to specify additional
 /// options.
@@ -294,6 +355,34 @@ impl RamdiskClient {
         }
     }
 
+    /// Get the topological path of the associated ramdisk, as reported by the device manager.
+    /// Unlike [`RamdiskClient::get_path`], the returned path is absolute and does not depend on the
+    /// dev_root the ramdisk was created with, so it can be handed to other components.
+    pub fn get_topological_path(&self) -> Result<String, zx::Status> {
+        let controller = fdevice::ControllerSynchronousProxy::new(self.open()?);
+        controller
+            .get_topological_path(zx::Time::INFINITE)
+            .map_err(fidl_error_status)?
+            .map_err(zx::Status::from_raw)
+    }
+
+    /// Get the partition type GUID of the associated ramdisk.  Fails with `ZX_ERR_NOT_SUPPORTED` if
+    /// the ramdisk was created without a GUID.
+    pub fn get_type_guid(&self) -> Result<[u8; 16], zx::Status> {
+        let partition = fpartition::PartitionSynchronousProxy::new(self.open()?);
+        let result = partition.get_type_guid(zx::Time::INFINITE);
+        partition_guid(result)
+    }
+
+    /// Get the partition instance GUID of the associated ramdisk.  The ramdisk driver does not
+    /// support instance GUIDs, so this currently always fails with `ZX_ERR_NOT_SUPPORTED`.  See
+    /// [`RamdiskClientBuilder::guid`].
+    pub fn get_instance_guid(&self) -> Result<[u8; 16], zx::Status> {
+        let partition = fpartition::PartitionSynchronousProxy::new(self.open()?);
+        let result = partition.get_instance_guid(zx::Time::INFINITE);
+        partition_guid(result)
+    }
+
     /// Get an open channel to the underlying ramdevice.
     pub fn open(&self) -> Result<zx::Channel, zx::Status> {
         struct UnownedFd(RawFd);
@@ -310,6 +399,164 @@ impl RamdiskClient {
         Ok(client_chan)
     }
 
+    /// Get an open connection to the underlying ramdevice, as a `fuchsia.hardware.block/Block`
+    /// proxy.  Same as [`RamdiskClient::open`], but the channel is already wrapped in the proxy, so
+    /// this has to be called from within an async executor.
+    pub fn open_block(&self) -> Result<fblock::BlockProxy, zx::Status> {
+        let client_end = fidl::endpoints::ClientEnd::<fblock::BlockMarker>::new(self.open()?);
+        client_end.into_proxy().map_err(fidl_error_status)
+    }
+
+    /// Send a block FLUSH request to the ramdisk through a new block FIFO and wait for it to
+    /// complete, returning the status the ramdisk reported for the request.
+    pub fn flush(&self) -> Result<(), zx::Status> {
+        let block = fblock::BlockSynchronousProxy::new(self.open()?);
+        let (status, fifo) = block.get_fifo(zx::Time::INFINITE).map_err(fidl_error_status)?;
+        zx::Status::ok(status)?;
+        let fifo = fifo.ok_or(zx::Status::INTERNAL)?;
+
+        let result = flush_fifo(&fifo);
+        let close_status = block.close_fifo(zx::Time::INFINITE).map_err(fidl_error_status)?;
+        result?;
+        zx::Status::ok(close_status)
+    }
+
+    /// Write `data` to the ramdisk, starting at byte `offset`.  The data is copied into a VMO,
+    /// which the ramdisk reads in chunks of up to [`WRITE_IMAGE_CHUNK_SIZE`] bytes, so this is much
+    /// faster than writing the data block by block.
+    ///
+    /// Fails with `ZX_ERR_INVALID_ARGS` if `offset` or the length of `data` is not a multiple of
+    /// the block size of the ramdisk, and with `ZX_ERR_OUT_OF_RANGE` if `data` does not fit.
+    pub fn write_image(&self, offset: u64, data: &[u8]) -> Result<(), zx::Status> {
+        let block = fblock::BlockSynchronousProxy::new(self.open()?);
+        let (status, info) = block.get_info(zx::Time::INFINITE).map_err(fidl_error_status)?;
+        zx::Status::ok(status)?;
+        let info = info.ok_or(zx::Status::INTERNAL)?;
+
+        let block_size = info.block_size as u64;
+        let length = data.len() as u64;
+        if offset % block_size != 0 || length % block_size != 0 {
+            return Err(zx::Status::INVALID_ARGS);
+        }
+        match offset.checked_add(length) {
+            Some(end) if end <= info.block_count * block_size => {}
+            _ => return Err(zx::Status::OUT_OF_RANGE),
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // Chunks must be a whole number of blocks, and no larger than what the device accepts in
+        // a single request.
+        let mut chunk_size = WRITE_IMAGE_CHUNK_SIZE;
+        if info.max_transfer_size != fblock::MAX_TRANSFER_UNBOUNDED {
+            chunk_size = std::cmp::min(chunk_size, info.max_transfer_size as u64);
+        }
+        let chunk_size = std::cmp::max(chunk_size - chunk_size % block_size, block_size);
+
+        let vmo = zx::Vmo::create(length)?;
+        vmo.write(data, 0)?;
+        let mut written = 0;
+        while written < length {
+            let chunk = std::cmp::min(chunk_size, length - written);
+            let status = block
+                .write_blocks(
+                    vmo.duplicate_handle(zx::Rights::SAME_RIGHTS)?,
+                    chunk,
+                    offset + written,
+                    written,
+                    zx::Time::INFINITE,
+                )
+                .map_err(fidl_error_status)?;
+            zx::Status::ok(status)?;
+            written += chunk;
+        }
+        Ok(())
+    }
+
+    /// Configure the ramdisk to fail requests as described by `config`. Replaces any previous
+    /// configuration, so passing [`ErrorInjection::Disabled`] makes the ramdisk process requests
+    /// normally again.
+    pub fn set_error_injection(&self, config: ErrorInjection) -> Result<(), zx::Status> {
+        // Safe because self.ramdisk is valid.
+        let status = unsafe {
+            match config {
+                ErrorInjection::Disabled => ramdevice_sys::ramdisk_wake(self.ramdisk),
+                ErrorInjection::FailAll => ramdevice_sys::ramdisk_sleep_after(self.ramdisk, 0),
+                ErrorInjection::FailAfter { blocks } => {
+                    ramdevice_sys::ramdisk_sleep_after(self.ramdisk, blocks)
+                }
+            }
+        };
+        zx::Status::ok(status)
+    }
+
+    /// Format the ramdisk as an FVM volume with slices of `slice_size` bytes, and allocate a
+    /// partition for each of `partitions`, in order.  Each partition gets a single slice and the
+    /// [`FVM_PARTITION_TYPE_GUID`] type GUID.
+    ///
+    /// Any data on the ramdisk is lost.  Blocks until the block device of every partition appears,
+    /// or fails with `ZX_ERR_TIMED_OUT` if a device takes longer than `timeout` to appear.
+    pub fn init_fvm(
+        &self,
+        slice_size: usize,
+        partitions: &[&str],
+        timeout: std::time::Duration,
+    ) -> Result<Vec<FvmPartition>, Error> {
+        // Safe because self.ramdisk is valid and the borrowed fd is not borrowed beyond this
+        // statement.
+        zx::Status::ok(unsafe {
+            ramdevice_sys::ramdisk_fvm_init(
+                ramdevice_sys::ramdisk_get_block_fd(self.ramdisk),
+                slice_size,
+            )
+        })?;
+
+        let controller = fdevice::ControllerSynchronousProxy::new(self.open()?);
+        controller
+            .bind(FVM_DRIVER_PATH, zx::Time::INFINITE)
+            .map_err(fidl_error_status)?
+            .map_err(zx::Status::from_raw)?;
+
+        let block = fdio::create_fd(self.open()?.into())?;
+        wait_for_device_at(&block, "fvm", timeout)?;
+        let (volume_manager, server_end) = zx::Channel::create()?;
+        fdio::service_connect_at(&fdio::clone_channel(&block)?, "fvm", server_end)?;
+        let volume_manager = fvolume::VolumeManagerSynchronousProxy::new(volume_manager);
+
+        let mut result = Vec::with_capacity(partitions.len());
+        for (index, name) in partitions.iter().enumerate() {
+            let mut type_guid = fpartition::Guid { value: FVM_PARTITION_TYPE_GUID };
+            // Instance GUIDs only need to be unique within the volume, so the partition index is
+            // enough.
+            let mut instance_guid = fpartition::Guid { value: [0; 16] };
+            instance_guid.value[..8].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+            let status = volume_manager
+                .allocate_partition(
+                    1,
+                    &mut type_guid,
+                    &mut instance_guid,
+                    name,
+                    0,
+                    zx::Time::INFINITE,
+                )
+                .map_err(fidl_error_status)?;
+            zx::Status::ok(status)?;
+
+            // The FVM driver names partitions after their index in the volume, starting at 1.
+            let relative_path = format!("fvm/{}-p-{}/block", name, index + 1);
+            wait_for_device_at(&block, &relative_path, timeout)?;
+            let (device, server_end) = zx::Channel::create()?;
+            fdio::service_connect_at(&fdio::clone_channel(&block)?, &relative_path, server_end)?;
+            result.push(FvmPartition {
+                name: name.to_string(),
+                path: format!("{}/{}", self.get_path(), relative_path),
+                device: fdio::create_fd(device.into())?,
+            });
+        }
+        Ok(result)
+    }
+
     /// Remove the underlying ramdisk. This deallocates all resources for this ramdisk, which will
     /// remove all data written to the associated ramdisk.
     pub fn destroy(self) -> Result<(), zx::Status> {
@@ -320,13 +567,53 @@ impl RamdiskClient {
     }
 }
 
+/// The type GUID of the partitions allocated by [`RamdiskClient::init_fvm`].
+pub const FVM_PARTITION_TYPE_GUID: [u8; 16] = [
+    0x66, 0x73, 0x2d, 0x74, 0x65, 0x73, 0x74, 0x2d, 0x70, 0x61, 0x72, 0x74, 0x69, 0x74, 0x69, 0x6f,
+];
+
+const FVM_DRIVER_PATH: &str = "fvm.so";
+
+/// The largest number of bytes [`RamdiskClient::write_image`] writes with a single request.
+pub const WRITE_IMAGE_CHUNK_SIZE: u64 = 256 * 1024;
+
+/// A partition of an FVM volume on a ramdisk, created by [`RamdiskClient::init_fvm`].  The
+/// partition is removed together with the ramdisk.
+pub struct FvmPartition {
+    name: String,
+    path: String,
+    device: fs::File,
+}
+
+impl FvmPartition {
+    /// Get the name the partition was allocated with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the device path of the block device of the partition.  Same as
+    /// [`RamdiskClient::get_path`], the path is relative to the dev_root of the ramdisk.
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get an open channel to the block device of the partition.
+    pub fn open(&self) -> Result<zx::Channel, zx::Status> {
+        fdio::clone_channel(&self.device)
+    }
+}
+
 /// This struct has exclusive ownership of the ramdisk pointer.
 /// It is safe to move this struct between threads.
 unsafe impl Send for RamdiskClient {}
 
 /// The only methods that can be invoked from a reference of RamdiskClient
-/// are open() and get_path(). Both these functions are non-destructive and
-/// can be called from multiple threads.
+/// are open(), open_block(), get_path(), get_topological_path(), get_type_guid(),
+/// get_instance_guid(), flush(), write_image(), set_error_injection() and init_fvm(). Apart from
+/// write_image() and init_fvm(), these functions are non-destructive and can be called from
+/// multiple threads. write_image() and init_fvm() overwrite the contents of the ramdisk, but they
+/// only read the ramdisk pointer and write to the device through its block protocol, so they are
+/// as safe to call concurrently as writing to the device from several clients.
 /// This implies that it is safe to share a reference to RamdiskClient between threads.
 unsafe impl Sync for RamdiskClient {}
 
@@ -336,6 +623,65 @@ impl Drop for RamdiskClient {
     }
 }
 
+// See fuchsia.hardware.block/block.fidl
+const BLOCKIO_FLUSH: u32 = 3;
+
+#[repr(C)]
+#[derive(Default)]
+struct BlockFifoRequest {
+    op_code: u32,
+    request_id: u32,
+    group_id: u16,
+    vmoid: u16,
+    block_count: u32,
+    vmo_block: u64,
+    device_block: u64,
+    trace_flow_id: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct BlockFifoResponse {
+    status: i32,
+    request_id: u32,
+    group_id: u16,
+    reserved1: u16,
+    count: u32,
+    reserved2: u64,
+    reserved3: u64,
+    reserved4: u64,
+}
+
+fn flush_fifo(fifo: &zx::Fifo) -> Result<(), zx::Status> {
+    let request = BlockFifoRequest { op_code: BLOCKIO_FLUSH, ..Default::default() };
+    fifo.write(std::slice::from_ref(&request))?;
+
+    fifo.wait_handle(
+        zx::Signals::FIFO_READABLE | zx::Signals::FIFO_PEER_CLOSED,
+        zx::Time::INFINITE,
+    )?;
+    let mut response = BlockFifoResponse::default();
+    // Safe because BlockFifoResponse is plain old data that matches the layout of the responses
+    // the block device writes to the FIFO.
+    unsafe { fifo.read(std::slice::from_mut(&mut response))? };
+    zx::Status::ok(response.status)
+}
+
+fn fidl_error_status(error: fidl::Error) -> zx::Status {
+    match error {
+        fidl::Error::ClientChannelClosed { status, .. } => status,
+        _ => zx::Status::INTERNAL,
+    }
+}
+
+fn partition_guid(
+    result: Result<(i32, Option<Box<fpartition::Guid>>), fidl::Error>,
+) -> Result<[u8; 16], zx::Status> {
+    let (status, guid) = result.map_err(fidl_error_status)?;
+    zx::Status::ok(status)?;
+    guid.map(|guid| guid.value).ok_or(zx::Status::INTERNAL)
+}
+
 fn open_isolated_devmgr() -> Result<fs::File, zx::Status> {
     let (client_chan, server_chan) = zx::Channel::create()?;
     fdio::service_connect("/svc/fuchsia.test.IsolatedDevmgr", server_chan)?;
@@ -351,6 +697,54 @@ pub fn wait_for_device(path: &str, duration: std::time::Duration) -> Result<(),
     })?)
 }
 
+/// Wait until |deadline| for the device at |path| to appear.
+///
+/// Unlike [`wait_for_device`], this does not block the calling thread.  Instead, it watches each
+/// directory along |path| until the next component appears.  Dropping the returned future stops
+/// waiting.
+///
+/// Only available with the `async` feature.
+#[cfg(feature = "async")]
+pub async fn wait_for_device_async(path: &str, deadline: zx::Time) -> Result<(), Error> {
+    wait_for_path(path.to_string())
+        .on_timeout(fasync::Time::from_zx(deadline), || Err(zx::Status::TIMED_OUT.into()))
+        .await
+}
+
+#[cfg(feature = "async")]
+fn wait_for_path(path: String) -> BoxFuture<'static, Result<(), Error>> {
+    async move {
+        let (parent, name) = path.rsplit_once('/').ok_or(zx::Status::BAD_PATH)?;
+        if parent.is_empty() {
+            // Entries at the root of the namespace are bound, not added, so they can not be
+            // watched for.
+            return if std::path::Path::new(&path).exists() {
+                Ok(())
+            } else {
+                Err(zx::Status::NOT_FOUND.into())
+            };
+        }
+
+        wait_for_path(parent.to_string()).await?;
+        if name.is_empty() {
+            return Ok(());
+        }
+
+        let dir =
+            fuchsia_fs::open_directory_in_namespace(parent, fuchsia_fs::OpenFlags::RIGHT_READABLE)?;
+        let mut watcher = Watcher::new(dir).await?;
+        while let Some(message) = watcher.try_next().await? {
+            if (message.event == WatchEvent::EXISTING || message.event == WatchEvent::ADD_FILE)
+                && message.filename.as_os_str() == name
+            {
+                return Ok(());
+            }
+        }
+        Err(format_err!("watcher for {} closed before {} appeared", parent, name))
+    }
+    .boxed()
+}
+
 /// Wait for no longer than |duration| for the device at |path| relative to |dirfd| to appear.
 pub fn wait_for_device_at(
     dirfd: &fs::File,
@@ -370,7 +764,16 @@ pub fn wait_for_device_at(
 #[cfg(test)]
 mod tests {
     use {
-        super::*, assert_matches::assert_matches, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        super::*,
+        assert_matches::assert_matches,
+        fidl::endpoints::create_endpoints,
+        fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        remote_block_device::{BlockClient as _, MutableBufferSlice, RemoteBlockClient},
+        vfs::{
+            directory::{entry::DirectoryEntry, helper::DirectlyMutable, mutable::simple},
+            execution_scope::ExecutionScope,
+            remote::remote_node,
+        },
     };
 
     // Note that if these tests flake, all downstream tests that depend on this crate may too.
@@ -380,6 +783,11 @@ mod tests {
         0x10,
     ];
 
+    const TEST_TYPE_GUID: [u8; 16] = [
+        0x10, 0x09, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0xff, 0xee, 0xdd, 0xcc, 0xbb,
+        0xaa,
+    ];
+
     const WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
     #[fasync::run_singlethreaded(test)]
@@ -418,6 +826,48 @@ mod tests {
         assert_eq!(ramdisk.destroy(), Ok(()));
     }
 
+    #[fasync::run_singlethreaded(test)]
+    async fn create_with_type_guid_get_guids_destroy() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let ramdisk = RamdiskClient::builder(512, 2048)
+            .guid(TEST_TYPE_GUID)
+            .type_guid(TEST_TYPE_GUID)
+            .build()
+            .expect("failed to create ramdisk");
+        assert_eq!(ramdisk.get_type_guid(), Ok(TEST_TYPE_GUID));
+        // The ramdisk driver only has a type GUID.
+        assert_eq!(ramdisk.get_instance_guid(), Err(zx::Status::NOT_SUPPORTED));
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn create_with_conflicting_guids_fails() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let result =
+            RamdiskClient::builder(512, 2048).guid(TEST_GUID).type_guid(TEST_TYPE_GUID).build();
+        assert_eq!(result.err(), Some(zx::Status::INVALID_ARGS));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn create_get_topological_path_destroy() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let ramdisk = RamdiskClient::create(512, 2048).unwrap();
+        let topological_path =
+            ramdisk.get_topological_path().expect("failed to get topological path");
+        assert!(!topological_path.is_empty());
+        assert!(topological_path.contains("ramdisk"), "{} is not a ramdisk", topological_path);
+        assert!(
+            topological_path.ends_with(ramdisk.get_path()),
+            "{} does not end with {}",
+            topological_path,
+            ramdisk.get_path()
+        );
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
     #[fasync::run_singlethreaded(test)]
     async fn create_open_destroy() {
         wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
@@ -427,6 +877,22 @@ mod tests {
         assert_eq!(ramdisk.destroy(), Ok(()));
     }
 
+    #[fasync::run_singlethreaded(test)]
+    async fn create_open_block_get_info_destroy() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let ramdisk = RamdiskClient::create(512, 2048).unwrap();
+        let block = ramdisk.open_block().expect("failed to open block proxy");
+
+        let (status, info) = block.get_info().await.expect("failed to send GetInfo");
+        assert_eq!(zx::Status::ok(status), Ok(()));
+        let info = info.expect("GetInfo returned no info");
+        assert_eq!(info.block_size, 512);
+        assert_eq!(info.block_count, 2048);
+
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
     #[fasync::run_singlethreaded(test)]
     async fn create_describe_destroy() {
         wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
@@ -443,4 +909,139 @@ mod tests {
 
         assert_eq!(ramdisk.destroy(), Ok(()));
     }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn create_flush_destroy() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let ramdisk = RamdiskClient::create(512, 2048).unwrap();
+        assert_eq!(ramdisk.flush(), Ok(()));
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn write_image_read_back() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let ramdisk = RamdiskClient::create(512, 4096).unwrap();
+        let image = (0..1 << 20).map(|i: u32| (i % 251) as u8).collect::<Vec<_>>();
+        let offset = 8 * 512;
+        ramdisk.write_image(offset, &image).expect("failed to write image");
+
+        let client = RemoteBlockClient::new(ramdisk.open().unwrap()).await.unwrap();
+        let mut buf = vec![0u8; 1024];
+        for position in [0, WRITE_IMAGE_CHUNK_SIZE - 512, image.len() as u64 - 1024] {
+            client
+                .read_at(MutableBufferSlice::Memory(&mut buf), offset + position)
+                .await
+                .expect("failed to read back");
+            let position = position as usize;
+            assert_eq!(buf, image[position..position + 1024]);
+        }
+
+        assert_eq!(ramdisk.write_image(1, &image[..512]), Err(zx::Status::INVALID_ARGS));
+        assert_eq!(ramdisk.write_image(0, &image[..100]), Err(zx::Status::INVALID_ARGS));
+        assert_eq!(ramdisk.write_image(4095 * 512, &image[..1024]), Err(zx::Status::OUT_OF_RANGE));
+
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn error_injection_fail_all_then_disabled() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let ramdisk = RamdiskClient::create(512, 2048).unwrap();
+        let client = RemoteBlockClient::new(ramdisk.open().unwrap()).await.unwrap();
+        let mut buf = vec![0u8; 512];
+
+        ramdisk.set_error_injection(ErrorInjection::FailAll).expect("failed to inject errors");
+        let err = client
+            .read_at(MutableBufferSlice::Memory(&mut buf), 0)
+            .await
+            .expect_err("read succeeded with error injection enabled");
+        assert_eq!(err.downcast_ref::<zx::Status>(), Some(&ErrorInjection::STATUS));
+
+        ramdisk.set_error_injection(ErrorInjection::Disabled).expect("failed to clear errors");
+        client
+            .read_at(MutableBufferSlice::Memory(&mut buf), 0)
+            .await
+            .expect("read failed with error injection disabled");
+
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn init_fvm_creates_partitions() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let ramdisk = RamdiskClient::create(512, 1 << 16).unwrap();
+        let partitions =
+            ramdisk.init_fvm(32 * 1024, &["data", "blob"], WAIT_TIMEOUT).expect("init_fvm failed");
+
+        assert_eq!(partitions.len(), 2);
+        for (partition, name) in partitions.iter().zip(["data", "blob"]) {
+            assert_eq!(partition.name(), name);
+            assert!(
+                partition.get_path().starts_with(ramdisk.get_path()),
+                "{} is not on {}",
+                partition.get_path(),
+                ramdisk.get_path()
+            );
+            assert!(std::path::Path::new(&format!("/dev/{}", partition.get_path())).exists());
+
+            let device = fasync::Channel::from_channel(partition.open().unwrap()).unwrap();
+            let proxy = fio::NodeProxy::new(device);
+            let info = proxy.describe().await.expect("failed to get node info");
+            assert_matches!(info, fio::NodeInfo::Device(_));
+        }
+        assert_ne!(partitions[0].get_path(), partitions[1].get_path());
+
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn wait_for_device_async_resolves_when_device_appears() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+
+        // Serve a directory in the namespace, so the test controls when the device appears.
+        let root = simple();
+        let (client_end, server_end) = create_endpoints::<fio::DirectoryMarker>().unwrap();
+        root.clone().open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            0,
+            vfs::path::Path::dot(),
+            server_end.into_channel().into(),
+        );
+        let namespace = fdio::Namespace::installed().unwrap();
+        namespace.bind("/wait-for-device-async", client_end.into_channel()).unwrap();
+
+        let wait = wait_for_device_async(
+            "/wait-for-device-async/ramdisk",
+            zx::Time::after(zx::Duration::from_seconds(10)),
+        );
+        futures::pin_mut!(wait);
+        assert!(futures::poll!(&mut wait).is_pending());
+
+        let ramdisk = RamdiskClient::create(512, 2048).unwrap();
+        let device = fasync::Channel::from_channel(ramdisk.open().unwrap()).unwrap();
+        root.add_entry("ramdisk", remote_node(fio::NodeProxy::new(device))).unwrap();
+        wait.await.expect("device did not appear");
+
+        namespace.unbind("/wait-for-device-async").unwrap();
+        assert_eq!(ramdisk.destroy(), Ok(()));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn wait_for_device_async_times_out() {
+        wait_for_device("/dev/sys/platform/00:00:2d/ramctl", WAIT_TIMEOUT)
+            .expect("ramctl did not appear");
+        let result = wait_for_device_async(
+            "/dev/sys/platform/00:00:2d/ramctl/does-not-exist",
+            zx::Time::after(zx::Duration::from_millis(100)),
+        )
+        .await;
+        assert_matches!(result.unwrap_err().downcast::<zx::Status>(), Ok(zx::Status::TIMED_OUT));
+    }
 }
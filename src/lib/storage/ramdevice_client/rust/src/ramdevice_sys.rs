@@ -61,6 +61,8 @@ extern "C" {
     ) -> zx_status_t;
     pub fn ramdisk_get_path(client: *const ramdisk_client_t) -> *const raw::c_char;
     pub fn ramdisk_get_block_fd(client: *const ramdisk_client_t) -> raw::c_int;
+    pub fn ramdisk_sleep_after(client: *const ramdisk_client_t, blk_count: u64) -> zx_status_t;
+    pub fn ramdisk_wake(client: *const ramdisk_client_t) -> zx_status_t;
     pub fn ramdisk_destroy(client: *const ramdisk_client_t) -> zx_status_t;
     pub fn wait_for_device(path: *const raw::c_char, timeout: u64) -> zx_status_t;
     pub fn wait_for_device_at(
@@ -69,3 +71,8 @@ extern "C" {
         timeout: u64,
     ) -> zx_status_t;
 }
+
+#[link(name = "fvm")]
+extern "C" {
+    pub fn ramdisk_fvm_init(fd: raw::c_int, slice_size: usize) -> zx_status_t;
+}
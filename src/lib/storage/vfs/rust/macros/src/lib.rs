@@ -117,11 +117,26 @@ fn pseudo_directory_impl(mutable: bool, input: TokenStream) -> TokenStream {
         }
     };
 
-    let entries = parsed.entries.into_iter().map(|DirectoryEntry { name, entry }| {
-        let location = format!("{:?}", Spanned::span(&name));
-        quote! {
-            #macro_mod::unwrap_add_entry_span(#name, #location,
-                                              #dir_var.clone().add_entry(#name, #entry));
+    let entries = parsed.entries.into_iter().map(|entry| match entry {
+        Entry::Named(DirectoryEntry { name, entry, .. }) => {
+            let location = format!("{:?}", Spanned::span(&name));
+            quote! {
+                #macro_mod::unwrap_add_entry_span(#name, #location,
+                                                  #dir_var.clone().add_entry(#name, #entry));
+            }
+        }
+        Entry::Splice(entries) => {
+            let location = format!("{:?}", Spanned::span(&entries));
+            // TODO(fxbug.dev/35905) Remove the underscores when span becomes Span::def_site().
+            let name = Ident::new("__name", span);
+            let entry = Ident::new("__entry", span);
+            quote! {
+                for (#name, #entry) in #entries {
+                    #macro_mod::unwrap_add_entry_span(&#name, #location,
+                                                      #dir_var.clone().add_entry(#name.clone(),
+                                                                                 #entry));
+                }
+            }
         }
     });
 
@@ -139,15 +154,38 @@ fn pseudo_directory_impl(mutable: bool, input: TokenStream) -> TokenStream {
     })
 }
 
+/// A single rule inside the `pseudo_directory!` macro.
+enum Entry {
+    /// `<name> => <entry>`
+    Named(DirectoryEntry),
+    /// `..<entries>`, where `entries` is an expression producing an `IntoIterator` of
+    /// `(String, Arc<dyn DirectoryEntry>)` pairs, that are all added to the directory.
+    Splice(Expr),
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        if input.peek(Token![..]) {
+            input.parse::<Token![..]>()?;
+            Ok(Entry::Splice(input.parse::<Expr>()?))
+        } else {
+            input.parse::<DirectoryEntry>().map(Entry::Named)
+        }
+    }
+}
+
 struct DirectoryEntry {
     name: proc_macro2::TokenStream,
+    /// When the entry name is a string or a byte string literal, holds the literal value and its
+    /// location.  Used to detect duplicate names at compile time.  Names given as expressions are
+    /// only checked at runtime.
+    literal: Option<(String, Span)>,
     entry: Expr,
 }
 
 impl Parse for DirectoryEntry {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
-        let mut seen = HashSet::new();
-        let mut check_literal = |name: String, span| {
+        let check_literal = |name: &str, span| {
             if name.len() as u64 > MAX_FILENAME {
                 let message = format!(
                     "Entry name is too long: '{}'\n\
@@ -159,31 +197,27 @@ impl Parse for DirectoryEntry {
                 );
                 return Err(syn::Error::new(span, message));
             }
-
-            if seen.insert(name.clone()) {
-                return Ok(());
-            }
-
-            let message = format!(
-                "Duplicate literal entry name '{}'. There is another literal \
-                 before this one with the same value.",
-                name
-            );
-            Err(syn::Error::new(span, message))
+            Ok(())
         };
 
+        let mut literal = None;
         let name = {
             // Can not use `input.lookahead()` here, as the third thing we expect is a complete
             // expression, and `lookahead()` only allows for tokens, as it is also building a nice
             // error message.  So we will construct an error message "manually" in the last branch.
             if input.peek(LitStr) {
                 let str_lit = input.parse::<LitStr>()?;
-                check_literal(str_lit.value(), str_lit.span())?;
+                let value = str_lit.value();
+                check_literal(&value, str_lit.span())?;
+                literal = Some((value, str_lit.span()));
                 str_lit.into_token_stream()
             } else if input.peek(LitByteStr) {
                 let byte_str_lit = input.parse::<LitByteStr>()?;
                 match String::from_utf8(byte_str_lit.value()) {
-                    Ok(value) => check_literal(value, byte_str_lit.span())?,
+                    Ok(value) => {
+                        check_literal(&value, byte_str_lit.span())?;
+                        literal = Some((value, byte_str_lit.span()));
+                    }
                     Err(err) => {
                         let text =
                             format!("Entry names should be valid UTF-8: {}", err.utf8_error());
@@ -208,13 +242,13 @@ impl Parse for DirectoryEntry {
         };
         input.parse::<Token![=>]>()?;
         let entry = input.parse::<Expr>()?;
-        Ok(DirectoryEntry { name, entry })
+        Ok(DirectoryEntry { name, literal, entry })
     }
 }
 
 struct PseudoDirectory {
     assign_to: Option<Ident>,
-    entries: Vec<DirectoryEntry>,
+    entries: Vec<Entry>,
 }
 
 impl Parse for PseudoDirectory {
@@ -227,8 +261,25 @@ impl Parse for PseudoDirectory {
             None
         };
 
-        let entries: Vec<DirectoryEntry> =
-            input.parse_terminated::<_, Token![,]>(DirectoryEntry::parse)?.into_iter().collect();
+        let entries: Vec<Entry> =
+            input.parse_terminated::<_, Token![,]>(Entry::parse)?.into_iter().collect();
+
+        // Names added via splices are only known at runtime, so they are not checked here.
+        let literals = entries.iter().filter_map(|entry| match entry {
+            Entry::Named(DirectoryEntry { literal, .. }) => literal.as_ref(),
+            Entry::Splice(_) => None,
+        });
+        let mut seen = HashSet::new();
+        for (name, span) in literals {
+            if !seen.insert(name) {
+                let message = format!(
+                    "Duplicate literal entry name '{}'. There is another literal \
+                     before this one with the same value.",
+                    name
+                );
+                return Err(syn::Error::new(*span, message));
+            }
+        }
 
         Ok(PseudoDirectory { assign_to, entries })
     }
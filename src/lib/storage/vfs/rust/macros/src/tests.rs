@@ -39,6 +39,22 @@ fn check_pseudo_directory_impl(input: &str, expected_immutable: &str) {
     }
 }
 
+fn check_pseudo_directory_impl_fails(input: &str, expected_message: &str) {
+    let input = TokenStream::from_str(input).unwrap();
+    for mutable in [false, true] {
+        let output = pseudo_directory_impl(mutable, input.clone()).to_string();
+        assert!(
+            output.contains("compile_error") && output.contains(expected_message),
+            "Expected a compile error containing:\n\
+             {}\n\
+             Actual:\n\
+             {}\n",
+            expected_message,
+            output
+        );
+    }
+}
+
 #[test]
 // Rustfmt is messing up indentation of the manually formatted code.
 #[rustfmt::skip]
@@ -141,3 +157,80 @@ fn entry_has_name_from_ref() {
          }"
     );
 }
+
+#[test]
+fn duplicate_literal_names() {
+    check_pseudo_directory_impl_fails(
+        indoc!(
+            r#"
+            "name" => read_only_static("A"),
+            "other" => read_only_static("B"),
+            "name" => read_only_static("C"),
+        "#
+        ),
+        "Duplicate literal entry name 'name'.",
+    );
+}
+
+#[test]
+fn duplicate_literal_and_byte_string_names() {
+    check_pseudo_directory_impl_fails(
+        indoc!(
+            r#"
+            "name" => read_only_static("A"),
+            b"name" => read_only_static("B"),
+        "#
+        ),
+        "Duplicate literal entry name 'name'.",
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn duplicate_expression_names_are_checked_at_runtime() {
+    check_pseudo_directory_impl(
+        indoc!(
+            r#"
+            test_name => read_only_static("A"),
+            test_name => read_only_static("B"),
+        "#
+        ),
+        "{ \
+             use :: vfs :: directory :: helper :: DirectlyMutable ; \
+             let __dir = :: vfs :: directory :: immutable :: simple () ; \
+             :: vfs :: pseudo_directory :: unwrap_add_entry_span (\
+                 test_name , \"bytes(1..10)\" , \
+                 __dir . clone () . add_entry (test_name , read_only_static (\"A\"))) ; \
+             :: vfs :: pseudo_directory :: unwrap_add_entry_span (\
+                 test_name , \"bytes(37..46)\" , \
+                 __dir . clone () . add_entry (test_name , read_only_static (\"B\"))) ; \
+             __dir \
+         }"
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn splice_entries() {
+    check_pseudo_directory_impl(
+        indoc!(
+            r#"
+            "first" => read_only_static("A"),
+            ..entries,
+        "#
+        ),
+        "{ \
+             use :: vfs :: directory :: helper :: DirectlyMutable ; \
+             let __dir = :: vfs :: directory :: immutable :: simple () ; \
+             :: vfs :: pseudo_directory :: unwrap_add_entry_span (\
+                 \"first\" , \"bytes(1..8)\" , \
+                 __dir . clone () . add_entry (\"first\" , read_only_static (\"A\"))) ; \
+             for (__name , __entry) in entries { \
+                 :: vfs :: pseudo_directory :: unwrap_add_entry_span (\
+                     & __name , \"bytes(37..44)\" , \
+                     __dir . clone () . add_entry (__name . clone () , __entry)) ; \
+             } \
+             __dir \
+         }"
+    );
+}
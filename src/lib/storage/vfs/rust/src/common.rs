@@ -160,7 +160,7 @@ pub fn rights_to_posix_mode_bits(readable: bool, writable: bool, executable: boo
 mod tests {
     use super::inherit_rights_for_clone;
 
-    use fidl_fuchsia_io as fio;
+    use {fidl_fuchsia_io as fio, fuchsia_zircon::Status};
 
     // TODO This should be converted into a function as soon as backtrace support is in place.
     // The only reason this is a macro is to generate error messages that point to the test
@@ -181,6 +181,19 @@ mod tests {
         }};
     }
 
+    macro_rules! irfc_err {
+        ($parent_flags:expr, $flags:expr, $expected_status:expr $(,)*) => {{
+            let res = inherit_rights_for_clone($parent_flags, $flags);
+            match res {
+                Ok(new_flags) => panic!(
+                    "`inherit_rights_for_clone` should have failed.  Got flags: {:X}",
+                    new_flags
+                ),
+                Err(status) => assert_eq!($expected_status, status),
+            }
+        }};
+    }
+
     #[test]
     fn node_reference_is_inherited() {
         irfc_ok!(
@@ -189,4 +202,65 @@ mod tests {
             fio::OpenFlags::NODE_REFERENCE
         );
     }
+
+    #[test]
+    fn same_rights_are_inherited() {
+        irfc_ok!(
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::CLONE_SAME_RIGHTS,
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE
+        );
+    }
+
+    #[test]
+    fn clone_to_fewer_rights() {
+        irfc_ok!(
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::RIGHT_READABLE,
+            fio::OpenFlags::RIGHT_READABLE
+        );
+        irfc_ok!(
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_EXECUTABLE,
+            fio::OpenFlags::empty(),
+            fio::OpenFlags::empty()
+        );
+    }
+
+    #[test]
+    fn clone_to_more_rights_is_denied() {
+        irfc_err!(
+            fio::OpenFlags::RIGHT_READABLE,
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            Status::ACCESS_DENIED
+        );
+        irfc_err!(
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::RIGHT_EXECUTABLE,
+            Status::ACCESS_DENIED
+        );
+    }
+
+    #[test]
+    fn same_rights_excludes_every_specific_right() {
+        let parent_flags = fio::OpenFlags::RIGHT_READABLE
+            | fio::OpenFlags::RIGHT_WRITABLE
+            | fio::OpenFlags::RIGHT_EXECUTABLE;
+        for right in [
+            fio::OpenFlags::RIGHT_READABLE,
+            fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::RIGHT_EXECUTABLE,
+        ] {
+            irfc_err!(
+                parent_flags,
+                fio::OpenFlags::CLONE_SAME_RIGHTS | right,
+                Status::INVALID_ARGS
+            );
+        }
+        // Mutual exclusion is checked before the rights are compared with the parent ones.
+        irfc_err!(
+            fio::OpenFlags::empty(),
+            fio::OpenFlags::CLONE_SAME_RIGHTS | fio::OpenFlags::RIGHT_WRITABLE,
+            Status::INVALID_ARGS
+        );
+    }
 }
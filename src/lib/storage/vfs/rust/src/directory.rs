@@ -15,11 +15,19 @@ pub mod mutable;
 
 pub mod simple;
 
+pub mod allowlist;
+pub use allowlist::allowlist;
 pub mod connection;
 pub mod dirents_sink;
 pub mod entry;
 pub mod entry_container;
+mod forwarding;
 pub mod helper;
+pub mod overlay;
+pub use overlay::overlay;
 pub mod read_dirents;
+pub mod renamed;
+pub use renamed::renamed;
+mod subtree_size;
 pub mod traversal_position;
 pub mod watchers;
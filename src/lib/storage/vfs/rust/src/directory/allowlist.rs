@@ -0,0 +1,78 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A directory that exposes only an explicit set of entries of another directory.  See
+//! [`allowlist`] for details.
+
+use crate::directory::{
+    entry_container::Directory,
+    renamed::{renamed, Renamed},
+};
+
+use std::{collections::HashSet, sync::Arc};
+
+/// Creates a directory that exposes only the entries of `inner` named in `allowed`.
+///
+/// Opening a name that is not in `allowed` fails with `ZX_ERR_NOT_FOUND`, even if `inner` has an
+/// entry with that name.  `ReadDirents` lists the names in `allowed` that `inner` actually has, so
+/// the set may contain names that do not exist in `inner`, and they are not listed.
+///
+/// This is a [`renamed`] directory that keeps the allowed names unchanged, so it is immutable and
+/// does not support watchers either.
+pub fn allowlist(inner: Arc<dyn Directory>, allowed: HashSet<String>) -> Arc<Renamed> {
+    let allowed = Arc::new(allowed);
+    let keep_allowed = |allowed: Arc<HashSet<String>>| {
+        move |name: &str| allowed.contains(name).then(|| name.to_string())
+    };
+    renamed(inner, keep_allowed(allowed.clone()), keep_allowed(allowed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::allowlist;
+
+    // Macros are exported into the root of the crate.
+    use crate::{
+        assert_close, assert_event, assert_read, assert_read_dirents, open_as_file_assert_err,
+        open_get_proxy_assert, open_get_vmo_file_proxy_assert_ok,
+    };
+
+    use crate::{
+        directory::test_utils::{run_server_client, DirentsSameInodeBuilder},
+        file::vmo::asynchronous::read_only_static,
+    };
+
+    use {fidl_fuchsia_io as fio, fuchsia_zircon::Status, vfs_macros::pseudo_directory};
+
+    #[test]
+    fn nonexistent_allowed_name_not_listed() {
+        let inner = pseudo_directory! {
+            "public" => read_only_static(b"Public content"),
+            "secret" => read_only_static(b"Secret content"),
+        };
+        let root = allowlist(
+            inner,
+            vec!["public".to_string(), "missing".to_string()].into_iter().collect(),
+        );
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+            {
+                let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "public");
+                assert_read!(file, "Public content");
+                assert_close!(file);
+            }
+
+            open_as_file_assert_err!(&root, flags, "secret", Status::NOT_FOUND);
+            open_as_file_assert_err!(&root, flags, "missing", Status::NOT_FOUND);
+
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected.add(fio::DirentType::Directory, b".").add(fio::DirentType::File, b"public");
+            assert_read_dirents!(root, 1000, expected.into_vec());
+
+            assert_close!(root);
+        });
+    }
+}
@@ -107,6 +107,15 @@ pub fn check_child_connection_flags(
         return Err(zx::Status::INVALID_ARGS);
     }
 
+    // OPEN_FLAG_APPEND and OPEN_FLAG_TRUNCATE are only meaningful for files.  Reject them here when
+    // the child is known to be a directory, rather than relying on the child connection to
+    // validate them.
+    if flags.intersects(fio::OpenFlags::APPEND | fio::OpenFlags::TRUNCATE)
+        && mode & fio::MODE_TYPE_MASK == fio::MODE_TYPE_DIRECTORY
+    {
+        return Err(zx::Status::INVALID_ARGS);
+    }
+
     // Can only specify OPEN_FLAG_CREATE_IF_ABSENT if OPEN_FLAG_CREATE is also specified.
     if flags.intersects(fio::OpenFlags::CREATE_IF_ABSENT)
         && !flags.intersects(fio::OpenFlags::CREATE)
@@ -140,6 +149,8 @@ pub fn check_child_connection_flags(
         return Err(zx::Status::ACCESS_DENIED);
     }
 
+    // Unlike the POSIX flags above, rights that the parent does not have are never removed, so
+    // requesting OPEN_RIGHT_EXECUTABLE over a non-executable parent fails.
     if stricter_or_same_rights(parent_flags, flags) {
         Ok((flags, mode))
     } else {
@@ -367,6 +378,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_child_connection_flags_append() {
+        // OPEN_FLAG_APPEND is rejected for directories, whether indicated by flags or by mode.
+        assert_eq!(
+            check_child_connection_flags(
+                fio::OpenFlags::RIGHT_WRITABLE,
+                fio::OpenFlags::DIRECTORY | fio::OpenFlags::APPEND,
+                0
+            ),
+            Err(zx::Status::INVALID_ARGS),
+        );
+        assert_eq!(
+            check_child_connection_flags(
+                fio::OpenFlags::RIGHT_WRITABLE,
+                fio::OpenFlags::APPEND,
+                fio::MODE_TYPE_DIRECTORY
+            ),
+            Err(zx::Status::INVALID_ARGS),
+        );
+
+        // It is still allowed when the child could be a file.
+        assert!(check_child_connection_flags(
+            fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::APPEND,
+            0
+        )
+        .is_ok());
+        assert!(check_child_connection_flags(
+            fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::APPEND,
+            fio::MODE_TYPE_FILE
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn check_child_connection_flags_truncate() {
+        // OPEN_FLAG_TRUNCATE is rejected for directories, whether indicated by flags or by mode.
+        assert_eq!(
+            check_child_connection_flags(
+                fio::OpenFlags::RIGHT_WRITABLE,
+                fio::OpenFlags::DIRECTORY | fio::OpenFlags::TRUNCATE,
+                0
+            ),
+            Err(zx::Status::INVALID_ARGS),
+        );
+        assert_eq!(
+            check_child_connection_flags(
+                fio::OpenFlags::RIGHT_WRITABLE,
+                fio::OpenFlags::TRUNCATE,
+                fio::MODE_TYPE_DIRECTORY
+            ),
+            Err(zx::Status::INVALID_ARGS),
+        );
+
+        // It is still allowed when the child could be a file.
+        assert!(check_child_connection_flags(
+            fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::TRUNCATE,
+            0
+        )
+        .is_ok());
+        assert!(check_child_connection_flags(
+            fio::OpenFlags::RIGHT_WRITABLE,
+            fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::TRUNCATE,
+            fio::MODE_TYPE_FILE
+        )
+        .is_ok());
+    }
+
     #[test]
     fn check_child_connection_flags_invalid() {
         // Cannot specify both OPEN_FLAG_DIRECTORY and OPEN_FLAG_NOT_DIRECTORY.
@@ -389,4 +470,77 @@ mod tests {
             Err(zx::Status::INVALID_ARGS),
         );
     }
+
+    #[test]
+    fn check_child_connection_flags_executable_escalation() {
+        // Requesting OPEN_RIGHT_EXECUTABLE over a non-executable parent is always rejected, no
+        // matter which other flags are specified.
+        for parent_flags in build_flag_combinations(
+            0,
+            (fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE).bits(),
+        ) {
+            let parent_flags = fio::OpenFlags::from_bits_truncate(parent_flags);
+            for flags in build_flag_combinations(
+                fio::OpenFlags::RIGHT_EXECUTABLE.bits(),
+                (fio::OpenFlags::RIGHT_READABLE
+                    | fio::OpenFlags::RIGHT_WRITABLE
+                    | fio::OpenFlags::POSIX_EXECUTABLE
+                    | fio::OpenFlags::POSIX_WRITABLE
+                    | fio::OpenFlags::POSIX_DEPRECATED)
+                    .bits(),
+            ) {
+                let flags = fio::OpenFlags::from_bits_truncate(flags);
+                assert_eq!(
+                    check_child_connection_flags(parent_flags, flags, 0),
+                    Err(zx::Status::ACCESS_DENIED),
+                    "parent_flags: {:?}, flags: {:?}",
+                    parent_flags,
+                    flags,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn check_child_connection_flags_posix_executable() {
+        // OPEN_FLAG_POSIX_EXECUTABLE is silently removed when the parent is not executable.
+        for parent_flags in build_flag_combinations(
+            0,
+            (fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE).bits(),
+        ) {
+            for flags in
+                build_flag_combinations(fio::OpenFlags::POSIX_EXECUTABLE.bits(), parent_flags)
+            {
+                let parent_flags = fio::OpenFlags::from_bits_truncate(parent_flags);
+                let flags = fio::OpenFlags::from_bits_truncate(flags);
+                assert_eq!(
+                    check_child_connection_flags(parent_flags, flags, 0),
+                    Ok((flags - fio::OpenFlags::POSIX_EXECUTABLE, 0)),
+                    "parent_flags: {:?}, flags: {:?}",
+                    parent_flags,
+                    flags,
+                );
+            }
+        }
+
+        // And is preserved when the parent is executable.
+        for parent_flags in build_flag_combinations(
+            fio::OpenFlags::RIGHT_EXECUTABLE.bits(),
+            (fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE).bits(),
+        ) {
+            for flags in
+                build_flag_combinations(fio::OpenFlags::POSIX_EXECUTABLE.bits(), parent_flags)
+            {
+                let parent_flags = fio::OpenFlags::from_bits_truncate(parent_flags);
+                let flags = fio::OpenFlags::from_bits_truncate(flags);
+                assert_eq!(
+                    check_child_connection_flags(parent_flags, flags, 0),
+                    Ok((flags, 0)),
+                    "parent_flags: {:?}, flags: {:?}",
+                    parent_flags,
+                    flags,
+                );
+            }
+        }
+    }
 }
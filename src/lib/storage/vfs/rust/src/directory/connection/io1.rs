@@ -18,12 +18,68 @@ use crate::{
 
 use {
     anyhow::Error,
-    fidl::{endpoints::ServerEnd, Handle},
+    fidl::{
+        endpoints::{RequestStream as _, ServerEnd},
+        Handle,
+    },
     fidl_fuchsia_io as fio, fuchsia_zircon as zx,
     futures::{channel::oneshot, future::BoxFuture, select, StreamExt},
+    log::warn,
     std::{convert::TryInto as _, default::Default, sync::Arc},
 };
 
+/// Selects the event a new directory connection describes itself with, when the client asked for
+/// it with `OPEN_FLAG_DESCRIBE`.  Connections opened with `Open` or `Clone` use
+/// [`DescribeMode::NodeInfo`], and the ones opened with `Reopen` use
+/// [`DescribeMode::ConnectionInfo`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DescribeMode {
+    /// fuchsia.io v1 `OnOpen` event, carrying a `NodeInfo`.
+    NodeInfo,
+    /// fuchsia.io v2 `OnConnectionInfo` event, carrying a `Representation::Directory`.
+    ConnectionInfo,
+}
+
+/// Connection information reported for directories by `Describe2` and `OnConnectionInfo`.
+pub(crate) fn connection_info() -> fio::ConnectionInfo {
+    fio::ConnectionInfo {
+        representation: Some(fio::Representation::Directory(fio::DirectoryInfo::EMPTY)),
+        ..fio::ConnectionInfo::EMPTY
+    }
+}
+
+/// Sends the event selected by `mode`, describing a newly established directory connection.
+pub(crate) fn send_describe(
+    control_handle: &fio::DirectoryControlHandle,
+    mode: DescribeMode,
+) -> Result<(), fidl::Error> {
+    match mode {
+        DescribeMode::NodeInfo => {
+            let mut info = fio::NodeInfo::Directory(fio::DirectoryObject);
+            control_handle.send_on_open_(zx::Status::OK.into_raw(), Some(&mut info))
+        }
+        DescribeMode::ConnectionInfo => control_handle.send_on_connection_info(connection_info()),
+    }
+}
+
+/// Reports a failure to establish a directory connection to a client expecting the connection to
+/// be described as selected by `mode`.  fuchsia.io v1 clients get an `OnOpen` event, see
+/// [`send_on_open_with_error`], while `OnConnectionInfo` has no status, so v2 clients only get an
+/// epitaph.
+pub(crate) fn send_describe_error(
+    flags: fio::OpenFlags,
+    mode: DescribeMode,
+    server_end: ServerEnd<fio::NodeMarker>,
+    status: zx::Status,
+) {
+    match mode {
+        DescribeMode::NodeInfo => send_on_open_with_error(flags, server_end, status),
+        DescribeMode::ConnectionInfo => {
+            let _ = server_end.close_with_epitaph(status);
+        }
+    }
+}
+
 /// Return type for `BaseConnection::handle_request` and [`DerivedConnection::handle_request`].
 pub enum ConnectionState {
     /// Connection is still alive.
@@ -56,6 +112,25 @@ pub trait DerivedConnection: Send + Sync {
         directory: Arc<Self::Directory>,
         flags: fio::OpenFlags,
         server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        Self::create_connection_with_describe_mode(
+            scope,
+            directory,
+            flags,
+            DescribeMode::NodeInfo,
+            server_end,
+        );
+    }
+
+    /// Same as [`DerivedConnection::create_connection`], but the connection is described, and
+    /// errors are reported, as selected by `describe_mode`.  See [`send_describe`] and
+    /// [`send_describe_error`].
+    fn create_connection_with_describe_mode(
+        scope: ExecutionScope,
+        directory: Arc<Self::Directory>,
+        flags: fio::OpenFlags,
+        describe_mode: DescribeMode,
+        server_end: ServerEnd<fio::NodeMarker>,
     );
 
     fn entry_not_found(
@@ -133,6 +208,16 @@ pub(in crate::directory) async fn handle_requests<Connection>(
         };
 
         match request_or_err {
+            Err(fidl::Error::UnknownOrdinal { ordinal, protocol_name }) => {
+                // A method added to the protocol after this connection was compiled.  Clients
+                // probing for newer methods should see a clear error, rather than a closed
+                // channel.
+                close_unsupported(
+                    &requests.control_handle(),
+                    &format!("{} ordinal {:#x}", protocol_name, ordinal),
+                );
+                break;
+            }
             Err(_) => {
                 // FIDL level error, such as invalid message format and alike.  Close the
                 // connection on any unexpected error.
@@ -153,6 +238,17 @@ pub(in crate::directory) async fn handle_requests<Connection>(
     // The underlying directory will be closed automatically when the OpenDirectory is dropped.
 }
 
+/// Closes a connection that received a request it does not implement, with a
+/// `ZX_ERR_NOT_SUPPORTED` epitaph.  `method` identifies the request in the log.
+fn close_unsupported(
+    control_handle: &fio::DirectoryControlHandle,
+    method: &str,
+) -> ConnectionState {
+    warn!("{} is not supported, closing the directory connection", method);
+    control_handle.shutdown_with_epitaph(zx::Status::NOT_SUPPORTED);
+    ConnectionState::Closed
+}
+
 impl<Connection> BaseConnection<Connection>
 where
     Connection: DerivedConnection,
@@ -181,8 +277,7 @@ where
             }
             fio::DirectoryRequest::Reopen { options, object_request, control_handle: _ } => {
                 fuchsia_trace::duration!("storage", "Directory::Reopen");
-                let _ = object_request;
-                todo!("https://fxbug.dev/77623: options={:?}", options);
+                self.handle_reopen(options, ServerEnd::new(object_request));
             }
             fio::DirectoryRequest::Close { responder } => {
                 fuchsia_trace::duration!("storage", "Directory::Close");
@@ -194,10 +289,9 @@ where
                 let mut info = fio::NodeInfo::Directory(fio::DirectoryObject);
                 responder.send(&mut info)?;
             }
-            fio::DirectoryRequest::Describe2 { query, responder } => {
+            fio::DirectoryRequest::Describe2 { query: _, responder } => {
                 fuchsia_trace::duration!("storage", "Directory::Describe2");
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: query={:?}", query);
+                responder.send(connection_info())?;
             }
             fio::DirectoryRequest::GetAttr { responder } => {
                 fuchsia_trace::duration!("storage", "Directory::GetAttr");
@@ -218,10 +312,9 @@ where
                 };
                 responder.send(status, &mut attrs)?;
             }
-            fio::DirectoryRequest::GetAttributes { query, responder } => {
+            fio::DirectoryRequest::GetAttributes { query: _, responder } => {
                 fuchsia_trace::duration!("storage", "Directory::GetAttributes");
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: query={:?}", query);
+                responder.send(&mut Err(zx::Status::NOT_SUPPORTED.into_raw()))?;
             }
             fio::DirectoryRequest::UpdateAttributes { attributes: _, responder } => {
                 fuchsia_trace::duration!("storage", "Directory::UpdateAttributes");
@@ -240,35 +333,27 @@ where
                 self.handle_open(flags, mode, path, object);
             }
             fio::DirectoryRequest::Open2 {
-                path,
-                mode,
-                options,
-                object_request,
-                control_handle: _,
+                path: _,
+                mode: _,
+                options: _,
+                object_request: _,
+                control_handle,
             } => {
                 fuchsia_trace::duration!("storage", "Directory::Open2");
-                let _ = object_request;
-                todo!(
-                    "https://fxbug.dev/77623: path={} mode={:?} options={:?}",
-                    path,
-                    mode,
-                    options
-                );
+                return Ok(close_unsupported(&control_handle, "Directory::Open2"));
             }
             fio::DirectoryRequest::AddInotifyFilter {
-                path,
-                filter,
-                watch_descriptor,
+                path: _,
+                filter: _,
+                watch_descriptor: _,
                 socket: _,
-                responder: _,
+                responder,
             } => {
                 fuchsia_trace::duration!("storage", "Directory::AddInotifyFilter");
-                todo!(
-                    "https://fxbug.dev/77623: path={} filter={:?} watch_descriptor={}",
-                    path,
-                    filter,
-                    watch_descriptor
-                );
+                return Ok(close_unsupported(
+                    &responder.control_handle(),
+                    "Directory::AddInotifyFilter",
+                ));
             }
             fio::DirectoryRequest::AdvisoryLock { request: _, responder } => {
                 fuchsia_trace::duration!("storage", "Directory::AdvisoryLock");
@@ -279,10 +364,9 @@ where
                 let (status, entries) = self.handle_read_dirents(max_bytes).await;
                 responder.send(status.into_raw(), entries.as_slice())?;
             }
-            fio::DirectoryRequest::Enumerate { options, iterator, control_handle: _ } => {
+            fio::DirectoryRequest::Enumerate { options: _, iterator: _, control_handle } => {
                 fuchsia_trace::duration!("storage", "Directory::Enumerate");
-                let _ = iterator;
-                todo!("https://fxbug.dev/77623: options={:?}", options);
+                return Ok(close_unsupported(&control_handle, "Directory::Enumerate"));
             }
             fio::DirectoryRequest::Rewind { responder } => {
                 fuchsia_trace::duration!("storage", "Directory::Rewind");
@@ -344,9 +428,49 @@ where
             }
         };
 
+        if let Some(observer) = self.scope.clone_observer() {
+            observer(flags);
+        }
+
         self.directory.clone().open(self.scope.clone(), flags, mode, Path::dot(), server_end);
     }
 
+    /// Opens another connection to this directory, with the same flags as this connection.  The
+    /// only option supported is `GET_CONNECTION_INFO`, which makes the new connection describe
+    /// itself with `OnConnectionInfo`.
+    fn handle_reopen(
+        &self,
+        options: fio::ConnectionOptions,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        let connection_flags = options.flags.unwrap_or(fio::ConnectionFlags::empty());
+        if options.protocols.is_some()
+            || options.rights_request.is_some()
+            || connection_flags.intersects(!fio::ConnectionFlags::GET_CONNECTION_INFO)
+        {
+            let _ = server_end.close_with_epitaph(zx::Status::NOT_SUPPORTED);
+            return;
+        }
+
+        let flags = if connection_flags.contains(fio::ConnectionFlags::GET_CONNECTION_INFO) {
+            self.flags | fio::OpenFlags::DESCRIBE
+        } else {
+            self.flags & !fio::OpenFlags::DESCRIBE
+        };
+
+        if let Some(observer) = self.scope.clone_observer() {
+            observer(flags);
+        }
+
+        Connection::create_connection_with_describe_mode(
+            self.scope.clone(),
+            self.directory.clone(),
+            flags,
+            DescribeMode::ConnectionInfo,
+            server_end,
+        );
+    }
+
     fn handle_open(
         &self,
         mut flags: fio::OpenFlags,
@@ -629,4 +753,47 @@ mod tests {
         );
         assert_matches!(event_stream.try_next().await, Ok(None));
     }
+
+    /// Sends a one-way request with an ordinal no fuchsia.io method uses over `client`, and checks
+    /// that the connection is closed with a `ZX_ERR_NOT_SUPPORTED` epitaph.
+    async fn assert_unknown_ordinal_not_supported(client: zx::Channel) {
+        // Zero transaction id, the V2 wire format flag and the FIDL magic number.
+        let mut message = vec![0, 0, 0, 0, 2, 0, 0, 1];
+        message.extend_from_slice(&0x0123_4567_89ab_cdefu64.to_le_bytes());
+        client.write(&message, &mut vec![]).unwrap();
+
+        let proxy = fio::DirectoryProxy::new(fasync::Channel::from_channel(client).unwrap());
+        assert_matches!(
+            proxy.take_event_stream().next().await,
+            Some(Err(fidl::Error::ClientChannelClosed { status: zx::Status::NOT_SUPPORTED, .. }))
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_unknown_ordinal_closes_with_not_supported() {
+        let (client, server) = zx::Channel::create().unwrap();
+        simple().open(
+            ExecutionScope::new(),
+            fio::OpenFlags::DIRECTORY | fio::OpenFlags::RIGHT_READABLE,
+            fio::MODE_TYPE_DIRECTORY,
+            Path::dot(),
+            ServerEnd::new(server),
+        );
+        assert_unknown_ordinal_not_supported(client).await;
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_unknown_ordinal_closes_mutable_with_not_supported() {
+        let (client, server) = zx::Channel::create().unwrap();
+        crate::directory::mutable::simple::simple().open(
+            ExecutionScope::new(),
+            fio::OpenFlags::DIRECTORY
+                | fio::OpenFlags::RIGHT_READABLE
+                | fio::OpenFlags::RIGHT_WRITABLE,
+            fio::MODE_TYPE_DIRECTORY,
+            Path::dot(),
+            ServerEnd::new(server),
+        );
+        assert_unknown_ordinal_not_supported(client).await;
+    }
 }
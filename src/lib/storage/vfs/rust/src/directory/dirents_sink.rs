@@ -4,9 +4,11 @@
 
 //! Types that help describe `get_entry_names` callback for the lazy directories.
 
-use crate::directory::entry::EntryInfo;
+use crate::directory::{
+    entry::EntryInfo, entry_container::Directory, traversal_position::TraversalPosition,
+};
 
-use std::any::Any;
+use {fuchsia_zircon::Status, std::any::Any};
 
 /// Every sink that can consume directory entry information implements this trait.
 pub trait Sink: Send {
@@ -54,3 +56,41 @@ pub enum AppendResult {
     /// Sink could not consume the last value provided.
     Sealed(Box<dyn Sealed>),
 }
+
+/// Lists all the entries of `dir`, except for ".".
+pub(crate) async fn collect_entries(
+    dir: &dyn Directory,
+) -> Result<Vec<(EntryInfo, String)>, Status> {
+    let mut entries = vec![];
+    let mut pos = TraversalPosition::Start;
+    while pos != TraversalPosition::End {
+        let (next, sealed) = dir.read_dirents(&pos, Box::new(CollectingSink::default())).await?;
+        let sink = sealed.open().downcast::<CollectingSink>().map_err(|_| Status::INTERNAL)?;
+        entries.extend(sink.entries.into_iter().filter(|(_, name)| name != "."));
+        pos = next;
+    }
+    Ok(entries)
+}
+
+/// Accepts any number of entries.
+#[derive(Default)]
+struct CollectingSink {
+    entries: Vec<(EntryInfo, String)>,
+}
+
+impl Sink for CollectingSink {
+    fn append(mut self: Box<Self>, entry: &EntryInfo, name: &str) -> AppendResult {
+        self.entries.push((entry.clone(), name.to_string()));
+        AppendResult::Ok(self)
+    }
+
+    fn seal(self: Box<Self>) -> Box<dyn Sealed> {
+        self
+    }
+}
+
+impl Sealed for CollectingSink {
+    fn open(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
@@ -6,7 +6,10 @@
 
 #![warn(missing_docs)]
 
-use crate::{common::IntoAny, execution_scope::ExecutionScope, path::Path};
+use crate::{
+    common::IntoAny, directory::entry_container::Directory, execution_scope::ExecutionScope,
+    file::File, path::Path,
+};
 
 use {
     fidl::endpoints::ServerEnd,
@@ -86,4 +89,25 @@ pub trait DirectoryEntry: IntoAny + Sync + Send {
 
     /// This method is used to populate ReadDirents() output.
     fn entry_info(&self) -> EntryInfo;
+
+    /// Called by directories that assign inode numbers to their entries, such as the ones created
+    /// by [`crate::directory::immutable::simple_with_assigned_inodes`], with the number assigned
+    /// to this entry.  Entries that can change the inode they report override this, so that
+    /// [`DirectoryEntry::entry_info`] and `GetAttr` agree with the listings of the directory.  By
+    /// default the number is ignored, and only the listings of the directory report it.
+    fn set_assigned_inode(&self, _inode: u64) {}
+
+    /// Returns this entry as a [`Directory`], if it is a directory served by this crate.  Used to
+    /// walk a tree of entries without opening connections to them.  Remote directories, and
+    /// entries that are not directories, return `None`.
+    fn into_directory(self: Arc<Self>) -> Option<Arc<dyn Directory>> {
+        None
+    }
+
+    /// Returns this entry as a [`File`], if it is a file that implements that trait.  Used to
+    /// inspect a tree of entries without opening connections to them.  Other entries, including
+    /// VMO backed files, return `None`.
+    fn into_file(self: Arc<Self>) -> Option<Arc<dyn File>> {
+        None
+    }
 }
@@ -6,7 +6,9 @@
 //! content.
 
 use crate::{
-    directory::{dirents_sink, entry::DirectoryEntry, traversal_position::TraversalPosition},
+    directory::{
+        dirents_sink, entry::DirectoryEntry, subtree_size, traversal_position::TraversalPosition,
+    },
     execution_scope::ExecutionScope,
     filesystem::Filesystem,
 };
@@ -15,6 +17,7 @@ use {
     async_trait::async_trait,
     fidl_fuchsia_io as fio,
     fuchsia_zircon::Status,
+    futures::future::{BoxFuture, FutureExt},
     std::{any::Any, sync::Arc},
 };
 
@@ -33,6 +36,12 @@ mod private {
             let Self { channel } = self;
             channel
         }
+
+        /// Consumes the watcher, returning the underlying channel.
+        pub fn into_channel(self) -> fuchsia_async::Channel {
+            let Self { channel } = self;
+            channel
+        }
     }
 
     impl TryFrom<fidl::endpoints::ServerEnd<fio::DirectoryWatcherMarker>> for DirectoryWatcher {
@@ -62,6 +71,13 @@ pub trait Directory: DirectoryEntry {
         sink: Box<dyn dirents_sink::Sink>,
     ) -> Result<(TraversalPosition, Box<dyn dirents_sink::Sealed>), Status>;
 
+    /// Returns the entry called `name` in this directory, without opening a connection to it.
+    /// Fails with `NOT_FOUND` if there is no such entry.  Directories that can not look up their
+    /// entries this way fail with `NOT_SUPPORTED`, which is the default.
+    async fn get_entry(&self, _name: &str) -> Result<Arc<dyn DirectoryEntry>, Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
     /// Register a watcher for this directory.
     /// Implementations will probably want to use a `Watcher` to manage watchers.
     fn register_watcher(
@@ -86,6 +102,21 @@ pub trait Directory: DirectoryEntry {
     fn query_filesystem(&self) -> Result<fio::FilesystemInfo, Status> {
         Err(Status::NOT_SUPPORTED)
     }
+
+    /// Returns the total size, in bytes, of all the files in the subtree rooted at this directory.
+    /// The subtree is walked through [`Directory::get_entry`], and the size of each file is the
+    /// one [`crate::file::File::get_size`] reports.  Entries that are neither directories nor
+    /// files served by this crate, such as VMO files, remotes and services, are skipped, and so
+    /// are entries that fail to report their size or list their content.
+    ///
+    /// Entries are visited only once, so cycles and hard links are handled.  Entries are told
+    /// apart by their inode number when they report one, and by their identity otherwise.
+    fn subtree_size(self: Arc<Self>) -> BoxFuture<'static, Result<u64, Status>>
+    where
+        Self: Sized + 'static,
+    {
+        subtree_size::subtree_size(self).boxed()
+    }
 }
 
 /// This trait indicates a directory that can be mutated by adding and removing entries.
@@ -111,6 +142,13 @@ pub trait MutableDirectory: Directory + Send + Sync {
         attributes: fio::NodeAttributes,
     ) -> Result<(), Status>;
 
+    /// Atomically swaps the entries behind `name_a` and `name_b` in this directory, similar to
+    /// the Linux `RENAME_EXCHANGE`.  Fails with ZX_ERR_NOT_FOUND if either of the entries does not
+    /// exist.
+    async fn rename_exchange(&self, _name_a: &str, _name_b: &str) -> Result<(), Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
     /// Removes an entry from this directory.
     async fn unlink(self: Arc<Self>, name: &str, must_be_directory: bool) -> Result<(), Status>;
 
@@ -0,0 +1,51 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Plumbing for the directories that serve the entries of another directory, such as
+//! [`crate::directory::renamed`].
+
+use crate::{
+    common::send_on_open_with_error,
+    directory::{
+        connection::io1::DerivedConnection, entry_container::Directory,
+        immutable::connection::io1::ImmutableConnection,
+    },
+    execution_scope::ExecutionScope,
+    path::Path,
+};
+
+use {
+    fidl::endpoints::ServerEnd, fidl_fuchsia_io as fio, fuchsia_zircon::Status,
+    std::future::Future, std::sync::Arc,
+};
+
+/// Handles an `Open` of `path` in `dir`.  An empty `path` opens `dir` itself, through an
+/// [`ImmutableConnection`].  Any other path is passed to `route`, which returns the directory that
+/// holds the entry and the path of the entry in that directory, and the open is forwarded there.
+/// Errors returned by `route` are reported to the client.
+pub(crate) fn open_forwarded<D, Route, RouteFuture>(
+    dir: Arc<D>,
+    scope: ExecutionScope,
+    flags: fio::OpenFlags,
+    mode: u32,
+    path: Path,
+    server_end: ServerEnd<fio::NodeMarker>,
+    route: Route,
+) where
+    D: Directory + 'static,
+    Route: FnOnce(Arc<D>, Path) -> RouteFuture + Send + 'static,
+    RouteFuture: Future<Output = Result<(Arc<dyn Directory>, Path), Status>> + Send + 'static,
+{
+    if path.is_empty() {
+        ImmutableConnection::create_connection(scope, dir, flags, server_end);
+        return;
+    }
+
+    scope.clone().spawn(async move {
+        match route(dir, path).await {
+            Ok((inner, inner_path)) => inner.open(scope, flags, mode, inner_path, server_end),
+            Err(status) => send_on_open_with_error(flags, server_end, status),
+        }
+    });
+}
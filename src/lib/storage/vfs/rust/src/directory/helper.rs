@@ -24,10 +24,11 @@ pub trait DirectlyMutable: Directory + Send + Sync {
     /// Adds a child entry to this directory.
     ///
     /// Possible errors are:
-    ///   * `ZX_ERR_INVALID_ARGS` if `name` exceeds [`fidl_fuchsia_io::MAX_FILENAME`] bytes in
+    ///   * `ZX_ERR_BAD_PATH` if `name` exceeds [`fidl_fuchsia_io::MAX_FILENAME`] bytes in
     ///     length, or if `name` includes a path separator ('/') character.
     ///   * `ZX_ERR_ALREADY_EXISTS` if an entry with the same name is already present in the
     ///     directory.
+    ///   * `ZX_ERR_NO_SPACE` if the directory limits the number of its entries, and is full.
     fn add_entry<Name>(&self, name: Name, entry: Arc<dyn DirectoryEntry>) -> Result<(), Status>
     where
         Name: Into<String>,
@@ -39,10 +40,12 @@ pub trait DirectlyMutable: Directory + Send + Sync {
     /// Adds a child entry to this directory.
     ///
     /// Possible errors are:
-    ///   * `ZX_ERR_INVALID_ARGS` if `name` exceeds [`fidl_fuchsia_io::MAX_FILENAME`] bytes in
+    ///   * `ZX_ERR_BAD_PATH` if `name` exceeds [`fidl_fuchsia_io::MAX_FILENAME`] bytes in
     ///     length, or if `name` includes a path separator ('/') character.
     ///   * `ZX_ERR_ALREADY_EXISTS` if an entry with the same name is already present in the
     ///     directory, and `overwrite` is false.
+    ///   * `ZX_ERR_NO_SPACE` if the directory limits the number of its entries, is full, and no
+    ///     entry is replaced.
     fn add_entry_impl(
         &self,
         name: String,
@@ -135,6 +138,15 @@ pub trait DirectlyMutable: Directory + Send + Sync {
     /// It should only be used by the [`crate::filesystem::FilesystemRename::rename()`].
     fn rename_within(&self, src: String, dst: String) -> Result<(), Status>;
 
+    /// Swaps the entries named `a` and `b` in this directory while holding the directory lock, so
+    /// that no client can observe the directory with only one of the entries moved.
+    ///
+    /// Possible errors are:
+    ///   * `ZX_ERR_INVALID_ARGS` if either name exceeds [`fidl_fuchsia_io::MAX_FILENAME`] bytes in
+    ///     length.
+    ///   * `ZX_ERR_NOT_FOUND` if either of the entries does not exist.
+    fn exchange_within(&self, a: String, b: String) -> Result<(), Status>;
+
     /// Get the filesystem this directory belongs to.
     fn get_filesystem(&self) -> &dyn Filesystem;
 }
@@ -149,6 +161,10 @@ impl<T: DirectlyMutable> MutableDirectory for T {
         }
     }
 
+    async fn rename_exchange(&self, name_a: &str, name_b: &str) -> Result<(), Status> {
+        self.exchange_within(name_a.into(), name_b.into())
+    }
+
     async fn set_attrs(
         &self,
         _flags: fio::NodeAttributeFlags,
@@ -7,9 +7,12 @@
 //! directories.
 
 pub mod simple;
-pub use simple::{simple, simple_with_inode, Simple};
+pub use simple::{
+    simple, simple_sealed, simple_with_assigned_inodes, simple_with_inode,
+    simple_with_stable_cookies, simple_with_type, Simple,
+};
 
 pub mod lazy;
-pub use lazy::{lazy, Lazy};
+pub use lazy::{lazy, lazy_from_fns, Lazy};
 
 pub mod connection;
@@ -6,13 +6,12 @@
 //! the client has on the FIDL connection.
 
 use crate::{
-    common::send_on_open_with_error,
     directory::{
         common::new_connection_validate_flags,
         connection::{
             io1::{
-                handle_requests, BaseConnection, BaseConnectionClient, ConnectionState,
-                DerivedConnection,
+                handle_requests, send_describe, send_describe_error, BaseConnection,
+                BaseConnectionClient, ConnectionState, DerivedConnection, DescribeMode,
             },
             util::OpenDirectory,
         },
@@ -47,53 +46,62 @@ impl DerivedConnection for ImmutableConnection {
         ImmutableConnection { base: BaseConnection::<Self>::new(scope, directory, flags) }
     }
 
-    fn create_connection(
+    fn create_connection_with_describe_mode(
         scope: ExecutionScope,
         directory: Arc<Self::Directory>,
         flags: fio::OpenFlags,
+        describe_mode: DescribeMode,
         server_end: ServerEnd<fio::NodeMarker>,
     ) {
         // Ensure we close the directory if we fail to create the connection.
         let directory = OpenDirectory::new(directory);
 
-        // TODO(fxbug.dev/82054): These flags should be validated before create_connection is called
-        // since at this point the directory resource has already been opened/created.
-        let flags = match new_connection_validate_flags(flags) {
-            Ok(updated) => updated,
-            Err(status) => {
-                send_on_open_with_error(flags, server_end, status);
-                return;
-            }
-        };
-
-        let (requests, control_handle) =
-            match ServerEnd::<fio::DirectoryMarker>::new(server_end.into_channel())
-                .into_stream_and_control_handle()
-            {
-                Ok((requests, control_handle)) => (requests, control_handle),
-                Err(_) => {
-                    // As we report all errors on `server_end`, if we failed to send an error over
-                    // this connection, there is nowhere to send the error to.
+        // The channel wrappers register with the executor that creates them, so they are created
+        // inside the task, which runs on the executor of the scope.
+        //
+        // If we fail to send the task to the executor, it is probably shut down or is in the
+        // process of shutting down (this is the only error state currently).  So there is nothing
+        // for us to do - the connection will be closed automatically when `server_end` is dropped.
+        let _ = scope.clone().spawn_with_shutdown(move |shutdown| async move {
+            // TODO(fxbug.dev/82054): These flags should be validated before create_connection is
+            // called since at this point the directory resource has already been opened/created.
+            let flags = match new_connection_validate_flags(flags) {
+                Ok(updated) => updated,
+                Err(status) => {
+                    send_describe_error(flags, describe_mode, server_end, status);
                     return;
                 }
             };
 
-        if flags.intersects(fio::OpenFlags::DESCRIBE) {
-            let mut info = fio::NodeInfo::Directory(fio::DirectoryObject);
-            match control_handle.send_on_open_(Status::OK.into_raw(), Some(&mut info)) {
-                Ok(()) => (),
-                Err(_) => return,
-            }
-        }
+            let _slot = match scope.acquire_connection() {
+                Ok(slot) => slot,
+                Err(status) => {
+                    send_describe_error(flags, describe_mode, server_end, status);
+                    return;
+                }
+            };
 
-        let connection = Self::new(scope.clone(), directory, flags);
+            let (requests, control_handle) =
+                match ServerEnd::<fio::DirectoryMarker>::new(server_end.into_channel())
+                    .into_stream_and_control_handle()
+                {
+                    Ok((requests, control_handle)) => (requests, control_handle),
+                    Err(_) => {
+                        // As we report all errors on `server_end`, if we failed to send an error
+                        // over this connection, there is nowhere to send the error to.
+                        return;
+                    }
+                };
+
+            if flags.intersects(fio::OpenFlags::DESCRIBE) {
+                match send_describe(&control_handle, describe_mode) {
+                    Ok(()) => (),
+                    Err(_) => return,
+                }
+            }
 
-        // If we fail to send the task to the executor, it is probably shut down or is in the
-        // process of shutting down (this is the only error state currently).  So there is nothing
-        // for us to do - the connection will be closed automatically when the connection object is
-        // dropped.
-        let _ = scope.spawn_with_shutdown(|shutdown| {
-            handle_requests::<Self>(requests, connection, shutdown)
+            let connection = Self::new(scope, directory, flags);
+            handle_requests::<Self>(requests, connection, shutdown).await
         });
     }
 
@@ -13,7 +13,7 @@ use crate::{
     common::{rights_to_posix_mode_bits, send_on_open_with_error},
     directory::{
         connection::io1::DerivedConnection,
-        dirents_sink,
+        dirents_sink::{self, AppendResult},
         entry::{DirectoryEntry, EntryInfo},
         entry_container::{Directory, DirectoryWatcher},
         immutable::connection::io1::ImmutableConnection,
@@ -89,11 +89,86 @@ where
     Lazy::new_with_watchers(scope, inner, watcher_events)
 }
 
+/// Creates a lazy directory from a pair of closures, with no watcher stream attached.  This is a
+/// shortcut for the cases when implementing [`LazyDirectory`] is not worth it.
+///
+/// `get_entry` is called every time an entry is opened by name.  It should construct the entry, or
+/// return `None` if there is no entry with this name, in which case the open fails with
+/// `NOT_FOUND`.
+///
+/// `get_entry_names` is called to produce the directory listing for `ReadDirents`.  It should
+/// return the names and the [`EntryInfo`] of all the entries, in any order.  The "." entry is
+/// added automatically.  As `ReadDirents` may require several calls to read the whole listing,
+/// `get_entry_names` may be called more than once per traversal.
+pub fn lazy_from_fns<GetEntry, GetEntryNames>(
+    get_entry: GetEntry,
+    get_entry_names: GetEntryNames,
+) -> Arc<Lazy<impl LazyDirectory>>
+where
+    GetEntry: Fn(&str) -> Option<Arc<dyn DirectoryEntry>> + Send + Sync + 'static,
+    GetEntryNames: Fn() -> Vec<(String, EntryInfo)> + Send + Sync + 'static,
+{
+    Lazy::new(LazyFns { get_entry, get_entry_names })
+}
+
+struct LazyFns<GetEntry, GetEntryNames> {
+    get_entry: GetEntry,
+    get_entry_names: GetEntryNames,
+}
+
+#[async_trait]
+impl<GetEntry, GetEntryNames> LazyDirectory for LazyFns<GetEntry, GetEntryNames>
+where
+    GetEntry: Fn(&str) -> Option<Arc<dyn DirectoryEntry>> + Send + Sync + 'static,
+    GetEntryNames: Fn() -> Vec<(String, EntryInfo)> + Send + Sync + 'static,
+{
+    async fn read_dirents<'a>(
+        &'a self,
+        pos: &'a TraversalPosition,
+        mut sink: Box<dyn dirents_sink::Sink>,
+    ) -> Result<(TraversalPosition, Box<dyn dirents_sink::Sealed>), Status> {
+        match pos {
+            TraversalPosition::Start => {
+                let dot = EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::Directory);
+                sink = match sink.append(&dot, ".") {
+                    AppendResult::Ok(sink) => sink,
+                    AppendResult::Sealed(done) => return Ok((TraversalPosition::Start, done)),
+                };
+            }
+            TraversalPosition::Name(_) => (),
+            TraversalPosition::Index(_) | TraversalPosition::Cookie(_) => unreachable!(),
+            TraversalPosition::End => return Ok((TraversalPosition::End, sink.seal())),
+        }
+
+        let mut entries = (self.get_entry_names)();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        // Entries are returned in alphabetical order, and the position holds the name of the next
+        // entry to be returned.
+        for (name, info) in entries {
+            match pos {
+                TraversalPosition::Name(next) if name < *next => continue,
+                _ => (),
+            }
+            sink = match sink.append(&info, &name) {
+                AppendResult::Ok(sink) => sink,
+                AppendResult::Sealed(done) => return Ok((TraversalPosition::Name(name), done)),
+            };
+        }
+
+        Ok((TraversalPosition::End, sink.seal()))
+    }
+
+    async fn get_entry(&self, name: &str) -> Result<Arc<dyn DirectoryEntry>, Status> {
+        (self.get_entry)(name).ok_or(Status::NOT_FOUND)
+    }
+}
+
 /// An implementation of a pseudo directory that generates nested entries only when they are
 /// requested.  This could be useful when the number of entries is big and the expected use case is
 /// that only a small fraction of all the entries will be interacted with at an given time.
 ///
-/// [`lazy`], and [`lazy_with_watchers`] are used to construct lazy directories.
+/// [`lazy`], [`lazy_with_watchers`], and [`lazy_from_fns`] are used to construct lazy directories.
 ///
 /// A lazy directory contains two callbacks and a stream.  One callback, called `get_entry_names`,
 /// which is used when a directory listing is requested.  Another callback, called `get_entry`, is
@@ -211,6 +286,10 @@ impl<T: LazyDirectory> DirectoryEntry for Lazy<T> {
     fn entry_info(&self) -> EntryInfo {
         EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::Directory)
     }
+
+    fn into_directory(self: Arc<Self>) -> Option<Arc<dyn Directory>> {
+        Some(self)
+    }
 }
 
 #[async_trait]
@@ -223,6 +302,10 @@ impl<T: LazyDirectory> Directory for Lazy<T> {
         self.inner.read_dirents(pos, sink).await
     }
 
+    async fn get_entry(&self, name: &str) -> Result<Arc<dyn DirectoryEntry>, Status> {
+        self.inner.get_entry(name).await
+    }
+
     fn register_watcher(
         self: Arc<Self>,
         scope: ExecutionScope,
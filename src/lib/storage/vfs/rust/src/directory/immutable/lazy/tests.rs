@@ -4,13 +4,13 @@
 
 //! Tests for the lazy directory.
 
-use super::{lazy, lazy_with_watchers, LazyDirectory, WatcherEvent};
+use super::{lazy, lazy_from_fns, lazy_with_watchers, LazyDirectory, WatcherEvent};
 
 // Macros are exported into the root of the crate.
 use crate::{
     assert_channel_closed, assert_close, assert_event, assert_read, assert_read_dirents,
-    assert_read_dirents_err, open_get_directory_proxy_assert_ok, open_get_proxy_assert,
-    open_get_vmo_file_proxy_assert_ok,
+    assert_read_dirents_err, open_as_file_assert_err, open_get_directory_proxy_assert_ok,
+    open_get_proxy_assert, open_get_vmo_file_proxy_assert_ok,
 };
 
 use crate::{
@@ -389,6 +389,83 @@ fn dynamic_entries() {
     );
 }
 
+#[test]
+fn entries_from_fns() {
+    let constructed = Arc::new(AtomicU8::new(0));
+    let get_entry = {
+        let constructed = constructed.clone();
+        move |name: &str| {
+            let pid = name.strip_prefix("pid-")?;
+            constructed.fetch_add(1, Ordering::Relaxed);
+            Some(read_only_const(format!("Process {}", pid).as_bytes()) as Arc<dyn DirectoryEntry>)
+        }
+    };
+    let get_entry_names = || {
+        vec![
+            ("pid-7".to_string(), EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::File)),
+            ("pid-42".to_string(), EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::File)),
+        ]
+    };
+
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE,
+        lazy_from_fns(get_entry, get_entry_names),
+        |root| async move {
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add(fio::DirentType::File, b"pid-42")
+                .add(fio::DirentType::File, b"pid-7");
+            assert_read_dirents!(root, 1000, expected.into_vec());
+
+            // Listing the directory does not construct any entries.
+            assert_eq!(constructed.load(Ordering::Relaxed), 0);
+
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+            open_as_vmo_file_assert_content!(&root, flags, "pid-42", "Process 42");
+            assert_eq!(constructed.load(Ordering::Relaxed), 1);
+
+            open_as_file_assert_err!(&root, flags, "missing", Status::NOT_FOUND);
+            assert_eq!(constructed.load(Ordering::Relaxed), 1);
+
+            assert_close!(root);
+        },
+    );
+}
+
+#[test]
+fn entries_from_fns_small_buffer() {
+    let get_entry_names = || {
+        vec![
+            ("b".to_string(), EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::File)),
+            ("a".to_string(), EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::File)),
+        ]
+    };
+
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE,
+        lazy_from_fns(|_name: &str| None, get_entry_names),
+        |root| async move {
+            {
+                let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+                // Entry header is 10 bytes + length of the name in bytes.
+                // (10 + 1) + (10 + 1) = 22
+                expected.add(fio::DirentType::Directory, b".").add(fio::DirentType::File, b"a");
+                assert_read_dirents!(root, 22, expected.into_vec());
+            }
+
+            {
+                let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+                expected.add(fio::DirentType::File, b"b");
+                assert_read_dirents!(root, 22, expected.into_vec());
+            }
+
+            assert_read_dirents!(root, 22, vec![]);
+            assert_close!(root);
+        },
+    );
+}
+
 #[test]
 fn read_dirents_small_buffer() {
     let entries = vec![
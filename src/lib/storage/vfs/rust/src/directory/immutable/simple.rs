@@ -30,3 +30,35 @@ pub fn simple() -> Arc<Simple> {
 pub fn simple_with_inode(inode: u64) -> Arc<Simple> {
     Simple::new(inode)
 }
+
+/// Creates an immutable empty "simple" directory, same as [`simple()`], but the directory will be
+/// reported as having `dirent_type` in the listings of its parent.  This is useful when the
+/// directory stands in for a node of a different kind, such as a service collection that should
+/// be listed as a service.  The directory itself is still served as a directory.
+pub fn simple_with_type(dirent_type: fio::DirentType) -> Arc<Simple> {
+    Simple::new_with_type(fio::INO_UNKNOWN, dirent_type)
+}
+
+/// Creates an immutable empty "simple" directory, same as [`simple()`], but the entries are listed
+/// in the order they were added, and `ReadDirents` resumes using a cookie assigned to each entry
+/// when it is added.  Removing entries does not invalidate a listing in progress: if the entry the
+/// listing was to resume at is removed, the listing continues with the next entry.
+pub fn simple_with_stable_cookies() -> Arc<Simple> {
+    Simple::new_with_stable_cookies(fio::INO_UNKNOWN)
+}
+
+/// Creates an immutable empty "simple" directory, same as [`simple()`], but the directory assigns
+/// increasing inode numbers, starting at `first_inode`, to the entries added to it.  Listings
+/// report the assigned numbers, rather than the inodes reported by the entries themselves.  An
+/// entry keeps its number for as long as it is in the directory, and an entry added under several
+/// names has the same number under all of them.
+pub fn simple_with_assigned_inodes(first_inode: u64) -> Arc<Simple> {
+    Simple::new_with_assigned_inodes(fio::INO_UNKNOWN, first_inode)
+}
+
+/// Creates an immutable empty "simple" directory, same as [`simple()`], but sealed: opening a name
+/// in this directory with `OpenFlags::CREATE` or `OpenFlags::CREATE_IF_ABSENT` fails with
+/// `ZX_ERR_ACCESS_DENIED`, rather than the error the connection would otherwise report.
+pub fn simple_sealed() -> Arc<Simple> {
+    Simple::new_sealed(fio::INO_UNKNOWN)
+}
@@ -17,14 +17,23 @@ use crate::{
 
 use crate::{
     directory::{
+        connection::io1::{DerivedConnection, DescribeMode},
         entry::{DirectoryEntry, EntryInfo},
+        entry_container::Directory,
         helper::DirectlyMutable,
-        immutable::{simple_with_inode, Simple},
-        test_utils::{run_server_client, DirentsSameInodeBuilder},
+        immutable::{
+            connection::io1::ImmutableConnection, simple_with_assigned_inodes, simple_with_inode,
+            simple_with_stable_cookies, simple_with_type, Simple,
+        },
+        test_utils::{run_server_client, test_server_client, DirentsSameInodeBuilder},
+        watchers::{WATCHER_OVERFLOW_STATUS, WATCHER_QUEUE_CAPACITY},
     },
     execution_scope::ExecutionScope,
-    file::vmo::asynchronous::{
-        read_only_static, read_write, simple_init_vmo_resizable_with_capacity,
+    file::{
+        mem_file,
+        vmo::asynchronous::{
+            read_only_static, read_write, simple_init_vmo_resizable_with_capacity,
+        },
     },
     path::Path,
     test_utils::node::open_get_proxy,
@@ -37,11 +46,15 @@ use {
     fuchsia_async::{self as fasync, TestExecutor},
     fuchsia_zircon::{
         sys::{self, ZX_OK},
-        Status,
+        AsHandleRef, MessageBuf, Signals, Status, Time,
     },
+    futures::StreamExt as _,
     libc::{S_IRUSR, S_IXUSR},
     static_assertions::assert_eq_size,
-    std::sync::{Arc, Mutex},
+    std::{
+        convert::TryInto as _,
+        sync::{Arc, Mutex},
+    },
     vfs_macros::pseudo_directory,
 };
 
@@ -123,6 +136,106 @@ fn open_empty_directory_with_describe() {
     });
 }
 
+#[test]
+fn open_empty_directory_with_connection_info() {
+    let exec = TestExecutor::new().expect("TestExecutor creation failed");
+    let scope = ExecutionScope::new();
+
+    let server = simple();
+
+    run_client(exec, || async move {
+        let (root, server_end) =
+            create_proxy::<fio::DirectoryMarker>().expect("Failed to create connection endpoints");
+
+        let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+        ImmutableConnection::create_connection_with_describe_mode(
+            scope,
+            server,
+            flags,
+            DescribeMode::ConnectionInfo,
+            server_end.into_channel().into(),
+        );
+
+        assert_event!(root, fio::DirectoryEvent::OnConnectionInfo { info }, {
+            assert_eq!(
+                info.representation,
+                Some(fio::Representation::Directory(fio::DirectoryInfo::EMPTY))
+            );
+        });
+    });
+}
+
+#[test]
+fn open_with_connection_info_reports_errors_in_epitaph() {
+    let exec = TestExecutor::new().expect("TestExecutor creation failed");
+    let scope = ExecutionScope::new();
+
+    let server = simple();
+
+    run_client(exec, || async move {
+        let (root, server_end) =
+            create_proxy::<fio::DirectoryMarker>().expect("Failed to create connection endpoints");
+
+        // APPEND is not valid for directories.
+        let flags =
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE | fio::OpenFlags::APPEND;
+        ImmutableConnection::create_connection_with_describe_mode(
+            scope,
+            server,
+            flags,
+            DescribeMode::ConnectionInfo,
+            server_end.into_channel().into(),
+        );
+
+        // `OnConnectionInfo` has no status, so there is no `OnOpen` event, only the epitaph.
+        match root.take_event_stream().next().await {
+            Some(Err(fidl::Error::ClientChannelClosed { status, .. })) => {
+                assert_eq!(status, Status::INVALID_ARGS)
+            }
+            event => panic!("Unexpected event: {:?}", event),
+        }
+    });
+}
+
+#[test]
+fn reopen_with_connection_info() {
+    let root = pseudo_directory! {
+        "file" => read_only_static(b"Content"),
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let (reopened, server_end) =
+            create_proxy::<fio::DirectoryMarker>().expect("Failed to create connection endpoints");
+        root.reopen(
+            fio::ConnectionOptions {
+                flags: Some(fio::ConnectionFlags::GET_CONNECTION_INFO),
+                ..fio::ConnectionOptions::EMPTY
+            },
+            server_end.into_channel(),
+        )
+        .expect("reopen failed");
+
+        assert_event!(reopened, fio::DirectoryEvent::OnConnectionInfo { info }, {
+            assert_eq!(
+                info.representation,
+                Some(fio::Representation::Directory(fio::DirectoryInfo::EMPTY))
+            );
+        });
+
+        // The new connection has the same rights as the original one.
+        let (status, flags) = reopened.get_flags().await.expect("get_flags failed");
+        assert_eq!(Status::from_raw(status), Status::OK);
+        assert_eq!(flags, fio::OpenFlags::RIGHT_READABLE);
+
+        let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+        expected.add(fio::DirentType::Directory, b".").add(fio::DirentType::File, b"file");
+        assert_read_dirents!(reopened, 1000, expected.into_vec());
+
+        assert_close!(reopened);
+        assert_close!(root);
+    });
+}
+
 #[test]
 fn clone() {
     let root = pseudo_directory! {
@@ -183,6 +296,100 @@ fn clone_inherit_access() {
     });
 }
 
+#[test]
+fn clone_same_rights_reports_granted_rights() {
+    let root = pseudo_directory! {
+        "file" => read_only_static(b"Content"),
+    };
+
+    let observed = Arc::new(Mutex::new(vec![]));
+
+    test_server_client(fio::OpenFlags::RIGHT_READABLE, root, {
+        let observed = observed.clone();
+        move |first_proxy| async move {
+            let second_proxy = clone_get_directory_proxy_assert_ok!(
+                &first_proxy,
+                fio::OpenFlags::CLONE_SAME_RIGHTS | fio::OpenFlags::DESCRIBE
+            );
+
+            {
+                let observed = observed.lock().unwrap();
+                assert_eq!(observed.len(), 1);
+                assert_eq!(
+                    observed[0] & (fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE),
+                    fio::OpenFlags::RIGHT_READABLE
+                );
+            }
+
+            let (status, flags) = second_proxy.get_flags().await.expect("get_flags failed");
+            assert_eq!(Status::from_raw(status), Status::OK);
+            assert!(flags.contains(fio::OpenFlags::RIGHT_READABLE));
+            assert!(!flags.intersects(fio::OpenFlags::RIGHT_WRITABLE));
+
+            assert_close!(first_proxy);
+            assert_close!(second_proxy);
+        }
+    })
+    .clone_observer(Arc::new(move |flags| observed.lock().unwrap().push(flags)))
+    .run();
+}
+
+#[test]
+fn max_connections_refuses_extra_connections() {
+    let root = pseudo_directory! {
+        "file" => read_only_static(b"Content"),
+    };
+
+    test_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+        // `root` is the first of the two allowed connections.
+        let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "file");
+        open_as_file_assert_err!(&root, flags, "file", Status::NO_RESOURCES);
+
+        // Closing a connection releases its slot.
+        assert_close!(file);
+        let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "file");
+        assert_read!(file, "Content");
+        assert_close!(file);
+
+        assert_close!(root);
+    })
+    .max_connections(2)
+    .run();
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn active_connection_count_returns_to_zero() {
+    let root = pseudo_directory! {
+        "file" => read_only_static(b"Content"),
+    };
+    let scope = ExecutionScope::build().count_connections().new();
+    assert_eq!(scope.active_connection_count(), Some(0));
+
+    let (proxy, server_end) = create_proxy::<fio::DirectoryMarker>().unwrap();
+    root.open(
+        scope.clone(),
+        fio::OpenFlags::RIGHT_READABLE,
+        0,
+        Path::dot(),
+        server_end.into_channel().into(),
+    );
+    let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+    let first = open_get_vmo_file_proxy_assert_ok!(&proxy, flags, "file");
+    let second = open_get_vmo_file_proxy_assert_ok!(&proxy, flags, "file");
+    assert_eq!(scope.active_connection_count(), Some(3));
+
+    drop(first);
+    drop(second);
+    drop(proxy);
+    scope.wait().await;
+    assert_eq!(scope.active_connection_count(), Some(0));
+
+    // Connections are only counted on request.
+    assert_eq!(ExecutionScope::new().active_connection_count(), None);
+}
+
 #[test]
 fn clone_cannot_increase_access() {
     let root = pseudo_directory! {
@@ -280,6 +487,43 @@ fn one_file_open_missing_not_found_handler() {
     });
 }
 
+#[test]
+fn one_file_open_missing_synthesized() {
+    let root = pseudo_directory! {
+        "file" => read_only_static(b"Content"),
+    };
+
+    root.clone().set_on_missing(Box::new(|name, _flags| {
+        if name.starts_with("default_") {
+            Some(read_only_static(b""))
+        } else {
+            None
+        }
+    }));
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+        {
+            let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "default_config");
+            assert_read!(file, "");
+            assert_close!(file);
+        }
+
+        open_as_file_assert_err!(&root, flags, "other", Status::NOT_FOUND);
+
+        // The synthesized entry stays in the directory.
+        let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+        expected
+            .add(fio::DirentType::Directory, b".")
+            .add(fio::DirentType::File, b"default_config")
+            .add(fio::DirentType::File, b"file");
+        assert_read_dirents!(root, 1000, expected.into_vec());
+
+        assert_close!(root);
+    });
+}
+
 #[test]
 fn one_file_open_missing() {
     let root = pseudo_directory! {
@@ -636,6 +880,51 @@ fn trailing_slash_means_directory() {
     });
 }
 
+#[test]
+fn open_directory_with_append() {
+    let root = pseudo_directory! {
+        "dir" => pseudo_directory! {},
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::RIGHT_READABLE
+            | fio::OpenFlags::DESCRIBE
+            | fio::OpenFlags::APPEND
+            | fio::OpenFlags::DIRECTORY;
+        open_as_directory_assert_err!(&root, flags, "dir", Status::INVALID_ARGS);
+        // A trailing slash also means the child must be a directory.
+        let flags = flags - fio::OpenFlags::DIRECTORY;
+        open_as_directory_assert_err!(&root, flags, "dir/", Status::INVALID_ARGS);
+
+        assert_close!(root);
+    });
+}
+
+#[test]
+fn open_directory_with_truncate_or_append_at_any_depth() {
+    let root = pseudo_directory! {
+        "dir" => pseudo_directory! {
+            "dir2" => pseudo_directory! {},
+        },
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        for flag in [fio::OpenFlags::TRUNCATE, fio::OpenFlags::APPEND] {
+            // Rejected by the parent connection when the flags say the child is a directory, and by
+            // the child connection otherwise.
+            for extra in [fio::OpenFlags::empty(), fio::OpenFlags::DIRECTORY] {
+                let flags =
+                    fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE | flag | extra;
+                for path in ["dir", "dir/", "dir/dir2", "dir/dir2/"] {
+                    open_as_directory_assert_err!(&root, flags, path, Status::INVALID_ARGS);
+                }
+            }
+        }
+
+        assert_close!(root);
+    });
+}
+
 #[test]
 fn no_dots_in_open() {
     let root = pseudo_directory! {
@@ -947,6 +1236,184 @@ fn read_dirents_very_small_buffer() {
     });
 }
 
+#[test]
+fn read_dirents_entry_too_large() {
+    let root = pseudo_directory! {
+        "a_very_long_entry_name" => read_only_static(b"Content"),
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| {
+        async move {
+            {
+                let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+                expected.add(fio::DirentType::Directory, b".");
+                assert_read_dirents!(root, 20, expected.into_vec());
+            }
+
+            // (10 + 22) = 32, so the next entry can not fit at all.
+            assert_read_dirents_err!(root, 20, Status::BUFFER_TOO_SMALL);
+
+            // The entry is not skipped, and is returned once the buffer is large enough.
+            {
+                let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+                expected.add(fio::DirentType::File, b"a_very_long_entry_name");
+                assert_read_dirents!(root, 32, expected.into_vec());
+            }
+
+            assert_read_dirents!(root, 100, vec![]);
+            assert_close!(root);
+        }
+    });
+}
+
+#[test]
+fn read_dirents_custom_dirent_type() {
+    let root = pseudo_directory! {
+        "dir" => pseudo_directory! { },
+        "svc" => simple_with_type(fio::DirentType::Service),
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+        expected
+            .add(fio::DirentType::Directory, b".")
+            .add(fio::DirentType::Directory, b"dir")
+            .add(fio::DirentType::Service, b"svc");
+
+        assert_read_dirents!(root, 1000, expected.into_vec());
+
+        // The directory itself is still served as a directory.
+        let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+        let svc = open_get_directory_proxy_assert_ok!(&root, flags, "svc");
+        let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+        expected.add(fio::DirentType::Directory, b".");
+        assert_read_dirents!(svc, 1000, expected.into_vec());
+
+        assert_close!(svc);
+        assert_close!(root);
+    });
+}
+
+#[test]
+fn read_dirents_stable_cookies_entry_removed() {
+    let root = simple_with_stable_cookies();
+    root.add_entry("uname", read_only_static(b"Fuchsia")).unwrap();
+    root.add_entry("etc", simple()).unwrap();
+    root.add_entry("files", read_only_static(b"Content")).unwrap();
+    root.add_entry("more", read_only_static(b"Content")).unwrap();
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root.clone(), |proxy| async move {
+        {
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            // Entries are listed in the order they were added.
+            expected
+                // (10 + 1) = 11
+                .add(fio::DirentType::Directory, b".")
+                // 11 + (10 + 5) = 26
+                .add(fio::DirentType::File, b"uname");
+            assert_read_dirents!(proxy, 26, expected.into_vec());
+        }
+
+        // The listing was going to resume at "etc".  Removing it should not invalidate the
+        // position, and the listing continues with the next entry.  An entry added during the
+        // listing gets a larger cookie, so it is listed last.
+        root.remove_entry("etc", true).unwrap().expect("\"etc\" was not found");
+        root.add_entry("aaa", read_only_static(b"Content")).unwrap();
+
+        {
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::File, b"files")
+                .add(fio::DirentType::File, b"more")
+                .add(fio::DirentType::File, b"aaa");
+            assert_read_dirents!(proxy, 100, expected.into_vec());
+        }
+
+        assert_read_dirents!(proxy, 100, vec![]);
+        assert_close!(proxy);
+    });
+}
+
+#[test]
+fn read_dirents_assigned_inodes() {
+    let root = simple_with_assigned_inodes(100);
+    let shared = read_only_static(b"Shared");
+    root.add_entry("uname", read_only_static(b"Fuchsia")).unwrap();
+    root.add_entry("etc", simple()).unwrap();
+    root.add_entry("files", shared.clone()).unwrap();
+    // Same entry under a second name.
+    root.add_entry("link", shared).unwrap();
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root.clone(), |proxy| async move {
+        let expected = || {
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add_with_inode(101, fio::DirentType::Directory, b"etc")
+                .add_with_inode(102, fio::DirentType::File, b"files")
+                .add_with_inode(102, fio::DirentType::File, b"link")
+                .add_with_inode(100, fio::DirentType::File, b"uname");
+            expected.into_vec()
+        };
+
+        // Inodes do not change between listings.
+        assert_read_dirents!(proxy, 1000, expected());
+        assert_rewind!(proxy);
+        assert_read_dirents!(proxy, 1000, expected());
+
+        // Removed inodes are not reused.
+        root.remove_entry("etc", true).unwrap().expect("\"etc\" was not found");
+        root.add_entry("etc", simple()).unwrap();
+        assert_rewind!(proxy);
+        {
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add_with_inode(103, fio::DirentType::Directory, b"etc")
+                .add_with_inode(102, fio::DirentType::File, b"files")
+                .add_with_inode(102, fio::DirentType::File, b"link")
+                .add_with_inode(100, fio::DirentType::File, b"uname");
+            assert_read_dirents!(proxy, 1000, expected.into_vec());
+        }
+
+        assert_close!(proxy);
+    });
+}
+
+#[test]
+fn get_attr_assigned_inodes() {
+    let root = simple_with_assigned_inodes(100);
+    root.add_entry("uname", read_only_static(b"Fuchsia")).unwrap();
+    root.add_entry("etc", simple()).unwrap();
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+        {
+            let uname = open_get_vmo_file_proxy_assert_ok!(&root, flags, "uname");
+            let (status, attrs) = uname.get_attr().await.expect("get_attr failed");
+            assert_eq!(Status::from_raw(status), Status::OK);
+            assert_eq!(attrs.id, 100);
+            assert_close!(uname);
+        }
+
+        {
+            let etc = open_get_directory_proxy_assert_ok!(&root, flags, "etc");
+            let (status, attrs) = etc.get_attr().await.expect("get_attr failed");
+            assert_eq!(Status::from_raw(status), Status::OK);
+            assert_eq!(attrs.id, 101);
+
+            // The directory also reports its inode for ".".
+            let mut expected = DirentsSameInodeBuilder::new(101);
+            expected.add(fio::DirentType::Directory, b".");
+            assert_read_dirents!(etc, 1000, expected.into_vec());
+            assert_close!(etc);
+        }
+
+        assert_close!(root);
+    });
+}
+
 #[test]
 fn read_dirents_rewind() {
     let root = pseudo_directory! {
@@ -1030,10 +1497,10 @@ fn add_entry_too_long_error() {
             "`add_entry()` succeeded for a name of {} bytes, when MAX_FILENAME is {}",
             name_len, max_filename
         ),
-        Err(Status::INVALID_ARGS) => (),
+        Err(Status::BAD_PATH) => (),
         Err(status) => panic!(
             "`add_entry()` failed for a name of {} bytes, with status {}.  Expected status is \
-             INVALID_ARGS.  MAX_FILENAME is {}.",
+             BAD_PATH.  MAX_FILENAME is {}.",
             name_len, status, max_filename
         ),
     }
@@ -1345,6 +1812,100 @@ fn in_tree_move_file() {
     });
 }
 
+#[test]
+fn in_tree_remove_entry_and_add_under_new_name() {
+    let etc;
+    let root = pseudo_directory! {
+        "etc" => pseudo_directory! {
+            etc ->
+            "fstab" => read_only_static(b"/dev/fs /"),
+            "passwd" => read_only_static(b"[redacted]"),
+        },
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+        let passwd = etc
+            .remove_entry("passwd", false)
+            .unwrap()
+            .expect("remove_entry() did not find 'passwd'");
+        assert!(matches!(etc.remove_entry("passwd", false), Ok(None)));
+
+        {
+            let etc_dir = open_get_directory_proxy_assert_ok!(&root, flags, "etc");
+
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected.add(fio::DirentType::Directory, b".").add(fio::DirentType::File, b"fstab");
+
+            assert_read_dirents!(etc_dir, 1000, expected.into_vec());
+            assert_close!(etc_dir);
+        }
+
+        etc.add_entry("shadow", passwd).unwrap();
+
+        {
+            let etc_dir = open_get_directory_proxy_assert_ok!(&root, flags, "etc");
+
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add(fio::DirentType::File, b"fstab")
+                .add(fio::DirentType::File, b"shadow");
+
+            assert_read_dirents!(etc_dir, 1000, expected.into_vec());
+            assert_close!(etc_dir);
+        }
+
+        open_as_vmo_file_assert_content!(&root, flags, "etc/shadow", "[redacted]");
+        open_as_file_assert_err!(&root, flags, "etc/passwd", Status::NOT_FOUND);
+
+        assert_close!(root);
+    });
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn subtree_size() {
+    let root = pseudo_directory! {
+        "a" => mem_file(b"abc".to_vec(), 100),
+        "empty" => pseudo_directory! {},
+        "dir" => pseudo_directory! {
+            "b" => mem_file(b"12345".to_vec(), 100),
+            "nested" => pseudo_directory! {
+                "c" => mem_file(b"0123456789".to_vec(), 100),
+            },
+        },
+        "vmo" => read_only_static(b"Not counted"),
+    };
+
+    assert_eq!(root.subtree_size().await, Ok(18));
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn subtree_size_counts_hard_links_once() {
+    let file = mem_file(b"abc".to_vec(), 100);
+    let root = pseudo_directory! {
+        "a" => file.clone(),
+        "dir" => pseudo_directory! {
+            "b" => file,
+        },
+    };
+
+    assert_eq!(root.subtree_size().await, Ok(3));
+}
+
+#[fasync::run_singlethreaded(test)]
+async fn subtree_size_cycle() {
+    let root = simple();
+    root.add_entry("a", mem_file(b"abc".to_vec(), 100)).unwrap();
+    root.add_entry("loop", root.clone()).unwrap();
+
+    assert_eq!(root.clone().subtree_size().await, Ok(3));
+
+    // Break the reference cycle, so the directory is freed.
+    root.remove_entry("loop", false).unwrap();
+}
+
 #[test]
 fn watch_empty() {
     run_server_client(fio::OpenFlags::RIGHT_READABLE, simple(), |root| async move {
@@ -1562,6 +2123,75 @@ fn watch_with_mask() {
     });
 }
 
+#[test]
+fn watch_overflow_closes_watcher() {
+    let root = simple();
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root.clone(), |proxy| async move {
+        let watcher_client = assert_watch!(proxy, fio::WatchMask::ADDED);
+
+        // The watcher is not read from, and its task can not run while the entries are added, so
+        // every event stays queued.  Adding entries must not block.
+        for i in 0..=WATCHER_QUEUE_CAPACITY {
+            root.add_entry(format!("file{}", i), read_only_static(b"Content")).unwrap();
+        }
+
+        // Queued events are dropped, and the epitaph is the only message.
+        let mut buf = MessageBuf::new();
+        watcher_client.recv_msg(&mut buf).await.unwrap();
+        let (bytes, handles) = buf.split();
+        assert!(handles.is_empty());
+        assert_eq!(bytes.len(), 24);
+        assert_eq!(u64::from_le_bytes(bytes[8..16].try_into().unwrap()), u64::MAX);
+        assert_eq!(
+            Status::from_raw(i32::from_le_bytes(bytes[16..20].try_into().unwrap())),
+            WATCHER_OVERFLOW_STATUS
+        );
+        assert_eq!(watcher_client.recv_msg(&mut buf).await, Err(Status::PEER_CLOSED));
+
+        assert_close!(proxy);
+    });
+}
+
+#[test]
+fn watch_full_channel_closes_watcher() {
+    let root = simple();
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root.clone(), |proxy| async move {
+        let watcher_client = assert_watch!(proxy, fio::WatchMask::ADDED);
+
+        // The watcher is never read from.  Entries are added in batches smaller than the queue, and
+        // the watcher task writes each batch into the channel before the next one, until the
+        // channel is full.
+        let batch = WATCHER_QUEUE_CAPACITY / 2;
+        let mut added = 0;
+        while watcher_client
+            .as_handle_ref()
+            .wait_handle(Signals::CHANNEL_PEER_CLOSED, Time::INFINITE_PAST)
+            .is_err()
+        {
+            assert!(added < 100 * batch, "the watcher channel was not closed");
+            for _ in 0..batch {
+                root.add_entry(format!("file{}", added), read_only_static(b"Content")).unwrap();
+                added += 1;
+            }
+            // Lets the watcher task run.
+            let (status, _) = proxy.get_attr().await.expect("get_attr failed");
+            assert_eq!(Status::from_raw(status), Status::OK);
+        }
+
+        // The events written before the channel filled up are still there, but not all of them.
+        let mut buf = MessageBuf::new();
+        let mut messages = 0;
+        while let Ok(()) = watcher_client.recv_msg(&mut buf).await {
+            messages += 1;
+        }
+        assert!(messages > 0 && messages < added, "{} messages for {} entries", messages, added);
+
+        assert_close!(proxy);
+    });
+}
+
 #[test]
 fn watch_addition_with_two_scopes() {
     let etc;
@@ -9,6 +9,9 @@
 pub mod entry_constructor;
 
 pub mod simple;
-pub use simple::simple;
+pub use simple::{
+    simple, simple_sealed, simple_with_assigned_inodes, simple_with_entries,
+    simple_with_rename_validator, simple_with_stable_cookies, simple_with_type,
+};
 
 pub mod connection;
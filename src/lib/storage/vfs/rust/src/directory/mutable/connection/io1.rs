@@ -5,13 +5,12 @@
 //! Connection to a directory that can be modified by the client though a FIDL connection.
 
 use crate::{
-    common::send_on_open_with_error,
     directory::{
         common::new_connection_validate_flags,
         connection::{
             io1::{
-                handle_requests, BaseConnection, BaseConnectionClient, ConnectionState,
-                DerivedConnection,
+                handle_requests, send_describe, send_describe_error, BaseConnection,
+                BaseConnectionClient, ConnectionState, DerivedConnection, DescribeMode,
             },
             util::OpenDirectory,
         },
@@ -19,7 +18,7 @@ use crate::{
         entry_container::MutableDirectory,
         mutable::entry_constructor::NewEntryType,
     },
-    execution_scope::ExecutionScope,
+    execution_scope::{ConnectionSlot, ExecutionScope},
     path::Path,
     registry::TokenRegistryClient,
 };
@@ -76,23 +75,29 @@ impl DerivedConnection for MutableConnection {
         MutableConnection { base: BaseConnection::<Self>::new(scope, directory, flags) }
     }
 
-    fn create_connection(
+    fn create_connection_with_describe_mode(
         scope: ExecutionScope,
         directory: Arc<Self::Directory>,
         flags: fio::OpenFlags,
+        describe_mode: DescribeMode,
         server_end: ServerEnd<fio::NodeMarker>,
     ) {
-        if let Ok((connection, requests)) =
-            Self::prepare_connection(scope.clone(), directory, flags, server_end)
-        {
-            // If we fail to send the task to the executor, it is probably shut down or is in the
-            // process of shutting down (this is the only error state currently).  So there is
-            // nothing for us to do - the connection will be closed automatically when the
-            // connection object is dropped.
-            let _ = scope.spawn_with_shutdown(move |shutdown| {
-                connection.handle_requests(requests, shutdown)
-            });
-        }
+        // Ensure we close the directory if we fail to create the connection.
+        let directory = OpenDirectory::new(directory);
+
+        // The channel wrappers register with the executor that creates them, so the connection is
+        // prepared inside the task, which runs on the executor of the scope.
+        //
+        // If we fail to send the task to the executor, it is probably shut down or is in the
+        // process of shutting down (this is the only error state currently).  So there is nothing
+        // for us to do - the connection will be closed automatically when `server_end` is dropped.
+        let _ = scope.clone().spawn_with_shutdown(move |shutdown| async move {
+            if let Ok((connection, requests, _slot)) =
+                Self::prepare_connection(scope, directory, flags, describe_mode, server_end)
+            {
+                connection.handle_requests(requests, shutdown).await
+            }
+        });
     }
 
     fn entry_not_found(
@@ -143,8 +148,9 @@ impl MutableConnection {
         server_end: ServerEnd<fio::NodeMarker>,
         shutdown: oneshot::Receiver<()>,
     ) {
-        if let Ok((connection, requests)) =
-            Self::prepare_connection(scope, directory, flags, server_end)
+        let directory = OpenDirectory::new(directory);
+        if let Ok((connection, requests, _slot)) =
+            Self::prepare_connection(scope, directory, flags, DescribeMode::NodeInfo, server_end)
         {
             connection.handle_requests(requests, shutdown).await;
         }
@@ -181,13 +187,11 @@ impl MutableConnection {
                 responder
                     .send(&mut self.base.directory.sync().await.map_err(zx::Status::into_raw))?;
             }
-            fio::DirectoryRequest::SetFlags { flags, responder } => {
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: flags={:?}", flags);
+            fio::DirectoryRequest::SetFlags { flags: _, responder } => {
+                responder.send(zx::Status::NOT_SUPPORTED.into_raw())?;
             }
-            fio::DirectoryRequest::UpdateAttributes { attributes, responder } => {
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: attributes={:?}", attributes);
+            fio::DirectoryRequest::UpdateAttributes { attributes: _, responder } => {
+                responder.send(&mut Err(zx::Status::NOT_SUPPORTED.into_raw()))?;
             }
             request @ (fio::DirectoryRequest::AddInotifyFilter { .. }
             | fio::DirectoryRequest::AdvisoryLock { .. }
@@ -313,19 +317,25 @@ impl MutableConnection {
 
     fn prepare_connection(
         scope: ExecutionScope,
-        directory: Arc<dyn MutableConnectionClient>,
+        directory: OpenDirectory<dyn MutableConnectionClient>,
         flags: fio::OpenFlags,
+        describe_mode: DescribeMode,
         server_end: ServerEnd<fio::NodeMarker>,
-    ) -> Result<(Self, fio::DirectoryRequestStream), Error> {
-        // Ensure we close the directory if we fail to prepare the connection.
-        let directory = OpenDirectory::new(directory);
-
+    ) -> Result<(Self, fio::DirectoryRequestStream, ConnectionSlot), Error> {
         // TODO(fxbug.dev/82054): These flags should be validated before prepare_connection is called
         // since at this point the directory resource has already been opened/created.
         let flags = match new_connection_validate_flags(flags) {
             Ok(updated) => updated,
             Err(status) => {
-                send_on_open_with_error(flags, server_end, status);
+                send_describe_error(flags, describe_mode, server_end, status);
+                bail!(status);
+            }
+        };
+
+        let slot = match scope.acquire_connection() {
+            Ok(slot) => slot,
+            Err(status) => {
+                send_describe_error(flags, describe_mode, server_end, status);
                 bail!(status);
             }
         };
@@ -335,11 +345,10 @@ impl MutableConnection {
                 .into_stream_and_control_handle()?;
 
         if flags.intersects(fio::OpenFlags::DESCRIBE) {
-            let mut info = fio::NodeInfo::Directory(fio::DirectoryObject);
-            control_handle.send_on_open_(zx::Status::OK.into_raw(), Some(&mut info))?;
+            send_describe(&control_handle, describe_mode)?;
         }
 
-        Ok((Self::new(scope, directory, flags), requests))
+        Ok((Self::new(scope, directory, flags), requests, slot))
     }
 
     async fn handle_requests(
@@ -11,6 +11,7 @@ mod tests;
 use crate::{
     directory::{
         entry::DirectoryEntry,
+        helper::DirectlyMutable,
         mutable::{
             connection,
             entry_constructor::{EntryConstructor, NewEntryType},
@@ -34,6 +35,66 @@ pub fn simple() -> Arc<Simple> {
     Simple::new(fio::INO_UNKNOWN)
 }
 
+/// Creates a mutable "simple" directory, same as [`simple()`], pre-populated with `entries`.  The
+/// directory can be modified afterwards, in the same way as a directory created with [`simple()`].
+///
+/// Fails with `ZX_ERR_ALREADY_EXISTS` if `entries` contains the same name more than once, or with
+/// any other error [`DirectlyMutable::add_entry()`] can return for an individual entry.
+pub fn simple_with_entries<Name, Entries>(entries: Entries) -> Result<Arc<Simple>, Status>
+where
+    Name: Into<String>,
+    Entries: IntoIterator<Item = (Name, Arc<dyn DirectoryEntry>)>,
+{
+    let dir = simple();
+    for (name, entry) in entries {
+        dir.add_entry(name, entry)?;
+    }
+    Ok(dir)
+}
+
+/// Creates a mutable empty "simple" directory, same as [`simple()`], but the directory will be
+/// reported as having `dirent_type` in the listings of its parent.
+pub fn simple_with_type(dirent_type: fio::DirentType) -> Arc<Simple> {
+    Simple::new_with_type(fio::INO_UNKNOWN, dirent_type)
+}
+
+/// Creates a mutable empty "simple" directory, same as [`simple()`], but the entries are listed in
+/// the order they were added, and `ReadDirents` resumes using a cookie assigned to each entry when
+/// it is added.  A listing in progress is not disturbed when entries are removed concurrently: if
+/// the entry the listing was to resume at is removed, the listing continues with the next entry.
+pub fn simple_with_stable_cookies() -> Arc<Simple> {
+    Simple::new_with_stable_cookies(fio::INO_UNKNOWN)
+}
+
+/// Creates a mutable empty "simple" directory, same as [`simple()`], but the directory assigns
+/// increasing inode numbers, starting at `first_inode`, to the entries added to it.  Listings
+/// report the assigned numbers, rather than the inodes reported by the entries themselves.  An
+/// entry keeps its number for as long as it is in the directory, including when a client renames
+/// it within the directory.
+pub fn simple_with_assigned_inodes(first_inode: u64) -> Arc<Simple> {
+    Simple::new_with_assigned_inodes(fio::INO_UNKNOWN, first_inode)
+}
+
+/// Creates a mutable empty "simple" directory, same as [`simple()`], but sealed: opening a name in
+/// this directory with `OpenFlags::CREATE` or `OpenFlags::CREATE_IF_ABSENT` fails with
+/// `ZX_ERR_ACCESS_DENIED`, even if the connection has write rights.  Existing entries can still be
+/// opened, and the server can still add and remove entries.
+pub fn simple_sealed() -> Arc<Simple> {
+    Simple::new_sealed(fio::INO_UNKNOWN)
+}
+
+/// Creates a mutable empty "simple" directory, same as [`simple()`], but every rename a client
+/// requests from or into this directory is first passed to `validator`, with the source and the
+/// destination names.  If `validator` returns an error, nothing is moved and the client receives
+/// that error.  Directories added to this directory use the same validator, unless they were
+/// created with a validator of their own.
+pub fn simple_with_rename_validator<F>(validator: F) -> Arc<Simple>
+where
+    F: Fn(&str, &str) -> Result<(), Status> + Send + Sync + 'static,
+{
+    Simple::new_with_rename_validator(fio::INO_UNKNOWN, validator)
+}
+
 /// Creates an [`EntryConstructor`] that will insert empty mutable directories when asked to create
 /// a directory and when asked to create a file will delegate to the `file_constructor` function.
 ///
@@ -8,7 +8,7 @@
 //! chance that the use cases covered by the unit tests for the immutable simple directory will
 //! fail for the mutable case.  So, this suite focuses on the mutable test cases.
 
-use super::simple;
+use super::{simple, simple_sealed, simple_with_entries, simple_with_rename_validator};
 
 // Macros are exported into the root of the crate.
 use crate::{
@@ -21,6 +21,9 @@ use crate::{
 
 use crate::{
     directory::{
+        entry::DirectoryEntry,
+        entry_container::MutableDirectory,
+        helper::DirectlyMutable,
         mutable::simple::tree_constructor,
         test_utils::{run_server_client, test_server_client, DirentsSameInodeBuilder},
     },
@@ -31,6 +34,7 @@ use crate::{
 use {
     fidl::Event,
     fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status,
     std::sync::{
         atomic::{AtomicU8, Ordering},
         Arc,
@@ -45,6 +49,50 @@ fn empty_directory() {
     });
 }
 
+#[test]
+fn with_entries() {
+    let root = simple_with_entries(vec![
+        ("fstab", read_only_static(b"/dev/fs /") as Arc<dyn DirectoryEntry>),
+        ("passwd", read_only_static(b"[redacted]")),
+    ])
+    .unwrap();
+
+    root.add_entry("motd", read_only_static(b"Hello")).unwrap();
+    assert_eq!(root.add_entry("fstab", read_only_static(b"")), Err(Status::ALREADY_EXISTS));
+
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root,
+        |proxy| async move {
+            let ro_flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+            open_as_vmo_file_assert_content!(&proxy, ro_flags, "fstab", "/dev/fs /");
+            open_as_vmo_file_assert_content!(&proxy, ro_flags, "passwd", "[redacted]");
+            open_as_vmo_file_assert_content!(&proxy, ro_flags, "motd", "Hello");
+
+            assert_unlink!(&proxy, "passwd");
+
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add(fio::DirentType::File, b"fstab")
+                .add(fio::DirentType::File, b"motd");
+            assert_read_dirents!(proxy, 1000, expected.into_vec());
+
+            assert_close!(proxy);
+        },
+    );
+}
+
+#[test]
+fn with_entries_name_collision() {
+    let result = simple_with_entries(vec![
+        ("fstab", read_only_static(b"/dev/fs /") as Arc<dyn DirectoryEntry>),
+        ("fstab", read_only_static(b"/dev/fs /")),
+    ]);
+    assert_eq!(result.err(), Some(Status::ALREADY_EXISTS));
+}
+
 #[test]
 fn unlink_entry() {
     let root = mut_pseudo_directory! {
@@ -280,6 +328,116 @@ fn rename_across_directories_twice() {
     .run();
 }
 
+#[test]
+fn add_and_remove_entry_with_watchers() {
+    let root = simple();
+
+    test_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root.clone(),
+        |proxy| async move {
+            let mask = fio::WatchMask::EXISTING | fio::WatchMask::ADDED | fio::WatchMask::REMOVED;
+            let watcher_client = assert_watch!(proxy, mask);
+            assert_watcher_one_message_watched_events!(watcher_client, { EXISTING, "." });
+
+            root.add_entry("fstab", read_only_static(b"/dev/fs /")).unwrap();
+            root.remove_entry("fstab", false).unwrap().expect("fstab was not found");
+
+            assert_watcher_one_message_watched_events!(watcher_client, { ADDED, "fstab" });
+            assert_watcher_one_message_watched_events!(watcher_client, { REMOVED, "fstab" });
+
+            drop(watcher_client);
+            assert_close!(proxy);
+        },
+    )
+    .run();
+}
+
+#[test]
+fn reopen_with_connection_info() {
+    let root = mut_pseudo_directory! {
+        "fstab" => read_only_static(b"/dev/fs /"),
+    };
+
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root,
+        |proxy| async move {
+            let (reopened, server_end) = fidl::endpoints::create_proxy::<fio::DirectoryMarker>()
+                .expect("Failed to create connection endpoints");
+            proxy
+                .reopen(
+                    fio::ConnectionOptions {
+                        flags: Some(fio::ConnectionFlags::GET_CONNECTION_INFO),
+                        ..fio::ConnectionOptions::EMPTY
+                    },
+                    server_end.into_channel(),
+                )
+                .expect("reopen failed");
+
+            assert_event!(reopened, fio::DirectoryEvent::OnConnectionInfo { info }, {
+                assert_eq!(
+                    info.representation,
+                    Some(fio::Representation::Directory(fio::DirectoryInfo::EMPTY))
+                );
+            });
+
+            // The new connection is mutable, with the same rights as the original one.
+            let (status, flags) = reopened.get_flags().await.expect("get_flags failed");
+            assert_eq!(Status::from_raw(status), Status::OK);
+            assert_eq!(flags, fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE);
+            assert_unlink!(&reopened, "fstab");
+
+            let ro_flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+            open_as_file_assert_err!(&proxy, ro_flags, "fstab", Status::NOT_FOUND);
+
+            assert_close!(reopened);
+            assert_close!(proxy);
+        },
+    );
+}
+
+#[test]
+fn rename_exchange_with_watchers() {
+    let root = mut_pseudo_directory! {
+        "fstab" => read_only_static(b"/dev/fs /"),
+        "passwd" => read_only_static(b"[redacted]"),
+    };
+
+    test_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root.clone(),
+        |proxy| async move {
+            let mask = fio::WatchMask::EXISTING | fio::WatchMask::ADDED | fio::WatchMask::REMOVED;
+            let watcher_client = assert_watch!(proxy, mask);
+            assert_watcher_one_message_watched_events!(
+                watcher_client,
+                { EXISTING, "." },
+                { EXISTING, "fstab" },
+                { EXISTING, "passwd" },
+            );
+
+            // A failed exchange does not produce any events.
+            assert_eq!(root.rename_exchange("fstab", "missing").await, Err(Status::NOT_FOUND));
+
+            root.rename_exchange("fstab", "passwd").await.expect("rename_exchange failed");
+
+            assert_watcher_one_message_watched_events!(watcher_client, { REMOVED, "fstab" });
+            assert_watcher_one_message_watched_events!(watcher_client, { REMOVED, "passwd" });
+            assert_watcher_one_message_watched_events!(watcher_client, { ADDED, "fstab" });
+            assert_watcher_one_message_watched_events!(watcher_client, { ADDED, "passwd" });
+
+            let ro_flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+            open_as_vmo_file_assert_content!(&proxy, ro_flags, "fstab", "[redacted]");
+            open_as_vmo_file_assert_content!(&proxy, ro_flags, "passwd", "/dev/fs /");
+
+            drop(watcher_client);
+            assert_close!(proxy);
+        },
+    )
+    .run();
+}
+
 /// This test should be exactly the same as the [`rename_within_directory`], but with watcher
 /// messages monitoring.  It should help narrow down the issue when something fails, by immediately
 /// showing if it is watchers related or not.
@@ -590,6 +748,81 @@ fn rename_fails_for_read_only_source() {
     .run();
 }
 
+#[test]
+fn rename_validator_applies_to_destination_and_subdirectories() {
+    let etc = simple_with_rename_validator(|_src, dst| {
+        if dst.starts_with('.') {
+            Err(Status::ACCESS_DENIED)
+        } else {
+            Ok(())
+        }
+    });
+    etc.add_entry(
+        "ssh",
+        mut_pseudo_directory! {
+            "config" => read_only_static(b"Port 22"),
+        },
+    )
+    .unwrap();
+    let root = mut_pseudo_directory! {
+        "etc" => etc,
+        "tmp" => mut_pseudo_directory! {
+            "fstab" => read_only_static(b"/dev/fs /"),
+        },
+    };
+
+    test_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root,
+        |proxy| async move {
+            let ro_flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+            let rw_flags = fio::OpenFlags::RIGHT_READABLE
+                | fio::OpenFlags::RIGHT_WRITABLE
+                | fio::OpenFlags::DESCRIBE;
+
+            let etc = open_get_directory_proxy_assert_ok!(&proxy, rw_flags, "etc");
+            let ssh = open_get_directory_proxy_assert_ok!(&proxy, rw_flags, "etc/ssh");
+            let tmp = open_get_directory_proxy_assert_ok!(&proxy, rw_flags, "tmp");
+
+            // The validator of the destination directory applies to renames from directories
+            // without a validator.
+            let etc_token = assert_get_token!(&etc);
+            assert_rename_err!(
+                &tmp,
+                "fstab",
+                Event::from(etc_token),
+                ".fstab",
+                Status::ACCESS_DENIED
+            );
+            let tmp_token = assert_get_token!(&tmp);
+            assert_rename!(&tmp, "fstab", Event::from(tmp_token), ".fstab");
+
+            // Subdirectories inherit the validator.
+            let ssh_token = assert_get_token!(&ssh);
+            assert_rename_err!(
+                &ssh,
+                "config",
+                Event::from(ssh_token),
+                ".config",
+                Status::ACCESS_DENIED
+            );
+            let ssh_token = assert_get_token!(&ssh);
+            assert_rename!(&ssh, "config", Event::from(ssh_token), "sshd_config");
+
+            open_as_vmo_file_assert_content!(&proxy, ro_flags, "tmp/.fstab", "/dev/fs /");
+            open_as_vmo_file_assert_content!(&proxy, ro_flags, "etc/ssh/sshd_config", "Port 22");
+            open_as_file_assert_err!(&proxy, ro_flags, "etc/ssh/.config", Status::NOT_FOUND);
+
+            assert_close!(ssh);
+            assert_close!(etc);
+            assert_close!(tmp);
+            assert_close!(proxy);
+        },
+    )
+    .token_registry(token_registry::Simple::new())
+    .run();
+}
+
 #[test]
 fn hardlink_not_supported() {
     let root = mut_pseudo_directory! {
@@ -693,6 +926,111 @@ fn create_directory() {
     .run();
 }
 
+#[test]
+fn create_if_absent_fails_for_existing_entry() {
+    let count = Arc::new(AtomicU8::new(0));
+
+    let constructor = tree_constructor(move |_parent, name| {
+        let index = count.fetch_add(1, Ordering::Relaxed);
+        let content = format!("{} - {}", name, index).into_bytes();
+        Ok(read_only_static(content.clone()))
+    });
+
+    let root = mut_pseudo_directory! {};
+
+    test_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root,
+        |proxy| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+            let create_flags = flags | fio::OpenFlags::CREATE;
+            let create_if_absent_flags = create_flags | fio::OpenFlags::CREATE_IF_ABSENT;
+
+            open_as_vmo_file_assert_content!(&proxy, create_if_absent_flags, "fstab", "fstab - 0");
+
+            // The entry now exists, and is not opened again.
+            open_as_file_assert_err!(
+                &proxy,
+                create_if_absent_flags,
+                "fstab",
+                Status::ALREADY_EXISTS
+            );
+
+            // Without `CREATE_IF_ABSENT` the existing entry is opened.
+            open_as_vmo_file_assert_content!(&proxy, create_flags, "fstab", "fstab - 0");
+
+            assert_close!(proxy);
+        },
+    )
+    .entry_constructor(constructor)
+    .run();
+}
+
+#[test]
+fn sealed_directory_rejects_create() {
+    let constructor = tree_constructor(|_parent, _name| panic!("No files should be created"));
+
+    let root = simple_sealed();
+    root.add_entry("passwd", read_only_static(b"[redacted]")).unwrap();
+
+    test_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root,
+        |proxy| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+            let create_flags = flags | fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::CREATE;
+
+            open_as_file_assert_err!(&proxy, create_flags, "fstab", Status::ACCESS_DENIED);
+            open_as_file_assert_err!(
+                &proxy,
+                create_flags | fio::OpenFlags::CREATE_IF_ABSENT,
+                "fstab",
+                Status::ACCESS_DENIED
+            );
+            open_as_file_assert_err!(&proxy, flags, "fstab", Status::NOT_FOUND);
+
+            open_as_vmo_file_assert_content!(&proxy, flags, "passwd", "[redacted]");
+
+            assert_close!(proxy);
+        },
+    )
+    .entry_constructor(constructor)
+    .run();
+}
+
+#[test]
+fn max_entries_rejects_new_names() {
+    let constructor = tree_constructor(|_parent, _name| panic!("No files should be created"));
+
+    let root = simple().with_max_entries(2);
+    root.add_entry("passwd", read_only_static(b"[redacted]")).unwrap();
+    root.add_entry("fstab", read_only_static(b"/dev/fs /")).unwrap();
+
+    assert_eq!(root.add_entry("motd", read_only_static(b"Hello")), Err(Status::NO_SPACE));
+    assert_eq!(root.get_entry("motd").err(), Some(Status::NOT_FOUND));
+    // Replacing an entry does not need more space.
+    root.add_entry_impl("fstab".to_string(), read_only_static(b"/dev/fs2 /"), true).unwrap();
+
+    test_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        root,
+        |proxy| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+            let create_flags = flags | fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::CREATE;
+
+            open_as_file_assert_err!(&proxy, create_flags, "motd", Status::NO_SPACE);
+            open_as_file_assert_err!(&proxy, flags, "motd", Status::NOT_FOUND);
+
+            open_as_vmo_file_assert_content!(&proxy, flags, "passwd", "[redacted]");
+            open_as_vmo_file_assert_content!(&proxy, flags, "fstab", "/dev/fs2 /");
+
+            assert_close!(proxy);
+        },
+    )
+    .entry_constructor(constructor)
+    .run();
+}
+
 #[test]
 fn create_two_levels_deep() {
     let count = Arc::new(AtomicU8::new(0));
@@ -0,0 +1,321 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A directory that merges the entries of a number of other directories.  See [`overlay`] for
+//! details.
+
+use crate::{
+    common::{rights_to_posix_mode_bits, send_on_open_with_error},
+    directory::{
+        connection::io1::DerivedConnection,
+        dirents_sink::{self, collect_entries},
+        entry::{DirectoryEntry, EntryInfo},
+        entry_container::{Directory, DirectoryWatcher},
+        immutable::connection::io1::ImmutableConnection,
+        read_dirents,
+        traversal_position::TraversalPosition,
+    },
+    execution_scope::ExecutionScope,
+    path::Path,
+};
+
+use {
+    async_trait::async_trait,
+    fidl::endpoints::{create_proxy, ServerEnd},
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status,
+    futures::StreamExt as _,
+    std::{collections::BTreeMap, sync::Arc},
+};
+
+/// Creates a directory that presents the union of the entries of `layers`.
+///
+/// Opening a path tries to open it in every layer in order, and uses the first layer where the
+/// open does not fail with `ZX_ERR_NOT_FOUND`.  So when more than one layer has an entry with the
+/// same name, the entry of the earliest layer is used, and the others are hidden.  As layers are
+/// asked to open the path, entries that only appear when opened, such as the ones provided by
+/// remotes, lazy directories or `on_missing` handlers, are found as well.  Every layer but the
+/// last is opened with `OPEN_FLAG_DESCRIBE` to learn the outcome, and the resulting connection is
+/// then cloned into the one the client asked for.
+///
+/// `ReadDirents` lists every name once, sorted, with the type reported by the layer that wins.
+/// Only the layers that are directories served by this crate can be listed; the entries of other
+/// layers, such as remotes, can be opened, but are not listed.
+///
+/// Layers are consulted on every `Open` and every `ReadDirents`, so changes to the layers are
+/// visible immediately.  But as the listing is recomputed for every `ReadDirents` call, entries
+/// added or removed while a listing is in progress may cause it to skip or repeat entries.
+///
+/// Clients can not add, remove or rename entries through the overlay, as it is not clear which
+/// layer such changes should go to, but can modify the entries themselves if the layer that
+/// provides them allows it.  Watchers are not supported either, as the overlay would need to
+/// track which layer provides each name to report changes correctly.
+pub fn overlay(layers: Vec<Arc<dyn DirectoryEntry>>) -> Arc<Overlay> {
+    Arc::new(Overlay { layers })
+}
+
+/// Directory that merges the entries of other directories.  See [`overlay`].
+pub struct Overlay {
+    layers: Vec<Arc<dyn DirectoryEntry>>,
+}
+
+impl Overlay {
+    /// Returns the merged entries of all the layers that can be listed, sorted by name.
+    async fn entries(&self) -> Result<Vec<(EntryInfo, String)>, Status> {
+        let mut merged = BTreeMap::new();
+        for layer in self.layers.iter().filter_map(|layer| layer.clone().into_directory()) {
+            for (info, name) in collect_entries(layer.as_ref()).await? {
+                let _ = merged.entry(name).or_insert(info);
+            }
+        }
+        Ok(merged.into_iter().map(|(name, info)| (info, name)).collect())
+    }
+
+    /// Opens `path` in the first layer where the open does not fail with `NOT_FOUND`.  The last
+    /// layer is given `server_end` directly, so its errors reach the client unchanged.
+    async fn open_in_layers(
+        &self,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        let (last, rest) = match self.layers.split_last() {
+            Some(layers) => layers,
+            None => {
+                send_on_open_with_error(flags, server_end, Status::NOT_FOUND);
+                return;
+            }
+        };
+
+        for layer in rest {
+            match probe_open(layer.clone(), scope.clone(), flags, mode, path.clone()).await {
+                Ok(node) => {
+                    // `CLONE_SAME_RIGHTS` also carries over `OPEN_FLAG_NODE_REFERENCE` and
+                    // `OPEN_FLAG_APPEND`.  Failure to send the request closes `server_end`, which
+                    // is all that can be done about it.
+                    let clone_flags =
+                        fio::OpenFlags::CLONE_SAME_RIGHTS | (flags & fio::OpenFlags::DESCRIBE);
+                    let _ = node.clone(clone_flags, server_end);
+                    return;
+                }
+                Err(Status::NOT_FOUND) => continue,
+                Err(status) => {
+                    send_on_open_with_error(flags, server_end, status);
+                    return;
+                }
+            }
+        }
+
+        last.clone().open(scope, flags, mode, path, server_end);
+    }
+}
+
+/// Opens `path` in `layer` with `OPEN_FLAG_DESCRIBE` added to `flags`, and returns the connection
+/// if the `OnOpen` event reports success, or the reported status otherwise.
+async fn probe_open(
+    layer: Arc<dyn DirectoryEntry>,
+    scope: ExecutionScope,
+    flags: fio::OpenFlags,
+    mode: u32,
+    path: Path,
+) -> Result<fio::NodeProxy, Status> {
+    let (node, server_end) = create_proxy::<fio::NodeMarker>().map_err(|_| Status::INTERNAL)?;
+    layer.open(scope, flags | fio::OpenFlags::DESCRIBE, mode, path, server_end);
+    match node.take_event_stream().next().await {
+        Some(Ok(fio::NodeEvent::OnOpen_ { s, .. })) => Status::ok(s).map(|()| node),
+        _ => Err(Status::PEER_CLOSED),
+    }
+}
+
+impl DirectoryEntry for Overlay {
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        if path.is_empty() {
+            ImmutableConnection::create_connection(scope, self, flags, server_end);
+            return;
+        }
+
+        scope.clone().spawn(async move {
+            self.open_in_layers(scope, flags, mode, path, server_end).await;
+        });
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::Directory)
+    }
+
+    fn into_directory(self: Arc<Self>) -> Option<Arc<dyn Directory>> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Directory for Overlay {
+    async fn read_dirents<'a>(
+        &'a self,
+        pos: &'a TraversalPosition,
+        sink: Box<dyn dirents_sink::Sink>,
+    ) -> Result<(TraversalPosition, Box<dyn dirents_sink::Sealed>), Status> {
+        let entries = self.entries().await?;
+        read_dirents::read_dirents(&entries, pos, sink).await
+    }
+
+    fn register_watcher(
+        self: Arc<Self>,
+        _scope: ExecutionScope,
+        _mask: fio::WatchMask,
+        _watcher: DirectoryWatcher,
+    ) -> Result<(), Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
+    fn unregister_watcher(self: Arc<Self>, _key: usize) {}
+
+    async fn get_attrs(&self) -> Result<fio::NodeAttributes, Status> {
+        Ok(fio::NodeAttributes {
+            mode: fio::MODE_TYPE_DIRECTORY
+                | rights_to_posix_mode_bits(/*r*/ true, /*w*/ false, /*x*/ true),
+            id: fio::INO_UNKNOWN,
+            content_size: 0,
+            storage_size: 0,
+            link_count: 1,
+            creation_time: 0,
+            modification_time: 0,
+        })
+    }
+
+    fn close(&self) -> Result<(), Status> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::overlay;
+
+    // Macros are exported into the root of the crate.
+    use crate::{
+        assert_close, assert_read, assert_read_dirents, open_as_file_assert_err,
+        open_get_vmo_file_proxy_assert_ok,
+    };
+
+    use crate::{
+        directory::{
+            entry::DirectoryEntry,
+            immutable::simple,
+            test_utils::{run_server_client, DirentsSameInodeBuilder},
+        },
+        file::vmo::asynchronous::read_only_static,
+        remote::remote,
+    };
+
+    use {
+        fidl_fuchsia_io as fio, fuchsia_zircon::Status, std::sync::Arc,
+        vfs_macros::pseudo_directory,
+    };
+
+    #[test]
+    fn open_and_list_merged_entries() {
+        let root = overlay(vec![
+            pseudo_directory! {
+                "both" => read_only_static(b"First layer"),
+                "first" => read_only_static(b"Only in first"),
+            },
+            pseudo_directory! {
+                "both" => read_only_static(b"Second layer"),
+                "nested" => pseudo_directory! {},
+                "second" => read_only_static(b"Only in second"),
+            },
+        ]);
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+            {
+                let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "second");
+                assert_read!(file, "Only in second");
+                assert_close!(file);
+            }
+
+            {
+                let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "both");
+                assert_read!(file, "First layer");
+                assert_close!(file);
+            }
+
+            open_as_file_assert_err!(&root, flags, "missing", Status::NOT_FOUND);
+
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add(fio::DirentType::File, b"both")
+                .add(fio::DirentType::File, b"first")
+                .add(fio::DirentType::Directory, b"nested")
+                .add(fio::DirentType::File, b"second");
+            assert_read_dirents!(root, 1000, expected.into_vec());
+
+            assert_close!(root);
+        });
+    }
+
+    #[test]
+    fn open_entries_that_are_not_listed() {
+        let remote_dir = pseudo_directory! {
+            "remote" => read_only_static(b"From remote"),
+        };
+        let generating = simple();
+        generating.clone().set_on_missing(Box::new(|name, _flags| {
+            if name == "generated" {
+                Some(read_only_static(b"Generated") as Arc<dyn DirectoryEntry>)
+            } else {
+                None
+            }
+        }));
+
+        let root = overlay(vec![
+            remote(move |scope, flags, mode, path, server_end| {
+                remote_dir.clone().open(scope, flags, mode, path, server_end)
+            }),
+            generating,
+            pseudo_directory! {
+                "generated" => read_only_static(b"Hidden"),
+                "last" => read_only_static(b"Only in last"),
+            },
+        ]);
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+            {
+                let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "remote");
+                assert_read!(file, "From remote");
+                assert_close!(file);
+            }
+
+            {
+                let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "generated");
+                assert_read!(file, "Generated");
+                assert_close!(file);
+            }
+
+            {
+                let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "last");
+                assert_read!(file, "Only in last");
+                assert_close!(file);
+            }
+
+            open_as_file_assert_err!(&root, flags, "missing", Status::NOT_FOUND);
+
+            assert_close!(root);
+        });
+    }
+}
@@ -33,6 +33,9 @@ pub struct Done {
 #[derive(PartialEq, Eq)]
 enum SinkState {
     NotCalled,
+    /// The first entry offered to the sink did not fit into `max_bytes`.  Returning an empty
+    /// buffer in this case would look like the end of the directory to the client, or cause it to
+    /// retry with the same buffer size forever, so `BUFFER_TOO_SMALL` is reported instead.
     DidNotFit,
     FitOne,
 }
@@ -100,7 +103,7 @@ pub async fn read_dirents<'a>(
         TraversalPosition::End => {
             return Ok((TraversalPosition::End, sink.seal()));
         }
-        TraversalPosition::Name(_) => {
+        TraversalPosition::Name(_) | TraversalPosition::Cookie(_) => {
             unreachable!("the VFS should never send this to us, since we never return it here");
         }
     };
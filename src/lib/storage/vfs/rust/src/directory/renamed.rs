@@ -0,0 +1,213 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A directory that exposes the entries of another directory under different names.  See
+//! [`renamed`] for details.
+
+use crate::{
+    directory::{
+        dirents_sink::{self, AppendResult},
+        entry::{DirectoryEntry, EntryInfo},
+        entry_container::{Directory, DirectoryWatcher},
+        forwarding::open_forwarded,
+        traversal_position::TraversalPosition,
+    },
+    execution_scope::ExecutionScope,
+    path::Path,
+};
+
+use {
+    async_trait::async_trait, fidl::endpoints::ServerEnd, fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status, std::sync::Arc,
+};
+
+type NameMap = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Creates a directory that exposes the entries of `inner` under transformed names.
+///
+/// `map` translates a name in the new directory, as used by the clients in `Open` requests, into
+/// the name of the corresponding entry in `inner`.  `unmap` is the inverse of `map`, and is used to
+/// translate the names of the `inner` entries for `ReadDirents`.  Names `map` returns `None` for
+/// can not be opened, failing with `ZX_ERR_NOT_FOUND`, and `inner` entries `unmap` returns `None`
+/// for are not listed.
+///
+/// The new directory is immutable, even when `inner` is not, and does not support watchers, as
+/// watcher events would report the `inner` names.
+pub fn renamed<Map, Unmap>(inner: Arc<dyn Directory>, map: Map, unmap: Unmap) -> Arc<Renamed>
+where
+    Map: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    Unmap: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    Arc::new(Renamed { inner, map: Arc::new(map), unmap: Arc::new(unmap) })
+}
+
+/// Directory that renames entries of another directory.  See [`renamed`].
+pub struct Renamed {
+    inner: Arc<dyn Directory>,
+    map: NameMap,
+    unmap: NameMap,
+}
+
+impl Renamed {
+    /// Translates `path`, as opened in this directory, into the path of the same entry in `inner`.
+    fn inner_path(&self, mut path: Path) -> Result<Path, Status> {
+        let inner_name = match path.next() {
+            Some(name) => (self.map)(name).ok_or(Status::NOT_FOUND)?,
+            None => return Err(Status::NOT_FOUND),
+        };
+
+        let is_dir = path.is_dir();
+        let rest = path.into_string();
+        let inner_path = match (rest.is_empty(), is_dir) {
+            (true, false) => inner_name,
+            (true, true) => format!("{}/", inner_name),
+            (false, _) => format!("{}/{}", inner_name, rest),
+        };
+        Path::validate_and_split(inner_path)
+    }
+}
+
+impl DirectoryEntry for Renamed {
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        open_forwarded(self, scope, flags, mode, path, server_end, |dir, path| async move {
+            let inner_path = dir.inner_path(path)?;
+            Ok((dir.inner.clone(), inner_path))
+        });
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        self.inner.entry_info()
+    }
+
+    fn into_directory(self: Arc<Self>) -> Option<Arc<dyn Directory>> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl Directory for Renamed {
+    async fn read_dirents<'a>(
+        &'a self,
+        pos: &'a TraversalPosition,
+        sink: Box<dyn dirents_sink::Sink>,
+    ) -> Result<(TraversalPosition, Box<dyn dirents_sink::Sealed>), Status> {
+        // The traversal position is owned by `inner`, so it is passed through unchanged.
+        self.inner
+            .read_dirents(pos, Box::new(RenamingSink { sink, unmap: self.unmap.clone() }))
+            .await
+    }
+
+    async fn get_entry(&self, name: &str) -> Result<Arc<dyn DirectoryEntry>, Status> {
+        let inner_name = (self.map)(name).ok_or(Status::NOT_FOUND)?;
+        self.inner.get_entry(&inner_name).await
+    }
+
+    fn register_watcher(
+        self: Arc<Self>,
+        _scope: ExecutionScope,
+        _mask: fio::WatchMask,
+        _watcher: DirectoryWatcher,
+    ) -> Result<(), Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
+    fn unregister_watcher(self: Arc<Self>, _key: usize) {}
+
+    async fn get_attrs(&self) -> Result<fio::NodeAttributes, Status> {
+        self.inner.get_attrs().await
+    }
+
+    fn close(&self) -> Result<(), Status> {
+        self.inner.close()
+    }
+
+    fn query_filesystem(&self) -> Result<fio::FilesystemInfo, Status> {
+        self.inner.query_filesystem()
+    }
+}
+
+/// Translates names of the entries produced by the inner directory before passing them to the
+/// sink provided by the connection.
+struct RenamingSink {
+    sink: Box<dyn dirents_sink::Sink>,
+    unmap: NameMap,
+}
+
+impl dirents_sink::Sink for RenamingSink {
+    fn append(self: Box<Self>, entry: &EntryInfo, name: &str) -> AppendResult {
+        let RenamingSink { sink, unmap } = *self;
+        let outer_name = if name == "." { Some(name.to_string()) } else { unmap(name) };
+        match outer_name {
+            Some(outer_name) => match sink.append(entry, &outer_name) {
+                AppendResult::Ok(sink) => AppendResult::Ok(Box::new(RenamingSink { sink, unmap })),
+                sealed @ AppendResult::Sealed(_) => sealed,
+            },
+            None => AppendResult::Ok(Box::new(RenamingSink { sink, unmap })),
+        }
+    }
+
+    fn seal(self: Box<Self>) -> Box<dyn dirents_sink::Sealed> {
+        self.sink.seal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::renamed;
+
+    // Macros are exported into the root of the crate.
+    use crate::{
+        assert_close, assert_event, assert_read, assert_read_dirents, open_as_file_assert_err,
+        open_get_proxy_assert, open_get_vmo_file_proxy_assert_ok,
+    };
+
+    use crate::{
+        directory::test_utils::{run_server_client, DirentsSameInodeBuilder},
+        file::vmo::asynchronous::read_only_static,
+    };
+
+    use {fidl_fuchsia_io as fio, fuchsia_zircon::Status, vfs_macros::pseudo_directory};
+
+    #[test]
+    fn strip_prefix() {
+        let inner = pseudo_directory! {
+            "foo" => read_only_static(b"Foo content"),
+            "bar" => read_only_static(b"Bar content"),
+        };
+        let root = renamed(
+            inner,
+            |name| name.strip_prefix("pub_").map(|name| name.to_string()),
+            |name| Some(format!("pub_{}", name)),
+        );
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+            {
+                let file = open_get_vmo_file_proxy_assert_ok!(&root, flags, "pub_foo");
+                assert_read!(file, "Foo content");
+                assert_close!(file);
+            }
+
+            // Inner names are not visible.
+            open_as_file_assert_err!(&root, flags, "foo", Status::NOT_FOUND);
+
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add(fio::DirentType::File, b"pub_bar")
+                .add(fio::DirentType::File, b"pub_foo");
+            assert_read_dirents!(root, 1000, expected.into_vec());
+
+            assert_close!(root);
+        });
+    }
+}
@@ -23,7 +23,10 @@ use crate::{
         },
     },
     execution_scope::ExecutionScope,
-    filesystem::{simple::SimpleFilesystem, Filesystem},
+    filesystem::{
+        simple::{SimpleDirectory, SimpleFilesystem},
+        Filesystem,
+    },
     path::Path,
     MAX_NAME_LENGTH,
 };
@@ -39,12 +42,15 @@ use {
         clone::Clone,
         collections::{
             btree_map::{self, Entry},
-            BTreeMap,
+            hash_map, BTreeMap, HashMap,
         },
         iter,
         marker::PhantomData,
         ops::DerefMut,
-        sync::{Arc, Mutex},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex, Weak,
+        },
     },
 };
 
@@ -62,19 +68,183 @@ where
     inner: Mutex<Inner>,
 
     // The inode for this directory. This should either be unique within this VFS, or INO_UNKNOWN.
-    inode: u64,
+    // Replaced by the number assigned by the parent, if the parent assigns inodes.
+    inode: AtomicU64,
+
+    // The type reported for this directory in the listings of its parent.  Usually
+    // `DirentType::Directory`.
+    dirent_type: fio::DirentType,
 
     _connection: PhantomData<Connection>,
 
     fs: SimpleFilesystem<Self>,
 
+    // The directory this directory was most recently added to.  Directories inherit the rename
+    // validator of their parent, see [`Simple::new_with_rename_validator()`].
+    parent: Mutex<Weak<Self>>,
+
+    // This directory, recorded as the parent of the directories added to it.
+    this: Weak<Self>,
+
     not_found_handler: Mutex<Option<Box<dyn FnMut(&str) + Send + Sync + 'static>>>,
+
+    on_missing: Mutex<Option<MissingEntryHandler>>,
+
+    // Set for directories that reject opens that would create entries in them.  See
+    // [`Simple::new_sealed()`].
+    sealed: bool,
 }
 
+/// Synthesizes an entry for a name that is not present in a directory.  See
+/// [`Simple::set_on_missing()`].
+pub type MissingEntryHandler =
+    Box<dyn Fn(&str, fio::OpenFlags) -> Option<Arc<dyn DirectoryEntry>> + Send + Sync + 'static>;
+
 struct Inner {
     entries: BTreeMap<String, Arc<dyn DirectoryEntry>>,
 
     watchers: Watchers,
+
+    // Set when the directory was created with stable cookies, in which case listings are produced
+    // in insertion order and resumed using `TraversalPosition::Cookie`.
+    cookies: Option<Cookies>,
+
+    // Set when the directory assigns inode numbers to its entries, in which case listings report
+    // the assigned numbers instead of the ones reported by the entries.
+    inodes: Option<Inodes>,
+
+    // Maximum number of entries in the directory.  See [`Simple::with_max_entries()`].
+    max_entries: Option<usize>,
+}
+
+impl Inner {
+    /// Fails with `ZX_ERR_NO_SPACE` if adding an entry named `name` would exceed the maximum number
+    /// of entries.  Replacing an existing entry does not change the number of entries.
+    fn check_space(&self, name: &str) -> Result<(), Status> {
+        match self.max_entries {
+            Some(max_entries)
+                if self.entries.len() >= max_entries && !self.entries.contains_key(name) =>
+            {
+                Err(Status::NO_SPACE)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn insert_entry(&mut self, name: String, entry: Arc<dyn DirectoryEntry>) {
+        if let Some(cookies) = &mut self.cookies {
+            cookies.assign(&name);
+        }
+        if let Some(inodes) = &mut self.inodes {
+            if let Some(inode) = inodes.acquire(&entry) {
+                entry.set_assigned_inode(inode);
+            }
+        }
+        if let Some(old) = self.entries.insert(name, entry) {
+            self.release_inode(&old);
+        }
+    }
+
+    /// Releases the inode of an entry that was removed under one of its names.
+    fn release_inode(&mut self, entry: &Arc<dyn DirectoryEntry>) {
+        if let Some(inodes) = &mut self.inodes {
+            inodes.release(entry);
+        }
+    }
+
+    /// Entry information reported for `entry` in the listings of this directory.
+    fn entry_info(&self, entry: &Arc<dyn DirectoryEntry>) -> EntryInfo {
+        let info = entry.entry_info();
+        match self.inodes.as_ref().and_then(|inodes| inodes.get(entry)) {
+            Some(inode) => EntryInfo::new(inode, info.type_()),
+            None => info,
+        }
+    }
+
+    fn release_cookie(&mut self, name: &str) {
+        if let Some(cookies) = &mut self.cookies {
+            cookies.release(name);
+        }
+    }
+}
+
+/// Cookies assigned to the entries of a directory, in the order the entries were added.
+#[derive(Default)]
+struct Cookies {
+    next: u64,
+    names: BTreeMap<u64, String>,
+    by_name: HashMap<String, u64>,
+}
+
+impl Cookies {
+    /// Assigns a new cookie to `name`, unless it already has one.  An entry that is overwritten
+    /// keeps its position in the listing.
+    fn assign(&mut self, name: &str) {
+        if self.by_name.contains_key(name) {
+            return;
+        }
+        let cookie = self.next;
+        self.next += 1;
+        let _ = self.names.insert(cookie, name.to_string());
+        let _ = self.by_name.insert(name.to_string(), cookie);
+    }
+
+    fn release(&mut self, name: &str) {
+        if let Some(cookie) = self.by_name.remove(name) {
+            let _ = self.names.remove(&cookie);
+        }
+    }
+}
+
+/// Inode numbers assigned to the entries of a directory, in the order the entries were added.
+/// Numbers are tracked per entry rather than per name, so an entry added under several names
+/// reports the same inode under all of them, and an entry keeps its inode when renamed within the
+/// directory.  A number is never reused, even after its entry is removed.
+struct Inodes {
+    next: u64,
+    // Inode and the number of names referring to the entry, keyed by the address of the entry.
+    // The directory holds the entry while it has any names, so the address can not be reused.
+    by_entry: HashMap<usize, (u64, usize)>,
+}
+
+impl Inodes {
+    fn new(first: u64) -> Self {
+        Inodes { next: first, by_entry: HashMap::new() }
+    }
+
+    fn key(entry: &Arc<dyn DirectoryEntry>) -> usize {
+        Arc::as_ptr(entry) as *const () as usize
+    }
+
+    /// Records a new name referring to `entry`, assigning the next inode if this is the first one.
+    /// Returns the inode if it was just assigned.
+    fn acquire(&mut self, entry: &Arc<dyn DirectoryEntry>) -> Option<u64> {
+        match self.by_entry.entry(Self::key(entry)) {
+            hash_map::Entry::Occupied(mut occupied) => {
+                occupied.get_mut().1 += 1;
+                None
+            }
+            hash_map::Entry::Vacant(vacant) => {
+                let inode = self.next;
+                self.next += 1;
+                vacant.insert((inode, 1));
+                Some(inode)
+            }
+        }
+    }
+
+    fn release(&mut self, entry: &Arc<dyn DirectoryEntry>) {
+        if let hash_map::Entry::Occupied(mut occupied) = self.by_entry.entry(Self::key(entry)) {
+            occupied.get_mut().1 -= 1;
+            if occupied.get().1 == 0 {
+                let _ = occupied.remove();
+            }
+        }
+    }
+
+    fn get(&self, entry: &Arc<dyn DirectoryEntry>) -> Option<u64> {
+        self.by_entry.get(&Self::key(entry)).map(|(inode, _)| *inode)
+    }
 }
 
 impl<Connection> Simple<Connection>
@@ -82,12 +252,90 @@ where
     Connection: DerivedConnection + 'static,
 {
     pub(super) fn new(inode: u64) -> Arc<Self> {
-        Arc::new(Simple {
-            inner: Mutex::new(Inner { entries: BTreeMap::new(), watchers: Watchers::new() }),
-            _connection: PhantomData,
+        Self::new_with_type(inode, fio::DirentType::Directory)
+    }
+
+    pub(super) fn new_with_type(inode: u64, dirent_type: fio::DirentType) -> Arc<Self> {
+        Self::new_impl(inode, dirent_type, None, None, false, SimpleFilesystem::new())
+    }
+
+    pub(super) fn new_with_stable_cookies(inode: u64) -> Arc<Self> {
+        Self::new_impl(
+            inode,
+            fio::DirentType::Directory,
+            Some(Cookies::default()),
+            None,
+            false,
+            SimpleFilesystem::new(),
+        )
+    }
+
+    /// Creates a directory that assigns increasing inode numbers, starting at `first_inode`, to
+    /// the entries added to it.  The assigned numbers are reported in the listings of the
+    /// directory, in place of the inodes reported by the entries themselves, and stay the same for
+    /// as long as the entry is in the directory.
+    pub(super) fn new_with_assigned_inodes(inode: u64, first_inode: u64) -> Arc<Self> {
+        Self::new_impl(
             inode,
-            fs: SimpleFilesystem::new(),
+            fio::DirentType::Directory,
+            None,
+            Some(Inodes::new(first_inode)),
+            false,
+            SimpleFilesystem::new(),
+        )
+    }
+
+    /// Creates a directory that passes the source and the destination names of every rename from
+    /// or into it to `validator`, before anything is moved.  An error returned by `validator`
+    /// fails the rename with that error.  Directories added to this directory, by the server or by
+    /// a client, use the same validator, unless they have one of their own.
+    pub(super) fn new_with_rename_validator<F>(inode: u64, validator: F) -> Arc<Self>
+    where
+        F: Fn(&str, &str) -> Result<(), Status> + Send + Sync + 'static,
+    {
+        Self::new_impl(
+            inode,
+            fio::DirentType::Directory,
+            None,
+            None,
+            false,
+            SimpleFilesystem::new_with_validator(validator),
+        )
+    }
+
+    /// Creates a directory that rejects all the opens that would create an entry in it, that is
+    /// opens of a single component path with `OpenFlags::CREATE` or `OpenFlags::CREATE_IF_ABSENT`,
+    /// with `ZX_ERR_ACCESS_DENIED`, regardless of the rights of the connection.  Existing entries
+    /// can still be opened, and the server can still add and remove entries.
+    pub(super) fn new_sealed(inode: u64) -> Arc<Self> {
+        Self::new_impl(inode, fio::DirentType::Directory, None, None, true, SimpleFilesystem::new())
+    }
+
+    fn new_impl(
+        inode: u64,
+        dirent_type: fio::DirentType,
+        cookies: Option<Cookies>,
+        inodes: Option<Inodes>,
+        sealed: bool,
+        fs: SimpleFilesystem<Self>,
+    ) -> Arc<Self> {
+        Arc::new_cyclic(|this| Simple {
+            inner: Mutex::new(Inner {
+                entries: BTreeMap::new(),
+                watchers: Watchers::new(),
+                cookies,
+                inodes,
+                max_entries: None,
+            }),
+            _connection: PhantomData,
+            inode: AtomicU64::new(inode),
+            dirent_type,
+            fs,
+            parent: Mutex::new(Weak::new()),
+            this: this.clone(),
             not_found_handler: Mutex::new(None),
+            on_missing: Mutex::new(None),
+            sealed,
         })
     }
 
@@ -110,20 +358,57 @@ where
                 Ok(entry.clone())
             }
             None => {
-                let entry = Connection::entry_not_found(
-                    scope.clone(),
-                    self.clone(),
-                    flags,
-                    mode,
-                    name,
-                    path,
-                )?;
-
-                let _ = this.entries.insert(name.to_string(), entry.clone());
+                let synthesized = if flags.intersects(fio::OpenFlags::CREATE) {
+                    None
+                } else {
+                    self.on_missing
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .and_then(|handler| handler(name, flags))
+                };
+
+                // Opens that would not add an entry still fail with `ZX_ERR_NOT_FOUND`.
+                if synthesized.is_some() || flags.intersects(fio::OpenFlags::CREATE) {
+                    this.check_space(name)?;
+                }
+
+                let entry = match synthesized {
+                    Some(entry) => entry,
+                    None => Connection::entry_not_found(
+                        scope.clone(),
+                        self.clone(),
+                        flags,
+                        mode,
+                        name,
+                        path,
+                    )?,
+                };
+
+                this.insert_entry(name.to_string(), entry.clone());
+                self.adopt(&entry);
                 Ok(entry)
             }
         }
     }
+
+    /// Records this directory as the parent of `entry`, if `entry` is a directory of the same
+    /// type.
+    fn adopt(&self, entry: &Arc<dyn DirectoryEntry>) {
+        if let Ok(dir) = entry.clone().into_any().downcast::<Self>() {
+            *dir.parent.lock().unwrap() = self.this.clone();
+        }
+    }
+
+    /// Clears the parent of `entry`, if it is this directory.
+    fn orphan(&self, entry: &Arc<dyn DirectoryEntry>) {
+        if let Ok(dir) = entry.clone().into_any().downcast::<Self>() {
+            let mut parent = dir.parent.lock().unwrap();
+            if parent.ptr_eq(&self.this) {
+                *parent = Weak::new();
+            }
+        }
+    }
     /// Registers a given function to be used when an item that is not present is opened. Typically
     /// used for logging.
     pub fn set_not_found_handler(
@@ -134,6 +419,29 @@ where
         this.replace(handler);
     }
 
+    /// Limits the number of entries in this directory to `max_entries`, modeling a directory with
+    /// a quota.  Once the limit is reached, adding an entry with a new name fails with
+    /// `ZX_ERR_NO_SPACE`, whether the entry is added by the server with
+    /// [`DirectlyMutable::add_entry()`], created by a client, or renamed into this directory.
+    /// Entries that are already in the directory are not removed if there are more of them than
+    /// `max_entries`.  [`Self::get_or_insert()`] is not limited.
+    pub fn with_max_entries(self: Arc<Self>, max_entries: usize) -> Arc<Self> {
+        self.inner.lock().unwrap().max_entries = Some(max_entries);
+        self
+    }
+
+    /// Registers a function that is consulted when a name that is not present is opened without
+    /// `OpenFlags::CREATE`, before the open fails with `ZX_ERR_NOT_FOUND`.  If the function
+    /// returns an entry, it is added to the directory under the opened name, and the open
+    /// proceeds as if the entry was always there.  Opens with `OpenFlags::CREATE` are handled as
+    /// usual.
+    ///
+    /// The function is called with the directory locked, so it must not access the directory.
+    pub fn set_on_missing(self: Arc<Self>, handler: MissingEntryHandler) {
+        let mut this = self.on_missing.lock().unwrap();
+        this.replace(handler);
+    }
+
     /// Returns the entry identified by `name`.
     pub fn get_entry(&self, name: &str) -> Result<Arc<dyn DirectoryEntry>, Status> {
         assert_eq_size!(u64, usize);
@@ -154,13 +462,14 @@ where
         name: String,
         f: impl FnOnce() -> Arc<T>,
     ) -> Arc<dyn DirectoryEntry> {
-        self.inner
-            .lock()
-            .unwrap()
-            .entries
-            .entry(name)
-            .or_insert_with(|| f() as Arc<dyn DirectoryEntry>)
-            .clone()
+        let mut this = self.inner.lock().unwrap();
+        if let Some(entry) = this.entries.get(&name) {
+            return entry.clone();
+        }
+        let entry = f() as Arc<dyn DirectoryEntry>;
+        this.insert_entry(name, entry.clone());
+        self.adopt(&entry);
+        entry
     }
 
     /// Filters and maps all directory entries.  It is similar to std::iter::Iterator::filter_map
@@ -201,6 +510,14 @@ where
             }
         };
 
+        if self.sealed
+            && path_ref.is_empty()
+            && flags.intersects(fio::OpenFlags::CREATE | fio::OpenFlags::CREATE_IF_ABSENT)
+        {
+            send_on_open_with_error(flags, server_end, Status::ACCESS_DENIED);
+            return;
+        }
+
         // Create copies so if this fails to open we can call the not found handler
         let ref_copy = self.clone();
         let name_copy = name.to_string();
@@ -230,7 +547,15 @@ where
     }
 
     fn entry_info(&self) -> EntryInfo {
-        EntryInfo::new(self.inode, fio::DirentType::Directory)
+        EntryInfo::new(self.inode.load(Ordering::Relaxed), self.dirent_type)
+    }
+
+    fn set_assigned_inode(&self, inode: u64) {
+        self.inode.store(inode, Ordering::Relaxed);
+    }
+
+    fn into_directory(self: Arc<Self>) -> Option<Arc<dyn Directory>> {
+        Some(self)
     }
 }
 
@@ -248,9 +573,52 @@ where
 
         let this = self.inner.lock().unwrap();
 
+        if let Some(cookies) = &this.cookies {
+            let (mut sink, first_cookie) = match pos {
+                TraversalPosition::Start => {
+                    let dot = EntryInfo::new(
+                        self.inode.load(Ordering::Relaxed),
+                        fio::DirentType::Directory,
+                    );
+                    match sink.append(&dot, ".") {
+                        AppendResult::Ok(sink) => (sink, 0),
+                        AppendResult::Sealed(sealed) => {
+                            let new_pos = match cookies.names.keys().next() {
+                                None => TraversalPosition::End,
+                                Some(cookie) => TraversalPosition::Cookie(*cookie),
+                            };
+                            return Ok((new_pos, sealed.into()));
+                        }
+                    }
+                }
+
+                TraversalPosition::Cookie(cookie) => (sink, *cookie),
+
+                TraversalPosition::Name(_) | TraversalPosition::Index(_) => unreachable!(),
+
+                TraversalPosition::End => return Ok((TraversalPosition::End, sink.seal().into())),
+            };
+
+            // Entries removed since the previous call no longer have cookies, so a traversal
+            // positioned on one of them just continues with the next entry.
+            for (cookie, name) in cookies.names.range(first_cookie..) {
+                let entry = &this.entries[name];
+                match sink.append(&this.entry_info(entry), name) {
+                    AppendResult::Ok(new_sink) => sink = new_sink,
+                    AppendResult::Sealed(sealed) => {
+                        return Ok((TraversalPosition::Cookie(*cookie), sealed.into()));
+                    }
+                }
+            }
+
+            return Ok((TraversalPosition::End, sink.seal().into()));
+        }
+
         let (mut sink, entries_iter) = match pos {
             TraversalPosition::Start => {
-                match sink.append(&EntryInfo::new(self.inode, fio::DirentType::Directory), ".") {
+                let dot =
+                    EntryInfo::new(self.inode.load(Ordering::Relaxed), fio::DirentType::Directory);
+                match sink.append(&dot, ".") {
                     AppendResult::Ok(sink) => {
                         // I wonder why, but rustc can not infer T in
                         //
@@ -282,13 +650,13 @@ where
                 (sink, this.entries.range::<String, _>(next_name.to_owned()..))
             }
 
-            TraversalPosition::Index(_) => unreachable!(),
+            TraversalPosition::Index(_) | TraversalPosition::Cookie(_) => unreachable!(),
 
             TraversalPosition::End => return Ok((TraversalPosition::End, sink.seal().into())),
         };
 
         for (name, entry) in entries_iter {
-            match sink.append(&entry.entry_info(), &name) {
+            match sink.append(&this.entry_info(entry), &name) {
                 AppendResult::Ok(new_sink) => sink = new_sink,
                 AppendResult::Sealed(sealed) => {
                     return Ok((TraversalPosition::Name(name.clone()), sealed.into()));
@@ -299,6 +667,11 @@ where
         Ok((TraversalPosition::End, sink.seal().into()))
     }
 
+    async fn get_entry(&self, name: &str) -> Result<Arc<dyn DirectoryEntry>, Status> {
+        // Resolves to the inherent method, which does the lookup.
+        self.get_entry(name)
+    }
+
     fn register_watcher(
         self: Arc<Self>,
         scope: ExecutionScope,
@@ -332,7 +705,7 @@ where
                     /*w*/ Connection::MUTABLE,
                     /*x*/ true,
                 ),
-            id: self.inode,
+            id: self.inode.load(Ordering::Relaxed),
             content_size: 0,
             storage_size: 0,
             link_count: 1,
@@ -356,12 +729,14 @@ where
         entry: Arc<dyn DirectoryEntry>,
         overwrite: bool,
     ) -> Result<(), Status> {
+        // Names are validated here, as `ReadDirents` can not report an invalid name that is
+        // already in the directory.
         assert_eq_size!(u64, usize);
         if name.len() as u64 > MAX_NAME_LENGTH {
-            return Err(Status::INVALID_ARGS);
+            return Err(Status::BAD_PATH);
         }
         if name.contains('/') {
-            return Err(Status::INVALID_ARGS);
+            return Err(Status::BAD_PATH);
         }
 
         let mut this = self.inner.lock().unwrap();
@@ -369,10 +744,12 @@ where
         if !overwrite && this.entries.contains_key(&name) {
             return Err(Status::ALREADY_EXISTS);
         }
+        this.check_space(&name)?;
 
         this.watchers.send_event(&mut SingleNameEventProducer::added(&name));
 
-        let _ = this.entries.insert(name, entry);
+        this.insert_entry(name, entry.clone());
+        self.adopt(&entry);
         Ok(())
     }
 
@@ -397,7 +774,10 @@ where
                     Err(Status::NOT_DIR)
                 } else {
                     let (key, value) = occupied.remove_entry();
+                    this.release_cookie(&key);
+                    this.release_inode(&value);
                     this.watchers.send_event(&mut SingleNameEventProducer::removed(&key));
+                    self.orphan(&value);
                     Ok(Some(value))
                 }
             }
@@ -426,7 +806,10 @@ where
 
         watchers.send_event(&mut SingleNameEventProducer::removed(&src));
 
-        let _ = map_entry.remove();
+        let entry = map_entry.remove();
+        this.release_cookie(&src);
+        this.release_inode(&entry);
+        self.orphan(&entry);
         Ok(())
     }
 
@@ -441,11 +824,14 @@ where
 
         let mut this = self.inner.lock().unwrap();
 
+        // Checked before `from` is called, as it removes the entry from the source directory.
+        this.check_space(&dst)?;
         let entry = from()?;
 
         this.watchers.send_event(&mut SingleNameEventProducer::added(&dst));
 
-        let _ = this.entries.insert(dst, entry);
+        this.insert_entry(dst, entry.clone());
+        self.adopt(&entry);
         Ok(())
     }
 
@@ -489,8 +875,37 @@ where
             None => return Err(Status::NOT_FOUND),
             Some(entry) => entry,
         };
+        this.release_cookie(&src);
 
-        let _ = this.entries.insert(dst, entry);
+        // The entry keeps its inode, as the new name is recorded before the old one is released.
+        this.insert_entry(dst, entry.clone());
+        this.release_inode(&entry);
+        Ok(())
+    }
+
+    fn exchange_within(&self, a: String, b: String) -> Result<(), Status> {
+        if a.len() as u64 >= MAX_NAME_LENGTH || b.len() as u64 >= MAX_NAME_LENGTH {
+            return Err(Status::INVALID_ARGS);
+        }
+
+        let mut this = self.inner.lock().unwrap();
+
+        let entry_b = match (this.entries.contains_key(&a), this.entries.get(&b)) {
+            (true, Some(entry_b)) => entry_b.clone(),
+            _ => return Err(Status::NOT_FOUND),
+        };
+
+        // Same as in `rename_within`, both names are reported as removed and then added back,
+        // while holding the lock, so the watchers can not see the directory in an intermediate
+        // state.
+        this.watchers.send_event(&mut SingleNameEventProducer::removed(&a));
+        this.watchers.send_event(&mut SingleNameEventProducer::removed(&b));
+        this.watchers.send_event(&mut SingleNameEventProducer::added(&a));
+        this.watchers.send_event(&mut SingleNameEventProducer::added(&b));
+
+        // Entries keep their positions in the listing, as the names do not change.
+        let entry_a = std::mem::replace(this.entries.get_mut(&a).unwrap(), entry_b);
+        this.entries.insert(b, entry_a);
         Ok(())
     }
 
@@ -499,10 +914,24 @@ where
     }
 }
 
+impl<Connection> SimpleDirectory for Simple<Connection>
+where
+    Connection: DerivedConnection + 'static,
+{
+    fn parent(&self) -> Option<Arc<Self>> {
+        self.parent.lock().unwrap().upgrade()
+    }
+
+    fn filesystem(&self) -> &SimpleFilesystem<Self> {
+        &self.fs
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::file::vmo::read_only_static;
+    use crate::{directory::entry_container::MutableDirectory, file::vmo::read_only_static};
+    use fuchsia_async as fasync;
 
     #[test]
     fn name_with_path_separator() {
@@ -510,11 +939,50 @@ mod tests {
         let status = dir
             .add_entry("path/with/separators", read_only_static(b"test"))
             .expect_err("add entry with path separator should fail");
-        assert_eq!(status, Status::INVALID_ARGS);
+        assert_eq!(status, Status::BAD_PATH);
         assert_eq!(
             dir.add_entry("path_without_separators", read_only_static(b"test")),
             Ok(()),
             "add entry with valid filename should succeed"
         );
     }
+
+    #[test]
+    fn name_length() {
+        let dir = crate::directory::mutable::simple();
+        let too_long = "a".repeat(MAX_NAME_LENGTH as usize + 1);
+        assert_eq!(dir.add_entry(too_long, read_only_static(b"test")), Err(Status::BAD_PATH));
+        assert_eq!(
+            dir.add_entry("a".repeat(MAX_NAME_LENGTH as usize), read_only_static(b"test")),
+            Ok(()),
+            "add entry with a name of MAX_FILENAME bytes should succeed"
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn rename_exchange() {
+        let dir = crate::directory::mutable::simple();
+        let a = read_only_static(b"a");
+        let b = read_only_static(b"b");
+        dir.add_entry("a", a.clone()).unwrap();
+        dir.add_entry("b", b.clone()).unwrap();
+
+        dir.rename_exchange("a", "b").await.expect("rename_exchange failed");
+
+        // Compare the data pointers only, as vtable pointers are not guaranteed to be the same
+        // across casts.
+        let ptr = |entry: Arc<dyn DirectoryEntry>| {
+            entry.as_ref() as *const dyn DirectoryEntry as *const u8
+        };
+        assert!(ptr(dir.get_entry("a").unwrap()) == ptr(b));
+        assert!(ptr(dir.get_entry("b").unwrap()) == ptr(a));
+
+        assert_eq!(dir.rename_exchange("a", "missing").await, Err(Status::NOT_FOUND));
+        assert_eq!(dir.rename_exchange("missing", "b").await, Err(Status::NOT_FOUND));
+
+        // Same limit as the other renames.
+        let long_name = "a".repeat(MAX_NAME_LENGTH as usize);
+        assert_eq!(dir.rename_exchange("a", &long_name).await, Err(Status::INVALID_ARGS));
+        assert_eq!(dir.rename_exchange(&long_name, "b").await, Err(Status::INVALID_ARGS));
+    }
 }
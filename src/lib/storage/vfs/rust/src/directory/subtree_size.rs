@@ -0,0 +1,78 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Implementation of [`crate::directory::entry_container::Directory::subtree_size`].
+
+use crate::directory::{dirents_sink::collect_entries, entry_container::Directory};
+
+use {
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status,
+    std::{collections::HashSet, sync::Arc},
+};
+
+/// Walks the subtree rooted at `root` through [`Directory::get_entry`], and sums the sizes of all
+/// the files found.
+///
+/// Traversal uses an explicit stack of directories instead of recursion, so deep trees do not
+/// exhaust the stack.  Every entry is visited once, which protects against cycles and against
+/// counting hard links more than once.
+pub(super) async fn subtree_size(root: Arc<dyn Directory>) -> Result<u64, Status> {
+    let mut visited = Visited::default();
+    let _ = visited.insert(root.entry_info().inode(), address(&root));
+
+    let mut total = 0u64;
+    // Failures to list the root directory are reported to the caller, while failures in any of the
+    // descendants only cause that descendant to be skipped.
+    let mut stack = vec![(root, true)];
+    while let Some((dir, is_root)) = stack.pop() {
+        let entries = match collect_entries(dir.as_ref()).await {
+            Ok(entries) => entries,
+            Err(status) if is_root => return Err(status),
+            Err(_) => continue,
+        };
+
+        for (info, name) in entries {
+            let entry = match dir.get_entry(&name).await {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if !visited.insert(info.inode(), address(&entry)) {
+                continue;
+            }
+
+            if let Some(file) = entry.clone().into_file() {
+                if let Ok(size) = file.get_size().await {
+                    total = total.saturating_add(size);
+                }
+            } else if let Some(child) = entry.into_directory() {
+                stack.push((child, false));
+            }
+        }
+    }
+
+    Ok(total)
+}
+
+/// Entries seen by the traversal.  An entry is identified by the inode number its directory
+/// lists, when one is known, and by the address of the entry itself.  Either one matching a
+/// previous entry makes it a repeat.
+#[derive(Default)]
+struct Visited {
+    inodes: HashSet<u64>,
+    entries: HashSet<usize>,
+}
+
+impl Visited {
+    /// Records an entry as visited.  Returns `false` if it was visited before.
+    fn insert(&mut self, inode: u64, address: usize) -> bool {
+        let new_entry = self.entries.insert(address);
+        let new_inode = inode == fio::INO_UNKNOWN || self.inodes.insert(inode);
+        new_entry && new_inode
+    }
+}
+
+fn address<T: ?Sized>(entry: &Arc<T>) -> usize {
+    Arc::as_ptr(entry) as *const () as usize
+}
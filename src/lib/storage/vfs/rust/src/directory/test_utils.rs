@@ -23,10 +23,15 @@ use crate::{
 };
 
 use {
-    byteorder::{LittleEndian, WriteBytesExt},
+    byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt},
     fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status,
     futures::Future,
-    std::{convert::TryInto as _, io::Write, sync::Arc},
+    std::{
+        convert::TryInto as _,
+        io::{Read, Write},
+        sync::Arc,
+    },
 };
 
 pub use run::{run_client, test_client};
@@ -71,6 +76,12 @@ impl DirentsSameInodeBuilder {
     }
 
     pub fn add(&mut self, type_: fio::DirentType, name: &[u8]) -> &mut Self {
+        self.add_with_inode(self.inode, type_, name)
+    }
+
+    /// Same as [`Self::add`], but the entry is expected to report `inode`, rather than the inode
+    /// this builder was created with.
+    pub fn add_with_inode(&mut self, inode: u64, type_: fio::DirentType, name: &[u8]) -> &mut Self {
         assert!(
             name.len() <= fio::MAX_FILENAME as usize,
             "Expected entry name should not exceed MAX_FILENAME ({}) bytes.\n\
@@ -81,7 +92,7 @@ impl DirentsSameInodeBuilder {
             name.len()
         );
 
-        self.expected.write_u64::<LittleEndian>(self.inode).unwrap();
+        self.expected.write_u64::<LittleEndian>(inode).unwrap();
         self.expected.write_u8(name.len().try_into().unwrap()).unwrap();
         self.expected.write_u8(type_.into_primitive()).unwrap();
         self.expected.write_all(name).unwrap();
@@ -94,6 +105,31 @@ impl DirentsSameInodeBuilder {
     }
 }
 
+/// Calls `ReadDirents` on `proxy` until the directory has no more entries, starting at the current
+/// position of the connection, and returns the names and types of the entries in the order they
+/// were listed.  Types are raw [`fio::DirentType`] values.  Panics if any of the calls fail.
+pub async fn read_all_dirents(proxy: &fio::DirectoryProxy) -> Vec<(String, u8)> {
+    let mut entries = vec![];
+    loop {
+        let (status, buf) =
+            proxy.read_dirents(fio::MAX_BUF).await.expect("read_dirents fidl failed");
+        assert_eq!(Status::from_raw(status), Status::OK, "read_dirents failed");
+        if buf.is_empty() {
+            return entries;
+        }
+
+        let mut buf = &buf[..];
+        while !buf.is_empty() {
+            let _inode = buf.read_u64::<LittleEndian>().expect("truncated dirent inode");
+            let name_len = buf.read_u8().expect("truncated dirent name length");
+            let type_ = buf.read_u8().expect("truncated dirent type");
+            let mut name = vec![0; name_len.into()];
+            buf.read_exact(&mut name).expect("truncated dirent name");
+            entries.push((String::from_utf8(name).expect("dirent name is not UTF-8"), type_));
+        }
+    }
+}
+
 /// Calls `rewind` on the provided `proxy`, checking that the result status is Status::OK.
 #[macro_export]
 macro_rules! assert_rewind {
@@ -200,3 +236,40 @@ macro_rules! assert_watcher_one_message_watched_events {
     (@expand_event_type ADDED) => { fio::WatchEvent::Added };
     (@expand_event_type REMOVED) => { fio::WatchEvent::Removed };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{read_all_dirents, run_server_client};
+
+    use crate::{assert_close, file::vmo::asynchronous::read_only_static};
+
+    use {fidl_fuchsia_io as fio, vfs_macros::pseudo_directory};
+
+    #[test]
+    fn read_all_dirents_alphabetical() {
+        let root = pseudo_directory! {
+            "ccc" => read_only_static(b"C"),
+            "aaa" => read_only_static(b"A"),
+            "bbb" => pseudo_directory! {},
+        };
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            let file = fio::DirentType::File.into_primitive();
+            let directory = fio::DirentType::Directory.into_primitive();
+            assert_eq!(
+                read_all_dirents(&root).await,
+                vec![
+                    (".".to_string(), directory),
+                    ("aaa".to_string(), file),
+                    ("bbb".to_string(), directory),
+                    ("ccc".to_string(), file),
+                ]
+            );
+
+            // Everything was read, so another call lists nothing.
+            assert_eq!(read_all_dirents(&root).await, vec![]);
+
+            assert_close!(root);
+        });
+    }
+}
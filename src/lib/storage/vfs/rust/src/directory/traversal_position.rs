@@ -4,9 +4,9 @@
 
 use std::default::Default;
 
-/// Seek position inside a directory.  The precise meaning of the Name and Index values are entirely
-/// up to an implementation; it could indicate the next entry to be returned or the last entry
-/// returned; the type should be considered to be opaque to the client.  There are some
+/// Seek position inside a directory.  The precise meaning of the Name, Index and Cookie values are
+/// entirely up to an implementation; it could indicate the next entry to be returned or the last
+/// entry returned; the type should be considered to be opaque to the client.  There are some
 /// implementations that return entries in alphabetical order, but they are not required to do so.
 /// The Start value indicates the first entry should be returned and the End value indicates no more
 /// entries should be returned.
@@ -18,6 +18,11 @@ pub enum TraversalPosition {
     Name(String),
     /// The index of an entry.
     Index(u64),
+    /// A cookie assigned to an entry when it was added to the directory.  Cookies are assigned in
+    /// increasing order, so the traversal resumes with the first entry that has a cookie equal to
+    /// or greater than this one.  If the entry this cookie was assigned to has been removed, the
+    /// traversal continues with the next entry instead.
+    Cookie(u64),
     /// The whole listing was traversed.  There is nothing else to return.
     End,
 }
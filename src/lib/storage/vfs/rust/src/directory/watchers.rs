@@ -8,7 +8,7 @@
 pub mod event_producers;
 
 mod watcher;
-pub use watcher::Controller;
+pub use watcher::{Controller, WATCHER_OVERFLOW_STATUS, WATCHER_QUEUE_CAPACITY};
 
 use crate::{
     directory::{
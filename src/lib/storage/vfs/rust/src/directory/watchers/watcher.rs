@@ -10,9 +10,10 @@ use crate::{
 };
 
 use {
+    fidl::endpoints::ServerEnd,
     fidl_fuchsia_io as fio,
     fuchsia_async::Channel,
-    fuchsia_zircon::MessageBuf,
+    fuchsia_zircon::{MessageBuf, Status},
     futures::{
         channel::mpsc::{self, UnboundedSender},
         select,
@@ -20,9 +21,28 @@ use {
         Future, FutureExt,
     },
     pin_utils::unsafe_pinned,
-    std::{ops::Drop, pin::Pin},
+    std::{
+        ops::Drop,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
 };
 
+/// Maximum number of event buffers queued for a watcher, that the watcher task has not written
+/// into the watcher channel yet.  The directory does not wait for the watcher task, so a watcher
+/// task that can not keep up with the events would otherwise grow its queue without a limit.
+/// When the limit is exceeded, the watcher channel is closed with [`WATCHER_OVERFLOW_STATUS`].
+pub const WATCHER_QUEUE_CAPACITY: usize = 1024;
+
+/// Epitaph sent over a watcher channel that is closed because the watcher fell behind by more than
+/// [`WATCHER_QUEUE_CAPACITY`] event buffers, or because the channel is full.  In the latter case
+/// there is no room left for the epitaph either, so the watcher only sees the channel closed once
+/// it has read the events already in the channel.
+pub const WATCHER_OVERFLOW_STATUS: Status = Status::NO_RESOURCES;
+
 /// `done` is not guaranteed to be called if the task failed to start.  It should only happen
 /// in case the return value is an `Err`.  Unfortunately, there is no way to return the `done`
 /// object itself, as the [`futures::Spawn::spawn_obj`] does not return the ownership in case
@@ -36,40 +56,64 @@ pub(crate) fn new(
     use futures::StreamExt as _;
 
     let (sender, mut receiver) = mpsc::unbounded();
-
-    let task = async move {
-        let mut buf = MessageBuf::new();
-        let mut recv_msg = watcher.channel().recv_msg(&mut buf).fuse();
-        loop {
-            select! {
-                command = receiver.next() => match command {
-                    Some(Command::Send(buffer)) => {
-                        let success = handle_send(watcher.channel(), buffer);
-                        if !success {
-                            break;
-                        }
+    let queued = Arc::new(AtomicUsize::new(0));
+
+    let task = {
+        let queued = queued.clone();
+        async move {
+            let mut buf = MessageBuf::new();
+            let mut recv_msg = watcher.channel().recv_msg(&mut buf).fuse();
+            let overflowed = loop {
+                select! {
+                    command = receiver.next() => match command {
+                        Some(Command::Send(buffer)) => {
+                            // Buffers still queued are dropped as soon as the queue overflows, so
+                            // the watcher learns about it without waiting for them to be written.
+                            if queued.load(Ordering::Relaxed) > WATCHER_QUEUE_CAPACITY {
+                                break true;
+                            }
+                            queued.fetch_sub(1, Ordering::Relaxed);
+                            match handle_send(watcher.channel(), buffer) {
+                                Ok(()) => (),
+                                // The watcher is not reading the events, and its channel is full.
+                                Err(Status::SHOULD_WAIT) => break true,
+                                Err(_) => break false,
+                            }
+                        },
+                        Some(Command::Overflow) => break true,
+                        Some(Command::Disconnect) => break false,
+                        None => break false,
+                    },
+                    _ = recv_msg => {
+                        // We do not expect any messages to be received over the watcher
+                        // connection.  Should we receive a message we will close the connection to
+                        // indicate an error.  If any error occurs, we also close the connection.
+                        // And if the connection is closed, we just stop the command processing as
+                        // well.
+                        break false;
                     },
-                    Some(Command::Disconnect) => break,
-                    None => break,
-                },
-                _ = recv_msg => {
-                    // We do not expect any messages to be received over the watcher connection.
-                    // Should we receive a message we will close the connection to indicate an
-                    // error.  If any error occurs, we also close the connection.  And if the
-                    // connection is closed, we just stop the command processing as well.
-                    break;
-                },
+                }
+            };
+
+            if overflowed {
+                drop(recv_msg);
+                let channel = watcher.into_channel().into_zx_channel();
+                let _ = ServerEnd::<fio::DirectoryWatcherMarker>::new(channel)
+                    .close_with_epitaph(WATCHER_OVERFLOW_STATUS);
             }
         }
     };
 
     scope.spawn(Box::pin(FutureWithDrop::new(task, done)));
-    Controller { mask, commands: sender }
+    Controller { mask, commands: sender, queued }
 }
 
 pub struct Controller {
     mask: fio::WatchMask,
     commands: UnboundedSender<Command>,
+    // Number of event buffers sent to the watcher task that it has not written into the watcher
+    // channel yet.  See [`WATCHER_QUEUE_CAPACITY`].
+    queued: Arc<AtomicUsize>,
 }
 
 impl Controller {
@@ -81,7 +125,24 @@ impl Controller {
             return;
         }
 
-        if self.commands.unbounded_send(Command::Send(buffer())).is_ok() {
+        self.queue_buffer(buffer());
+    }
+
+    /// Queues `buffer` to be written into the watcher channel, unless the watcher has already
+    /// fallen behind by [`WATCHER_QUEUE_CAPACITY`] buffers, in which case the watcher task is told
+    /// to close the channel instead.
+    fn queue_buffer(&self, buffer: Vec<u8>) {
+        let queued = self.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        let command = if queued <= WATCHER_QUEUE_CAPACITY {
+            Command::Send(buffer)
+        } else if queued == WATCHER_QUEUE_CAPACITY + 1 {
+            Command::Overflow
+        } else {
+            // The watcher task has already been told to close the channel.
+            return;
+        };
+
+        if self.commands.unbounded_send(command).is_ok() {
             return;
         }
 
@@ -102,15 +163,7 @@ impl Controller {
         }
 
         while producer.prepare_for_next_buffer() {
-            let buffer = producer.buffer();
-            if self.commands.unbounded_send(Command::Send(buffer)).is_ok() {
-                continue;
-            }
-
-            // An error to send indicates the execution task has been disconnected.  Controller
-            // should always be removed from the watchers list before it is destroyed.  So this is
-            // some logical bug.
-            debug_assert!(false, "Watcher controller failed to send a command to the watcher.");
+            self.queue_buffer(producer.buffer());
         }
 
         return true;
@@ -134,11 +187,13 @@ impl Controller {
 
 enum Command {
     Send(Vec<u8>),
+    /// The watcher fell behind by more than [`WATCHER_QUEUE_CAPACITY`] buffers.
+    Overflow,
     Disconnect,
 }
 
-fn handle_send(channel: &Channel, buffer: Vec<u8>) -> bool {
-    channel.write(&*buffer, &mut vec![]).is_ok()
+fn handle_send(channel: &Channel, buffer: Vec<u8>) -> Result<(), Status> {
+    channel.write(&*buffer, &mut vec![])
 }
 
 struct FutureWithDrop<Wrapped, Done>
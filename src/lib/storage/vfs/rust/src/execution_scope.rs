@@ -22,22 +22,33 @@ use crate::{
 };
 
 use {
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status,
     futures::{
-        channel::oneshot,
+        channel::{mpsc, oneshot},
+        future::BoxFuture,
         task::{self, Context, Poll},
-        Future, FutureExt,
+        Future, FutureExt, StreamExt,
     },
     pin_project::pin_project,
     slab::Slab,
     std::{
         ops::Drop,
         pin::Pin,
-        sync::{Arc, Mutex},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
     },
 };
 
 pub type SpawnError = task::SpawnError;
 
+/// A callback invoked with the flags of every connection created by a `Clone` request, after the
+/// rights have been inherited from the connection being cloned.  See
+/// [`ExecutionScopeParams::clone_observer()`].
+pub type CloneObserver = Arc<dyn Fn(fio::OpenFlags) + Send + Sync>;
+
 /// An execution scope that is hosting tasks for a group of connections.  See the module level
 /// documentation for details.
 ///
@@ -56,6 +67,18 @@ pub struct ExecutionScope {
     inode_registry: Option<Arc<dyn InodeRegistry + Send + Sync>>,
 
     entry_constructor: Option<Arc<dyn EntryConstructor + Send + Sync>>,
+
+    clone_observer: Option<CloneObserver>,
+
+    connection_limit: Option<Arc<ConnectionLimit>>,
+}
+
+/// Counts connections active in a scope, and all of its clones.  See
+/// [`ExecutionScopeParams::max_connections()`] and [`ExecutionScopeParams::count_connections()`].
+/// When connections are only counted, `max` is `usize::MAX`.
+struct ConnectionLimit {
+    max: usize,
+    active: AtomicUsize,
 }
 
 struct Executor {
@@ -66,12 +89,16 @@ struct Executor {
 
     /// Waiters waiting for all connections to be closed.
     waiters: std::vec::Vec<oneshot::Sender<()>>,
+
+    /// Tasks are sent here, if the scope runs them on a dedicated thread pool.  See
+    /// [`ExecutionScopeParams::thread_pool()`].
+    thread_pool: Option<mpsc::UnboundedSender<BoxFuture<'static, ()>>>,
 }
 
 impl ExecutionScope {
-    /// Constructs an execution scope that has no `token_registry`, `inode_registry`, nor
-    /// `entry_constructor`.  Use [`ExecutionScope::build()`] if you want to specify other
-    /// parameters.
+    /// Constructs an execution scope that has no `token_registry`, `inode_registry`,
+    /// `entry_constructor`, `clone_observer`, connection limit, nor a thread pool.  Use
+    /// [`ExecutionScope::build()`] if you want to specify other parameters.
     pub fn new() -> Self {
         Self::build().new()
     }
@@ -80,7 +107,15 @@ impl ExecutionScope {
     /// accepting additional parameters.  Run [`ExecutionScopeParams::new()`] to get an actual
     /// [`ExecutionScope`] object.
     pub fn build() -> ExecutionScopeParams {
-        ExecutionScopeParams { token_registry: None, inode_registry: None, entry_constructor: None }
+        ExecutionScopeParams {
+            token_registry: None,
+            inode_registry: None,
+            entry_constructor: None,
+            clone_observer: None,
+            max_connections: None,
+            count_connections: false,
+            thread_pool: None,
+        }
     }
 
     /// Sends a `task` to be executed in this execution scope.  This is very similar to
@@ -125,6 +160,24 @@ impl ExecutionScope {
         Executor::run_abort_with_shutdown(self.executor.clone(), constructor(receiver), sender)
     }
 
+    /// Sends a `task` to be executed in this execution scope, same as [`ExecutionScope::spawn()`],
+    /// and returns a [`TaskHandle`] that completes when this particular task finishes, either by
+    /// running to completion, or by being interrupted when the scope is shut down.  Useful to wait
+    /// for the teardown of a single connection, without shutting down the whole scope.
+    pub fn spawn_tracked<Task>(&self, task: Task) -> TaskHandle
+    where
+        Task: Future<Output = ()> + Send + 'static,
+    {
+        let (done_sender, done_receiver) = oneshot::channel();
+        // `done_sender` is dropped when the task completes or is interrupted, either way waking up
+        // the handle.
+        self.spawn(async move {
+            task.await;
+            drop(done_sender);
+        });
+        TaskHandle { done: done_receiver }
+    }
+
     pub fn token_registry(&self) -> Option<Arc<dyn TokenRegistry + Send + Sync>> {
         self.token_registry.as_ref().map(Arc::clone)
     }
@@ -137,6 +190,46 @@ impl ExecutionScope {
         self.entry_constructor.as_ref().map(Arc::clone)
     }
 
+    pub fn clone_observer(&self) -> Option<CloneObserver> {
+        self.clone_observer.as_ref().map(Arc::clone)
+    }
+
+    /// Reserves a slot for a new connection in this scope.  Connections call this before they
+    /// start processing requests, and hold the returned [`ConnectionSlot`] for as long as they are
+    /// alive.
+    ///
+    /// Fails with `ZX_ERR_NO_RESOURCES` if the scope already hosts the maximum number of
+    /// connections set with [`ExecutionScopeParams::max_connections()`].  Never fails if no limit
+    /// was set.
+    pub fn acquire_connection(&self) -> Result<ConnectionSlot, Status> {
+        let limit = match &self.connection_limit {
+            None => return Ok(ConnectionSlot { limit: None }),
+            Some(limit) => limit,
+        };
+        limit
+            .active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                if active < limit.max {
+                    Some(active + 1)
+                } else {
+                    None
+                }
+            })
+            .map_err(|_| Status::NO_RESOURCES)?;
+        Ok(ConnectionSlot { limit: Some(limit.clone()) })
+    }
+
+    /// Returns the number of connections currently active in this scope and all of its clones,
+    /// for debugging, for example to detect connections leaked by a test.  A connection is counted
+    /// from the moment it is created until its task finishes, so after [`ExecutionScope::wait()`]
+    /// the count only includes connections that are still open.
+    ///
+    /// Returns `None` unless the scope was built with [`ExecutionScopeParams::count_connections()`]
+    /// or [`ExecutionScopeParams::max_connections()`].
+    pub fn active_connection_count(&self) -> Option<usize> {
+        self.connection_limit.as_ref().map(|limit| limit.active.load(Ordering::Acquire))
+    }
+
     pub fn shutdown(&self) {
         let mut this = self.executor.lock().unwrap();
         this.shutdown();
@@ -167,14 +260,50 @@ impl Clone for ExecutionScope {
             token_registry: self.token_registry.as_ref().map(Arc::clone),
             inode_registry: self.inode_registry.as_ref().map(Arc::clone),
             entry_constructor: self.entry_constructor.as_ref().map(Arc::clone),
+            clone_observer: self.clone_observer.as_ref().map(Arc::clone),
+            connection_limit: self.connection_limit.as_ref().map(Arc::clone),
         }
     }
 }
 
+/// A slot taken by a connection in an [`ExecutionScope`] that limits the number of active
+/// connections.  The slot is released when this value is dropped.  See
+/// [`ExecutionScope::acquire_connection()`].
+pub struct ConnectionSlot {
+    limit: Option<Arc<ConnectionLimit>>,
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        if let Some(limit) = &self.limit {
+            limit.active.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+/// A future that completes when a task sent with [`ExecutionScope::spawn_tracked()`] finishes.
+/// Dropping the handle does not affect the task.
+pub struct TaskHandle {
+    done: oneshot::Receiver<()>,
+}
+
+impl Future for TaskHandle {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // The sender is never used, so the only possible outcome is `Canceled`.
+        self.done.poll_unpin(cx).map(|_| ())
+    }
+}
+
 pub struct ExecutionScopeParams {
     token_registry: Option<Arc<dyn TokenRegistry + Send + Sync>>,
     inode_registry: Option<Arc<dyn InodeRegistry + Send + Sync>>,
     entry_constructor: Option<Arc<dyn EntryConstructor + Send + Sync>>,
+    clone_observer: Option<CloneObserver>,
+    max_connections: Option<usize>,
+    count_connections: bool,
+    thread_pool: Option<usize>,
 }
 
 impl ExecutionScopeParams {
@@ -196,16 +325,83 @@ impl ExecutionScopeParams {
         self
     }
 
+    /// Sets a callback that is invoked with the flags of every connection created by a `Clone`
+    /// request in this scope.  The flags include the rights actually granted to the clone, which
+    /// is useful when debugging rights inheritance, as the client can not observe them until an
+    /// operation fails.
+    pub fn clone_observer(mut self, value: CloneObserver) -> Self {
+        assert!(self.clone_observer.is_none(), "`clone_observer` is already set");
+        self.clone_observer = Some(value);
+        self
+    }
+
+    /// Limits the number of connections that can be active in this scope at the same time.  Once
+    /// the limit is reached, new connections are refused with `ZX_ERR_NO_RESOURCES`, until some
+    /// of the existing connections are closed.
+    pub fn max_connections(mut self, value: usize) -> Self {
+        assert!(self.max_connections.is_none(), "`max_connections` is already set");
+        self.max_connections = Some(value);
+        self
+    }
+
+    /// Counts the connections active in this scope, without limiting them, so that
+    /// [`ExecutionScope::active_connection_count()`] can report them.  Scopes with
+    /// [`Self::max_connections()`] count their connections regardless.
+    pub fn count_connections(mut self) -> Self {
+        self.count_connections = true;
+        self
+    }
+
+    /// Runs the tasks of this scope on a dedicated multithreaded executor with `threads` threads,
+    /// rather than on the executor of the thread that spawns them.  Useful to keep CPU heavy
+    /// connections from stalling the rest of the process.
+    ///
+    /// The executor runs on its own thread, which exits once the scope, all of its clones, and all
+    /// of its tasks are gone.
+    pub fn thread_pool(mut self, threads: usize) -> Self {
+        assert!(self.thread_pool.is_none(), "`thread_pool` is already set");
+        assert!(threads > 0, "`thread_pool` needs at least one thread");
+        self.thread_pool = Some(threads);
+        self
+    }
+
     pub fn new(self) -> ExecutionScope {
         ExecutionScope {
-            executor: Arc::new(Mutex::new(Executor { running: Slab::new(), waiters: Vec::new() })),
+            executor: Arc::new(Mutex::new(Executor {
+                running: Slab::new(),
+                waiters: Vec::new(),
+                thread_pool: self.thread_pool.map(start_thread_pool),
+            })),
             token_registry: self.token_registry,
             inode_registry: self.inode_registry,
             entry_constructor: self.entry_constructor,
+            clone_observer: self.clone_observer,
+            connection_limit: self
+                .max_connections
+                .or_else(|| self.count_connections.then(|| usize::MAX))
+                .map(|max| Arc::new(ConnectionLimit { max, active: AtomicUsize::new(0) })),
         }
     }
 }
 
+/// Starts a thread running a multithreaded executor, with `threads` threads, that runs all the
+/// tasks sent over the returned channel.  The executor stops once the channel is closed.
+fn start_thread_pool(threads: usize) -> mpsc::UnboundedSender<BoxFuture<'static, ()>> {
+    let (sender, mut receiver) = mpsc::unbounded::<BoxFuture<'static, ()>>();
+    std::thread::spawn(move || {
+        let mut executor =
+            fuchsia_async::SendExecutor::new(threads).expect("failed to create executor");
+        // The sender is owned by the `Executor`, which every task keeps alive until it completes,
+        // so there are no tasks left to run once the channel is closed.
+        executor.run(async move {
+            while let Some(task) = receiver.next().await {
+                fuchsia_async::Task::spawn(task).detach();
+            }
+        });
+    });
+    sender
+}
+
 // A future that completes when either of two futures completes.
 #[pin_project]
 struct FirstToFinish<A, B> {
@@ -261,7 +457,15 @@ impl Executor {
         let executor_clone = executor.clone();
         let task = task
             .then(move |_| async move { executor_clone.lock().unwrap().task_did_finish(task_id) });
-        fuchsia_async::Task::spawn(task).detach();
+        match &this.thread_pool {
+            None => fuchsia_async::Task::spawn(task).detach(),
+            Some(thread_pool) => {
+                if thread_pool.unbounded_send(task.boxed()).is_err() {
+                    // The thread pool is gone, so the task will never run.
+                    this.task_did_finish(task_id);
+                }
+            }
+        }
     }
 
     fn shutdown(&mut self) {
@@ -302,13 +506,17 @@ mod tests {
     use super::ExecutionScope;
 
     use crate::{
-        directory::mutable::entry_constructor::EntryConstructor,
+        directory::mutable::{
+            self, connection::io1::MutableConnection, entry_constructor::EntryConstructor,
+        },
         registry::{inode_registry, token_registry, InodeRegistry, TokenRegistry},
     };
 
     use {
+        fidl::endpoints::create_proxy,
+        fidl_fuchsia_io as fio,
         fuchsia_async::{TestExecutor, Time, Timer},
-        fuchsia_zircon::prelude::*,
+        fuchsia_zircon::{prelude::*, Status},
         futures::{
             channel::{mpsc, oneshot},
             select,
@@ -418,6 +626,29 @@ mod tests {
         });
     }
 
+    #[test]
+    fn thread_pool_runs_tasks_concurrently() {
+        let mut executor = TestExecutor::new().expect("Executor creation failed");
+        let scope = ExecutionScope::build().thread_pool(2).new();
+
+        let (unblock_sender, unblock_receiver) = std::sync::mpsc::channel();
+        let (done_sender, done_receiver) = oneshot::channel();
+
+        // Blocks one of the pool threads, until the second task runs on the other one.
+        scope.spawn(async move {
+            unblock_receiver.recv().unwrap();
+            done_sender.send(()).unwrap();
+        });
+        scope.spawn(async move {
+            unblock_sender.send(()).unwrap();
+        });
+
+        executor.run_singlethreaded(async {
+            done_receiver.await.unwrap();
+            scope.wait().await;
+        });
+    }
+
     #[test]
     fn spawn_with_shutdown() {
         run_test(|scope| async move {
@@ -481,6 +712,43 @@ mod tests {
         });
     }
 
+    #[test]
+    fn spawn_tracked_completes_with_its_connection() {
+        run_test(|scope| async move {
+            let dir = mutable::simple();
+            let connect = || {
+                let (proxy, server_end) = create_proxy::<fio::DirectoryMarker>().unwrap();
+                // The shutdown sender is kept alive, so the connection only ends when it is closed
+                // or when the scope is shut down.
+                let (shutdown_sender, shutdown) = oneshot::channel();
+                let handle = scope.spawn_tracked(MutableConnection::create_connection_async(
+                    scope.clone(),
+                    dir.clone(),
+                    fio::OpenFlags::RIGHT_READABLE,
+                    server_end.into_channel().into(),
+                    shutdown,
+                ));
+                (proxy, handle, shutdown_sender)
+            };
+
+            let (first, first_handle, _first_shutdown) = connect();
+            let (second, mut second_handle, _second_shutdown) = connect();
+
+            assert_eq!(first.close().await.unwrap(), Ok(()));
+            first_handle.await;
+
+            // The other connection is still served.
+            assert_eq!(futures::poll!(&mut second_handle), Poll::Pending);
+            let (status, flags) = second.get_flags().await.unwrap();
+            assert_eq!(Status::from_raw(status), Status::OK);
+            assert_eq!(flags, fio::OpenFlags::RIGHT_READABLE);
+
+            // Connections interrupted by the scope shutdown complete their handles as well.
+            scope.shutdown();
+            second_handle.await;
+        });
+    }
+
     #[test]
     fn with_token_registry() {
         let registry: Arc<dyn TokenRegistry + Send + Sync> = token_registry::Simple::new();
@@ -4,10 +4,22 @@
 
 //! Module holding different kinds of files and their building blocks.
 use {
-    crate::directory::entry::DirectoryEntry, async_trait::async_trait, fidl_fuchsia_io as fio,
-    fidl_fuchsia_mem::Buffer, fuchsia_zircon::Status,
+    crate::directory::entry::DirectoryEntry,
+    async_trait::async_trait,
+    fidl_fuchsia_io as fio,
+    fidl_fuchsia_mem::Buffer,
+    fuchsia_zircon::{self as zx, Status},
 };
 
+pub use event::{with_event, WithEvent};
+pub use exclusive::ExclusiveOpen;
+pub use from_fn::{read_only_fn, ReadOnlyFn};
+pub use log_file::{log_file, LogFile};
+pub use mem::{mem_file, MemFile};
+pub use throttled::{throttled, Throttled};
+pub use throughput::{ThroughputRecorder, ThroughputSnapshot};
+pub use wrapper::{FileWrapper, WrapperHooks};
+
 /// File nodes backed by VMOs.
 pub mod vmo;
 
@@ -17,6 +29,22 @@ mod common;
 
 pub mod connection;
 
+mod event;
+
+mod exclusive;
+
+mod from_fn;
+
+mod log_file;
+
+mod mem;
+
+mod throttled;
+
+mod throughput;
+
+mod wrapper;
+
 /// Trait used for all files.
 #[async_trait]
 pub trait File: Sync + Send + DirectoryEntry {
@@ -34,6 +62,15 @@ pub trait File: Sync + Send + DirectoryEntry {
     /// of bytes read into |buffer|.
     async fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<u64, Status>;
 
+    /// Verifies the integrity of `data`, which was just returned by [`Self::read_at`] for the
+    /// `length` bytes starting at `offset`.  Files that can check their content, for example
+    /// against per-block hashes, should return `ZX_ERR_IO_DATA_INTEGRITY` when the check fails, in
+    /// which case the read fails with the same error and the data is not sent to the client.
+    /// Defaults to accepting all the data.
+    async fn verify_range(&self, _offset: u64, _length: u64, _data: &[u8]) -> Result<(), Status> {
+        Ok(())
+    }
+
     /// Write |content| starting at |offset|, returning the number of bytes that were successfully
     /// written.
     /// If there are pending attributes to update (see set_attrs), they should also be flushed at
@@ -55,6 +92,16 @@ pub trait File: Sync + Send + DirectoryEntry {
 
     /// Get a VMO representing this file.
     /// If not supported by the underlying filesystem, should return Error(NOT_SUPPORTED).
+    ///
+    /// `flags` have already been validated against the rights of the connection, and never contain
+    /// both `PRIVATE_CLONE` and `SHARED_BUFFER`.  The sharing mode requested by `flags` is:
+    /// * `PRIVATE_CLONE` - a copy-on-write clone of the file content, that is not affected by any
+    ///   later changes to the file, and whose changes are not visible to other clients.
+    /// * `SHARED_BUFFER` - the exact VMO backing the file, so the changes made through the file
+    ///   are visible in the VMO and vice versa.  Implementations that can not share their VMO
+    ///   should return Error(NOT_SUPPORTED).
+    /// * Neither flag - the implementation may choose either of the above, whichever is cheaper.
+    ///   Clients that do not specify a sharing mode must not depend on one.
     async fn get_buffer(&self, flags: fio::VmoFlags) -> Result<Buffer, Status>;
 
     /// Get the size of this file.
@@ -64,6 +111,36 @@ pub trait File: Sync + Send + DirectoryEntry {
     /// Get this file's attributes.
     async fn get_attrs(&self) -> Result<fio::NodeAttributes, Status>;
 
+    /// Returns the attributes of this file selected by `requested`, as used by the io2
+    /// `GetAttributes` request.  Attributes that were not requested are left unset.
+    ///
+    /// Defaults to the attributes reported by [`Self::get_attrs`].  Only the attributes present in
+    /// the io1 attributes are reported, the rest are left unset even if requested.
+    async fn get_attributes(
+        &self,
+        requested: fio::NodeAttributesQuery,
+    ) -> Result<fio::NodeAttributes2, Status> {
+        let attrs = self.get_attrs().await?;
+        let select = |query, value| if requested.contains(query) { Some(value) } else { None };
+        Ok(fio::NodeAttributes2 {
+            mutable_attributes: fio::MutableNodeAttributes {
+                creation_time: select(fio::NodeAttributesQuery::CREATION_TIME, attrs.creation_time),
+                modification_time: select(
+                    fio::NodeAttributesQuery::MODIFICATION_TIME,
+                    attrs.modification_time,
+                ),
+                ..fio::MutableNodeAttributes::EMPTY
+            },
+            immutable_attributes: fio::ImmutableNodeAttributes {
+                content_size: select(fio::NodeAttributesQuery::CONTENT_SIZE, attrs.content_size),
+                storage_size: select(fio::NodeAttributesQuery::STORAGE_SIZE, attrs.storage_size),
+                link_count: select(fio::NodeAttributesQuery::LINK_COUNT, attrs.link_count),
+                id: select(fio::NodeAttributesQuery::ID, attrs.id),
+                ..fio::ImmutableNodeAttributes::EMPTY
+            },
+        })
+    }
+
     /// Set the attributes of this file based on the values in `attrs`.
     async fn set_attrs(
         &self,
@@ -86,8 +163,177 @@ pub trait File: Sync + Send + DirectoryEntry {
         Err(Status::NOT_SUPPORTED)
     }
 
-    /// Describes the underlying object.  Defaults to a simple file.
+    /// Files that only allow one connection at a time should return the [`ExclusiveOpen`] instance
+    /// tracking whether they are currently open.  While a connection to such a file is open, any
+    /// other attempts to open it fail with `ZX_ERR_ALREADY_BOUND`.  Defaults to `None`, allowing
+    /// any number of connections.
+    fn exclusive(&self) -> Option<&ExclusiveOpen> {
+        None
+    }
+
+    /// Files that want the data transferred over their connections to be accounted for should
+    /// return the [`ThroughputRecorder`] collecting the numbers.  Defaults to `None`, in which case
+    /// connections do not track throughput.
+    fn throughput(&self) -> Option<&ThroughputRecorder> {
+        None
+    }
+
+    /// Called by the connection after every successful read or write, with the number of bytes
+    /// transferred.  Lets files aggregate their own I/O statistics, without the connection owning
+    /// any metrics.  Defaults to doing nothing.
+    fn record_io(&self, _bytes_read: u64, _bytes_written: u64) {}
+
+    /// Called by the connection after every successful write, append or truncate, including the
+    /// truncate done for `OPEN_FLAG_TRUNCATE`.  Files that track their modification time should
+    /// advance it here, as POSIX clients expect.  Defaults to doing nothing.
+    fn touch_modified(&self) {}
+
+    /// Returns the event included in the `FileObject` describing this file, for clients to wait
+    /// on.  Files signal the event, for example with `ZX_USER_SIGNAL_0`, to tell clients about
+    /// their state, such as data being ready to read.  Every call should return a new handle to the
+    /// same event.  Defaults to `None`.  See [`with_event`] for a wrapper providing an event.
+    fn event(&self) -> Result<Option<zx::Event>, Status> {
+        Ok(None)
+    }
+
+    /// Describes the underlying object.  Defaults to a simple file, carrying the event returned by
+    /// [`Self::event`].
+    ///
+    /// The result is not cached: the connection calls this method for the `OnOpen` event and for
+    /// every `Describe` request, so files whose capabilities change at runtime (for example, gain
+    /// an event or a stream) can report their current state.
     fn describe(&self, _connection_flags: fio::OpenFlags) -> Result<fio::NodeInfo, Status> {
-        Ok(fio::NodeInfo::File(fio::FileObject { event: None, stream: None }))
+        Ok(fio::NodeInfo::File(fio::FileObject { event: self.event()?, stream: None }))
+    }
+}
+
+/// Reads `len` bytes starting at `offset` from `file`, splitting the range into as many
+/// [`File::read_at`] calls as necessary, each for at most `fio::MAX_BUF` bytes.  Stops at the
+/// first read that returns less than it was asked for, usually the end of the file, so the result
+/// may be shorter than `len`.  Every chunk is checked with [`File::verify_range`] before it is
+/// added to the result, as connections do for `Read` requests, and the first failure is returned.
+///
+/// Connections still reject `Read` requests for more than `fio::MAX_BUF` bytes; this only saves
+/// Rust callers that hold the file from writing the loop themselves.
+pub async fn read_all<F: File + ?Sized>(
+    file: &F,
+    offset: u64,
+    len: u64,
+) -> Result<Vec<u8>, Status> {
+    let mut content = Vec::new();
+    while (content.len() as u64) < len {
+        let start = content.len();
+        let chunk_offset = offset.checked_add(start as u64).ok_or(Status::OUT_OF_RANGE)?;
+        let chunk_len = std::cmp::min(len - start as u64, fio::MAX_BUF) as usize;
+        content.resize(start + chunk_len, 0);
+        let read = file.read_at(chunk_offset, &mut content[start..]).await?;
+        let read = std::cmp::min(read, chunk_len as u64) as usize;
+        content.truncate(start + read);
+        let () = file.verify_range(chunk_offset, read as u64, &content[start..]).await?;
+        if read < chunk_len {
+            break;
+        }
+    }
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mem_file, read_all, File, FileWrapper, MemFile, WrapperHooks};
+
+    use {
+        async_trait::async_trait, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::Status, std::sync::Mutex,
+    };
+
+    /// Records the ranges read from the wrapped file, and fails the verification of any range
+    /// holding the `tampered` offset.
+    #[derive(Default)]
+    struct Recorder {
+        reads: Mutex<Vec<(u64, u64)>>,
+        tampered: Option<u64>,
+    }
+
+    #[async_trait]
+    impl WrapperHooks<MemFile> for Recorder {
+        async fn read_at(
+            &self,
+            inner: &MemFile,
+            offset: u64,
+            buffer: &mut [u8],
+        ) -> Result<u64, Status> {
+            self.reads.lock().unwrap().push((offset, buffer.len() as u64));
+            inner.read_at(offset, buffer).await
+        }
+
+        async fn verify_range(
+            &self,
+            _inner: &MemFile,
+            offset: u64,
+            length: u64,
+            _data: &[u8],
+        ) -> Result<(), Status> {
+            match self.tampered {
+                Some(tampered) if (offset..offset + length).contains(&tampered) => {
+                    Err(Status::IO_DATA_INTEGRITY)
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn content(len: u64) -> Vec<u8> {
+        (0..len).map(|i| (i % 256) as u8).collect()
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn read_all_splits_at_max_buf() {
+        let len = 2 * fio::MAX_BUF + 10;
+        let file = FileWrapper::new(
+            mem_file(content(len + 20), 0),
+            Recorder::default(),
+            fio::OpenFlags::RIGHT_READABLE,
+        );
+
+        let data = read_all(&*file, 5, len).await.unwrap();
+
+        assert_eq!(data, &content(len + 5)[5..]);
+        assert_eq!(
+            *file.hooks().reads.lock().unwrap(),
+            vec![(5, fio::MAX_BUF), (5 + fio::MAX_BUF, fio::MAX_BUF), (5 + 2 * fio::MAX_BUF, 10)]
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn read_all_stops_at_end_of_file() {
+        let file = FileWrapper::new(
+            mem_file(content(fio::MAX_BUF + 7), 0),
+            Recorder::default(),
+            fio::OpenFlags::RIGHT_READABLE,
+        );
+
+        let data = read_all(&*file, 0, 3 * fio::MAX_BUF).await.unwrap();
+
+        assert_eq!(data, content(fio::MAX_BUF + 7));
+        assert_eq!(
+            *file.hooks().reads.lock().unwrap(),
+            vec![(0, fio::MAX_BUF), (fio::MAX_BUF, fio::MAX_BUF)]
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn read_all_verifies_every_chunk() {
+        let len = 3 * fio::MAX_BUF;
+        let file = FileWrapper::new(
+            mem_file(content(len), 0),
+            Recorder { tampered: Some(fio::MAX_BUF + 1), ..Default::default() },
+            fio::OpenFlags::RIGHT_READABLE,
+        );
+
+        assert_eq!(read_all(&*file, 0, len).await, Err(Status::IO_DATA_INTEGRITY));
+        assert_eq!(
+            *file.hooks().reads.lock().unwrap(),
+            vec![(0, fio::MAX_BUF), (fio::MAX_BUF, fio::MAX_BUF)]
+        );
     }
 }
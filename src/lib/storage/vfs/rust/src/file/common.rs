@@ -4,7 +4,42 @@
 
 //! Common utilities for pseudo file implementations
 
-use {fidl_fuchsia_io as fio, fuchsia_zircon as zx};
+use crate::{
+    common::send_on_open_with_error,
+    execution_scope::ExecutionScope,
+    file::{connection::io1::FileConnection, File},
+    path::Path,
+};
+
+use {fidl::endpoints::ServerEnd, fidl_fuchsia_io as fio, fuchsia_zircon as zx, std::sync::Arc};
+
+/// Handles an `Open` of `path` in `file`, for files served with a [`FileConnection`].  An empty
+/// `path` opens a connection that can only be granted the `RIGHT_*` flags present in `rights`.
+/// Files do not have entries, so opening any other path fails with `ZX_ERR_NOT_DIR`.
+pub(crate) fn open_file<T: File>(
+    file: Arc<T>,
+    scope: ExecutionScope,
+    flags: fio::OpenFlags,
+    path: Path,
+    server_end: ServerEnd<fio::NodeMarker>,
+    rights: fio::OpenFlags,
+) {
+    if !path.is_empty() {
+        send_on_open_with_error(flags, server_end, zx::Status::NOT_DIR);
+        return;
+    }
+
+    FileConnection::create_connection(
+        scope,
+        file,
+        flags,
+        server_end,
+        rights.intersects(fio::OpenFlags::RIGHT_READABLE),
+        rights.intersects(fio::OpenFlags::RIGHT_WRITABLE),
+        rights.intersects(fio::OpenFlags::RIGHT_EXECUTABLE),
+        /*no_sparse_writes=*/ false,
+    );
+}
 
 /// Validate that the requested flags for a new connection are valid.  This function will make sure
 /// that `flags` only requests read access when `readable` is true, or only write access when
@@ -16,6 +51,9 @@ use {fidl_fuchsia_io as fio, fuchsia_zircon as zx};
 ///
 /// `OPEN_FLAG_NODE_REFERENCE` is preserved and prohibits both read and write access.
 ///
+/// `OPEN_FLAG_APPEND` is only accepted when both `writable` and `append_allowed` are true, and is
+/// rejected with `NOT_SUPPORTED` otherwise.
+///
 /// Changing this function can be dangerous!  Flags operations may have security implications.
 pub fn new_connection_validate_flags(
     mut flags: fio::OpenFlags,
@@ -61,15 +99,10 @@ pub fn new_connection_validate_flags(
         | if writable && append_allowed { fio::OpenFlags::APPEND } else { fio::OpenFlags::empty() }
         | if executable { fio::OpenFlags::RIGHT_EXECUTABLE } else { fio::OpenFlags::empty() };
 
-    let prohibited_flags = (fio::OpenFlags::empty() | if readable {
-            fio::OpenFlags::TRUNCATE
-        } else {
-            fio::OpenFlags::empty()
-        } | if writable {
-            fio::OpenFlags::APPEND
-        } else {
-            fio::OpenFlags::empty()
-        })
+    // APPEND is not prohibited, so nodes that do not allow it report it as NOT_SUPPORTED, whether
+    // they are writable or not.
+    let prohibited_flags = (fio::OpenFlags::empty()
+        | if readable { fio::OpenFlags::TRUNCATE } else { fio::OpenFlags::empty() })
         // allowed_flags takes precedence over prohibited_flags.
         & !allowed_flags
         // TRUNCATE is only allowed if the file is writable.
@@ -340,7 +373,7 @@ mod tests {
             true,
             true,
             false,
-            zx::Status::INVALID_ARGS,
+            zx::Status::NOT_SUPPORTED,
         );
     }
 
@@ -375,6 +408,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn new_connection_validate_flags_all_combinations() {
+        use zx::Status;
+
+        const OK: Result<(), Status> = Ok(());
+        const AD: Result<(), Status> = Err(Status::ACCESS_DENIED);
+        const IA: Result<(), Status> = Err(Status::INVALID_ARGS);
+        const NS: Result<(), Status> = Err(Status::NOT_SUPPORTED);
+
+        const R: fio::OpenFlags = fio::OpenFlags::RIGHT_READABLE;
+        const W: fio::OpenFlags = fio::OpenFlags::RIGHT_WRITABLE;
+        const X: fio::OpenFlags = fio::OpenFlags::RIGHT_EXECUTABLE;
+        const RW: fio::OpenFlags = R.union(W);
+        const RX: fio::OpenFlags = R.union(X);
+        const WX: fio::OpenFlags = W.union(X);
+        const RWX: fio::OpenFlags = RW.union(X);
+        const NONE: fio::OpenFlags = fio::OpenFlags::empty();
+        const APPEND: fio::OpenFlags = fio::OpenFlags::APPEND;
+        const TRUNCATE: fio::OpenFlags = fio::OpenFlags::TRUNCATE;
+
+        // (readable, writable, executable, append_allowed) of the nodes in the table columns.
+        let nodes = [
+            (true, false, false, false),
+            (true, false, false, true),
+            (true, true, false, false),
+            (true, true, false, true),
+            (true, false, true, false),
+        ];
+
+        #[rustfmt::skip]
+        let table = [
+            // Flags                 R   R+A RW  RW+A RX
+            (NONE,                 [OK, OK, OK, OK, OK]),
+            (R,                    [OK, OK, OK, OK, OK]),
+            (W,                    [AD, AD, OK, OK, AD]),
+            (X,                    [AD, AD, AD, AD, OK]),
+            (RW,                   [AD, AD, OK, OK, AD]),
+            (RX,                   [AD, AD, AD, AD, OK]),
+            (WX,                   [AD, AD, AD, AD, AD]),
+            (RWX,                  [AD, AD, AD, AD, AD]),
+            (APPEND,               [NS, NS, NS, OK, NS]),
+            (R.union(APPEND),      [NS, NS, NS, OK, NS]),
+            (W.union(APPEND),      [AD, AD, NS, OK, AD]),
+            (X.union(APPEND),      [AD, AD, AD, AD, NS]),
+            (RW.union(APPEND),     [AD, AD, NS, OK, AD]),
+            (RX.union(APPEND),     [AD, AD, AD, AD, NS]),
+            (WX.union(APPEND),     [AD, AD, AD, AD, AD]),
+            (RWX.union(APPEND),    [AD, AD, AD, AD, AD]),
+            (TRUNCATE,             [IA, IA, IA, IA, IA]),
+            (R.union(TRUNCATE),    [IA, IA, IA, IA, IA]),
+            (W.union(TRUNCATE),    [AD, AD, OK, OK, AD]),
+            (X.union(TRUNCATE),    [AD, AD, AD, AD, IA]),
+            (RW.union(TRUNCATE),   [AD, AD, OK, OK, AD]),
+            (RX.union(TRUNCATE),   [AD, AD, AD, AD, IA]),
+            (WX.union(TRUNCATE),   [AD, AD, AD, AD, AD]),
+            (RWX.union(TRUNCATE),  [AD, AD, AD, AD, AD]),
+        ];
+
+        for (flags, expected) in table {
+            for (&(readable, writable, executable, append_allowed), expected) in
+                nodes.iter().zip(expected)
+            {
+                for extra in build_flag_combinations(
+                    0,
+                    (fio::OpenFlags::DESCRIBE | fio::OpenFlags::CREATE).bits(),
+                ) {
+                    let flags = flags | fio::OpenFlags::from_bits_truncate(extra);
+                    let validate = |flags| {
+                        new_connection_validate_flags(
+                            flags,
+                            readable,
+                            writable,
+                            executable,
+                            append_allowed,
+                        )
+                    };
+                    let context = format!(
+                        "flags: {:?}, readable: {}, writable: {}, executable: {}, \
+                         append_allowed: {}",
+                        flags, readable, writable, executable, append_allowed
+                    );
+
+                    assert_eq!(validate(flags), expected.map(|()| flags), "{}", context);
+
+                    // Node references ignore the rights of the node and drop every flag that
+                    // needs access to the content.
+                    assert_eq!(
+                        validate(flags | fio::OpenFlags::NODE_REFERENCE),
+                        Ok(fio::OpenFlags::NODE_REFERENCE | (flags & fio::OpenFlags::DESCRIBE)),
+                        "{}",
+                        context
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn new_connection_validate_flags_append_allowed() {
+        let flags = fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::APPEND;
+        assert_eq!(new_connection_validate_flags(flags, false, true, false, true), Ok(flags));
+        // Append does not require the connection itself to be writable.
+        assert_eq!(
+            new_connection_validate_flags(
+                fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::APPEND,
+                true,
+                true,
+                false,
+                true
+            ),
+            Ok(fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::APPEND)
+        );
+        assert_eq!(
+            new_connection_validate_flags(flags, false, true, false, false),
+            Err(zx::Status::NOT_SUPPORTED)
+        );
+    }
+
     /// Validates that the passed VMO flags are correctly mapped to their respective Rights.
     #[test]
     fn test_vmo_flags_to_rights() {
@@ -5,23 +5,23 @@
 use {
     crate::{
         common::{inherit_rights_for_clone, send_on_open_with_error, GET_FLAGS_VISIBLE},
-        directory::entry::DirectoryEntry,
         execution_scope::ExecutionScope,
         file::{
             common::{get_buffer_validate_flags, new_connection_validate_flags},
             connection::util::OpenFile,
+            throughput::ConnectionThroughput,
             File,
         },
-        path::Path,
     },
     anyhow::Error,
-    fidl::endpoints::ServerEnd,
+    fidl::endpoints::{RequestStream as _, ServerEnd},
     fidl_fuchsia_io as fio,
     fuchsia_zircon::{
         self as zx,
         sys::{ZX_ERR_NOT_SUPPORTED, ZX_OK},
     },
     futures::{channel::oneshot, select, stream::StreamExt},
+    log::warn,
     static_assertions::assert_eq_size,
     std::{convert::TryInto as _, sync::Arc},
 };
@@ -38,7 +38,8 @@ pub struct FileConnection<T: 'static + File> {
     /// Wraps a FIDL connection, providing messages coming from the client.
     requests: fio::FileRequestStream,
 
-    /// Either the "flags" value passed into [`DirectoryEntry::open()`], or the "flags" value
+    /// Either the "flags" value passed into
+    /// [`crate::directory::entry::DirectoryEntry::open()`], or the "flags" value
     /// received with [`value@FileRequest::Clone`].
     flags: fio::OpenFlags,
 
@@ -54,6 +55,15 @@ pub struct FileConnection<T: 'static + File> {
     // Should we need to port to a 128 bit platform, there are static assertions in the code that
     // would fail.
     seek: u64,
+
+    /// Accumulates the number of bytes transferred over this connection, if the file tracks
+    /// throughput.  See [`File::throughput`].
+    throughput: Option<ConnectionThroughput>,
+
+    /// Set for connections that reject writes starting past the end of the file with
+    /// `ZX_ERR_OUT_OF_RANGE`, so that the file can only grow contiguously.  Clones of the
+    /// connection keep the setting.
+    no_sparse_writes: bool,
 }
 
 /// Return type for [`handle_request()`] functions.
@@ -75,6 +85,10 @@ impl<T: 'static + File> FileConnection<T> {
     ///
     /// Per connection buffer is initialized using the `init_buffer` closure, as part of the
     /// connection initialization.
+    ///
+    /// When `no_sparse_writes` is set, writes that start past the current end of the file, and
+    /// would leave a hole in it, are rejected with `ZX_ERR_OUT_OF_RANGE` before reaching
+    /// [`File::write_at`].
     pub fn create_connection(
         scope: ExecutionScope,
         file: Arc<T>,
@@ -83,13 +97,22 @@ impl<T: 'static + File> FileConnection<T> {
         readable: bool,
         writable: bool,
         executable: bool,
+        no_sparse_writes: bool,
     ) {
         // If we failed to send the task to the executor, it is probably shut down or is in the
         // process of shutting down (this is the only error state currently). `server_end` and the
         // file will be closed when they're dropped - there seems to be no error to report there.
         let _ = scope.clone().spawn_with_shutdown(move |shutdown| {
             Self::create_connection_async(
-                scope, file, flags, server_end, readable, writable, executable, shutdown,
+                scope,
+                file,
+                flags,
+                server_end,
+                readable,
+                writable,
+                executable,
+                no_sparse_writes,
+                shutdown,
             )
         });
     }
@@ -103,10 +126,11 @@ impl<T: 'static + File> FileConnection<T> {
         readable: bool,
         writable: bool,
         executable: bool,
+        no_sparse_writes: bool,
         shutdown: oneshot::Receiver<()>,
     ) {
         // RAII helper that ensures that the file is closed if we fail to create the connection.
-        let file = OpenFile::new(file, scope.clone());
+        let mut file = OpenFile::new(file, scope.clone());
 
         let flags = match new_connection_validate_flags(
             flags, readable, writable, executable, /*append_allowed=*/ true,
@@ -118,6 +142,20 @@ impl<T: 'static + File> FileConnection<T> {
             }
         };
 
+        // Held until the connection is closed.
+        let _slot = match scope.acquire_connection() {
+            Ok(slot) => slot,
+            Err(status) => {
+                send_on_open_with_error(flags, server_end, status);
+                return;
+            }
+        };
+
+        if let Err(status) = file.acquire_exclusive() {
+            send_on_open_with_error(flags, server_end, status);
+            return;
+        }
+
         match File::open(file.as_ref(), flags).await {
             Ok(()) => (),
             Err(status) => {
@@ -131,6 +169,7 @@ impl<T: 'static + File> FileConnection<T> {
                 send_on_open_with_error(flags, server_end, status);
                 return;
             }
+            file.touch_modified();
         }
 
         let info = if flags.intersects(fio::OpenFlags::DESCRIBE) {
@@ -164,9 +203,27 @@ impl<T: 'static + File> FileConnection<T> {
             }
         }
 
-        FileConnection { scope: scope.clone(), file, requests, flags, seek: 0 }
-            .handle_requests(shutdown)
-            .await;
+        let throughput = file.throughput().map(|recorder| recorder.new_connection());
+
+        FileConnection {
+            scope: scope.clone(),
+            file,
+            requests,
+            flags,
+            seek: 0,
+            throughput,
+            no_sparse_writes,
+        }
+        .handle_requests(shutdown)
+        .await;
+    }
+
+    /// Returns the current seek position of this connection and its flags, masked the same way
+    /// as for `GetFlags`.  Lets tests inspect the state of a connection directly, without going
+    /// through FIDL.
+    #[cfg(test)]
+    pub(crate) fn debug_info(&self) -> (u64, fio::OpenFlags) {
+        (self.seek, self.flags & GET_FLAGS_VISIBLE)
     }
 
     async fn handle_requests(mut self, mut shutdown: oneshot::Receiver<()>) {
@@ -183,6 +240,12 @@ impl<T: 'static + File> FileConnection<T> {
             };
 
             let state = match request {
+                Err(fidl::Error::UnknownOrdinal { ordinal, protocol_name }) => {
+                    // A method added to the protocol after this connection was compiled.  Clients
+                    // probing for newer methods should see a clear error, rather than a closed
+                    // channel.
+                    self.close_unsupported(&format!("{} ordinal {:#x}", protocol_name, ordinal))
+                }
                 Err(_) => {
                     // FIDL level error, such as invalid message format and alike.  Close the
                     // connection on any unexpected error.
@@ -217,10 +280,9 @@ impl<T: 'static + File> FileConnection<T> {
                 fuchsia_trace::duration!("storage", "File::Clone");
                 self.handle_clone(self.flags, flags, object);
             }
-            fio::FileRequest::Reopen { options, object_request, control_handle: _ } => {
+            fio::FileRequest::Reopen { options: _, object_request: _, control_handle: _ } => {
                 fuchsia_trace::duration!("storage", "File::Reopen");
-                let _ = object_request;
-                todo!("https://fxbug.dev/77623: options={:?}", options);
+                return Ok(self.close_unsupported("File::Reopen"));
             }
             fio::FileRequest::Close { responder } => {
                 fuchsia_trace::duration!("storage", "File::Close");
@@ -231,10 +293,9 @@ impl<T: 'static + File> FileConnection<T> {
                 fuchsia_trace::duration!("storage", "File::Describe");
                 responder.send(&mut self.file.describe(self.flags)?)?;
             }
-            fio::FileRequest::Describe2 { query, responder } => {
+            fio::FileRequest::Describe2 { query: _, responder: _ } => {
                 fuchsia_trace::duration!("storage", "File::Describe2");
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: query={:?}", query);
+                return Ok(self.close_unsupported("File::Describe2"));
             }
             fio::FileRequest::Sync { responder } => {
                 fuchsia_trace::duration!("storage", "File::Sync");
@@ -252,13 +313,15 @@ impl<T: 'static + File> FileConnection<T> {
             }
             fio::FileRequest::GetAttributes { query, responder } => {
                 fuchsia_trace::duration!("storage", "File::GetAttributes");
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: query={:?}", query);
+                let result = self.file.get_attributes(query).await.map(|attrs| {
+                    let fio::NodeAttributes2 { mutable_attributes, immutable_attributes } = attrs;
+                    (mutable_attributes, immutable_attributes)
+                });
+                responder.send(&mut result.map_err(zx::Status::into_raw))?;
             }
-            fio::FileRequest::UpdateAttributes { attributes, responder } => {
+            fio::FileRequest::UpdateAttributes { attributes: _, responder } => {
                 fuchsia_trace::duration!("storage", "File::UpdateAttributes");
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: attributes={:?}", attributes);
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
             }
             fio::FileRequest::Read { count, responder } => {
                 fuchsia_trace::duration!("storage", "File::Read", "bytes" => count);
@@ -348,6 +411,15 @@ impl<T: 'static + File> FileConnection<T> {
         Ok(ConnectionState::Alive)
     }
 
+    /// Closes the connection with a `ZX_ERR_NOT_SUPPORTED` epitaph, for requests this connection
+    /// does not implement and that can not report an error in their response.  `method` identifies
+    /// the request in the log.
+    fn close_unsupported(&self, method: &str) -> ConnectionState {
+        warn!("{} is not supported, closing the file connection", method);
+        self.requests.control_handle().shutdown_with_epitaph(zx::Status::NOT_SUPPORTED);
+        ConnectionState::Dropped
+    }
+
     fn handle_clone(
         &mut self,
         parent_flags: fio::OpenFlags,
@@ -362,8 +434,22 @@ impl<T: 'static + File> FileConnection<T> {
             }
         };
 
-        let file: Arc<dyn DirectoryEntry> = self.file.clone();
-        file.open(self.scope.clone(), flags, 0, Path::dot(), server_end);
+        if let Some(observer) = self.scope.clone_observer() {
+            observer(flags);
+        }
+
+        // `flags` can not hold more rights than this connection has, so they were already
+        // validated against the file.
+        Self::create_connection(
+            self.scope.clone(),
+            (*self.file).clone(),
+            flags,
+            server_end,
+            true,
+            true,
+            true,
+            self.no_sparse_writes,
+        );
     }
 
     async fn handle_get_attr(&mut self) -> (zx::Status, fio::NodeAttributes) {
@@ -396,11 +482,22 @@ impl<T: 'static + File> FileConnection<T> {
             return Err(zx::Status::OUT_OF_RANGE);
         }
 
+        // There is nothing to read, so do not bother the file.
+        if count == 0 {
+            return Ok(vec![]);
+        }
+
+        let start = zx::Time::get_monotonic();
         let mut buffer = vec![0u8; count as usize];
         let count = self.file.read_at(offset, &mut buffer[..]).await?;
         let () = buffer.resize_with(count.try_into().unwrap(), || {
             panic!("unexpected call on vector trimming")
         });
+        let () = self.file.verify_range(offset, count, &buffer).await?;
+        if let Some(throughput) = &self.throughput {
+            throughput.record_read(count, zx::Time::get_monotonic() - start);
+        }
+        self.file.record_io(count, 0);
         Ok(buffer)
     }
 
@@ -410,7 +507,13 @@ impl<T: 'static + File> FileConnection<T> {
         }
 
         if self.flags.intersects(fio::OpenFlags::APPEND) {
+            let start = zx::Time::get_monotonic();
             let (bytes, offset) = self.file.append(content).await?;
+            if let Some(throughput) = &self.throughput {
+                throughput.record_write(bytes, zx::Time::get_monotonic() - start);
+            }
+            self.file.record_io(0, bytes);
+            self.file.touch_modified();
             self.seek = offset;
             Ok(bytes)
         } else {
@@ -425,7 +528,42 @@ impl<T: 'static + File> FileConnection<T> {
             return Err(zx::Status::BAD_HANDLE);
         }
 
-        self.file.write_at(offset, content).await
+        // The write must end at an offset representable as a `u64`, regardless of the width of
+        // `usize` on the target.
+        if offset.checked_add(content.len() as u64).is_none() {
+            return Err(zx::Status::OUT_OF_RANGE);
+        }
+
+        if self.no_sparse_writes && offset > self.file.get_size().await? {
+            return Err(zx::Status::OUT_OF_RANGE);
+        }
+
+        let start = zx::Time::get_monotonic();
+        let actual = self.file.write_at(offset, content).await?;
+        if let Some(throughput) = &self.throughput {
+            throughput.record_write(actual, zx::Time::get_monotonic() - start);
+        }
+        self.file.record_io(0, actual);
+        self.file.touch_modified();
+        Ok(actual)
+    }
+
+    /// Writes `content` at `offset`, same as the `WriteAt` request, and returns the number of bytes
+    /// written together with the size of the file after the write.  The size is only queried
+    /// again with [`File::get_size`] if the write extended the file.
+    pub async fn write_at_reporting(
+        &mut self,
+        offset: u64,
+        content: &[u8],
+    ) -> Result<(u64, u64), zx::Status> {
+        let old_size = self.file.get_size().await?;
+        let actual = self.handle_write_at(offset, content).await?;
+        let new_size = if offset.saturating_add(actual) > old_size {
+            self.file.get_size().await?
+        } else {
+            old_size
+        };
+        Ok((actual, new_size))
     }
 
     /// Move seek position to byte `offset` relative to the origin specified by `start`.
@@ -480,7 +618,9 @@ impl<T: 'static + File> FileConnection<T> {
             return Err(zx::Status::BAD_HANDLE);
         }
 
-        self.file.truncate(length).await
+        self.file.truncate(length).await?;
+        self.file.touch_modified();
+        Ok(())
     }
 
     async fn handle_get_buffer(
@@ -495,9 +635,22 @@ impl<T: 'static + File> FileConnection<T> {
 #[cfg(test)]
 mod tests {
     use {
-        super::*, assert_matches::assert_matches, async_trait::async_trait,
-        fuchsia_async as fasync, fuchsia_zircon as zx, futures::prelude::*,
-        lazy_static::lazy_static, std::sync::Mutex,
+        super::*,
+        crate::{
+            directory::entry::DirectoryEntry,
+            file::{ExclusiveOpen, ThroughputRecorder, ThroughputSnapshot},
+            path::Path,
+        },
+        assert_matches::assert_matches,
+        async_trait::async_trait,
+        fuchsia_async as fasync,
+        fuchsia_zircon::{self as zx, HandleBased as _},
+        futures::prelude::*,
+        lazy_static::lazy_static,
+        std::sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
     };
 
     #[derive(Debug, PartialEq)]
@@ -522,8 +675,20 @@ mod tests {
         operations: Mutex<Vec<FileOperation>>,
         /// Callback used to determine how to respond to given operation.
         callback: MockCallbackType,
-        /// Only used for get_size/get_attributes
-        file_size: u64,
+        /// Only used for get_size/get_attributes.  Grows when `write_at` writes past the end.
+        file_size: AtomicU64,
+        /// Set for files that only allow one connection at a time.
+        exclusive: Option<ExclusiveOpen>,
+        /// Set for files that track throughput of their connections.
+        throughput: Option<ThroughputRecorder>,
+        /// Event reported by `describe`, once set.
+        describe_event: Mutex<Option<zx::Event>>,
+        /// Index of a `MOCK_FILE_BLOCK_SIZE` block that `read_at` returns corrupted content for.
+        tampered_block: Option<u64>,
+        /// Totals of the bytes read and written, as reported by `record_io`.
+        io_totals: Mutex<(u64, u64)>,
+        /// Number of `touch_modified` calls.
+        touches: AtomicU64,
     }
 
     lazy_static! {
@@ -533,13 +698,26 @@ mod tests {
     const MOCK_FILE_LINKS: u64 = 2;
     const MOCK_FILE_CREATION_TIME: u64 = 10;
     const MOCK_FILE_MODIFICATION_TIME: u64 = 100;
+    const MOCK_FILE_BLOCK_SIZE: u64 = 16;
     impl MockFile {
         pub fn new(callback: MockCallbackType) -> Arc<Self> {
-            Arc::new(MockFile {
+            Arc::new(Self::with_callback(callback))
+        }
+
+        /// Returns a file in the default state.  Tests that need other settings override them with
+        /// `MockFile { exclusive: ..., ..MockFile::with_callback(callback) }`.
+        fn with_callback(callback: MockCallbackType) -> Self {
+            MockFile {
                 operations: Mutex::new(Vec::new()),
                 callback,
-                file_size: *MOCK_FILE_SIZE,
-            })
+                file_size: AtomicU64::new(*MOCK_FILE_SIZE),
+                exclusive: None,
+                throughput: None,
+                describe_event: Mutex::new(None),
+                tampered_block: None,
+                io_totals: Mutex::new((0, 0)),
+                touches: AtomicU64::new(0),
+            }
         }
 
         fn handle_operation(&self, operation: FileOperation) -> Result<(), zx::Status> {
@@ -567,20 +745,42 @@ mod tests {
             let mut i = offset;
             buffer.fill_with(|| {
                 let v = (i % 256) as u8;
+                let v = match self.tampered_block {
+                    Some(block) if i / MOCK_FILE_BLOCK_SIZE == block => !v,
+                    _ => v,
+                };
                 i += 1;
                 v
             });
             Ok(count)
         }
 
+        async fn verify_range(
+            &self,
+            offset: u64,
+            length: u64,
+            data: &[u8],
+        ) -> Result<(), zx::Status> {
+            assert_eq!(length, data.len() as u64);
+            // The expected content is known, so there is no need to actually hash the blocks.
+            let expected = (offset..offset + length).map(|i| (i % 256) as u8);
+            if data.iter().copied().eq(expected) {
+                Ok(())
+            } else {
+                Err(zx::Status::IO_DATA_INTEGRITY)
+            }
+        }
+
         async fn write_at(&self, offset: u64, content: &[u8]) -> Result<u64, zx::Status> {
             self.handle_operation(FileOperation::WriteAt { offset, content: content.to_vec() })?;
+            self.file_size.fetch_max(offset + content.len() as u64, Ordering::Relaxed);
             Ok(content.len() as u64)
         }
 
         async fn append(&self, content: &[u8]) -> Result<(u64, u64), zx::Status> {
             self.handle_operation(FileOperation::Append { content: content.to_vec() })?;
-            Ok((content.len() as u64, self.file_size + content.len() as u64))
+            let file_size = self.file_size.load(Ordering::Relaxed);
+            Ok((content.len() as u64, file_size + content.len() as u64))
         }
 
         async fn truncate(&self, length: u64) -> Result<(), zx::Status> {
@@ -597,16 +797,17 @@ mod tests {
 
         async fn get_size(&self) -> Result<u64, zx::Status> {
             self.handle_operation(FileOperation::GetSize)?;
-            Ok(self.file_size)
+            Ok(self.file_size.load(Ordering::Relaxed))
         }
 
         async fn get_attrs(&self) -> Result<fio::NodeAttributes, zx::Status> {
             self.handle_operation(FileOperation::GetAttrs)?;
+            let file_size = self.file_size.load(Ordering::Relaxed);
             Ok(fio::NodeAttributes {
                 mode: fio::MODE_TYPE_FILE,
                 id: MOCK_FILE_ID,
-                content_size: self.file_size,
-                storage_size: 2 * self.file_size,
+                content_size: file_size,
+                storage_size: 2 * file_size,
                 link_count: MOCK_FILE_LINKS,
                 creation_time: MOCK_FILE_CREATION_TIME,
                 modification_time: MOCK_FILE_MODIFICATION_TIME,
@@ -630,6 +831,32 @@ mod tests {
         async fn sync(&self) -> Result<(), zx::Status> {
             self.handle_operation(FileOperation::Sync)
         }
+
+        fn exclusive(&self) -> Option<&ExclusiveOpen> {
+            self.exclusive.as_ref()
+        }
+
+        fn throughput(&self) -> Option<&ThroughputRecorder> {
+            self.throughput.as_ref()
+        }
+
+        fn record_io(&self, bytes_read: u64, bytes_written: u64) {
+            let mut io_totals = self.io_totals.lock().unwrap();
+            io_totals.0 += bytes_read;
+            io_totals.1 += bytes_written;
+        }
+
+        fn touch_modified(&self) {
+            self.touches.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn describe(&self, _connection_flags: fio::OpenFlags) -> Result<fio::NodeInfo, zx::Status> {
+            let event = match &*self.describe_event.lock().unwrap() {
+                Some(event) => Some(event.duplicate_handle(zx::Rights::SAME_RIGHTS)?),
+                None => None,
+            };
+            Ok(fio::NodeInfo::File(fio::FileObject { event, stream: None }))
+        }
     }
 
     impl DirectoryEntry for MockFile {
@@ -651,6 +878,7 @@ mod tests {
                 true,
                 true,
                 false,
+                false,
             );
         }
 
@@ -680,21 +908,45 @@ mod tests {
 
     fn init_mock_file(callback: MockCallbackType, flags: fio::OpenFlags) -> TestEnv {
         let file = MockFile::new(callback);
+        let scope = ExecutionScope::new();
+        let proxy = open_mock_file(&scope, &file, flags);
+        TestEnv { file, proxy, scope }
+    }
+
+    /// Opens a new connection to `file`, running in `scope`.
+    fn open_mock_file(
+        scope: &ExecutionScope,
+        file: &Arc<MockFile>,
+        flags: fio::OpenFlags,
+    ) -> fio::FileProxy {
         let (proxy, server_end) =
             fidl::endpoints::create_proxy::<fio::FileMarker>().expect("Create proxy to succeed");
+        file.clone().open(scope.clone(), flags, 0, Path::dot(), server_end.into_channel().into());
+        proxy
+    }
 
+    /// Returns a connection to `file` that is not running, so that tests can call its methods
+    /// directly, and a proxy whose requests are queued on the connection.
+    fn mock_connection(
+        file: &Arc<MockFile>,
+        flags: fio::OpenFlags,
+    ) -> (fio::FileProxy, FileConnection<MockFile>) {
         let scope = ExecutionScope::new();
-        FileConnection::create_connection(
-            scope.clone(),
-            file.clone(),
-            flags,
-            server_end.into_channel().into(),
-            true,
-            true,
-            false,
-        );
-
-        TestEnv { file, proxy, scope }
+        let (proxy, requests) =
+            fidl::endpoints::create_proxy_and_stream::<fio::FileMarker>().unwrap();
+        let file = OpenFile::new(file.clone(), scope.clone());
+        (
+            proxy,
+            FileConnection {
+                scope,
+                file,
+                requests,
+                flags,
+                seek: 0,
+                throughput: None,
+                no_sparse_writes: false,
+            },
+        )
     }
 
     #[fasync::run_singlethreaded(test)]
@@ -758,6 +1010,78 @@ mod tests {
         );
     }
 
+    async fn clone_status(proxy: &fio::FileProxy, flags: fio::OpenFlags) -> zx::Status {
+        let (clone_proxy, remote) = fidl::endpoints::create_proxy::<fio::FileMarker>().unwrap();
+        proxy.clone(flags | fio::OpenFlags::DESCRIBE, remote.into_channel().into()).unwrap();
+        match clone_proxy.take_event_stream().try_next().await.unwrap() {
+            Some(fio::FileEvent::OnOpen_ { s, .. }) => zx::Status::from_raw(s),
+            e => panic!("Expected OnOpen event, got {:?}", e),
+        }
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_clone_fewer_rights() {
+        let env = init_mock_file(
+            Box::new(always_succeed_callback),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        );
+        let (clone_proxy, remote) = fidl::endpoints::create_proxy::<fio::FileMarker>().unwrap();
+        env.proxy.clone(fio::OpenFlags::RIGHT_READABLE, remote.into_channel().into()).unwrap();
+
+        let _: Vec<u8> = clone_proxy.read(5).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let result = clone_proxy.write(b"foo").await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::BAD_HANDLE));
+
+        let events = env.file.operations.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                FileOperation::Init {
+                    flags: fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE
+                },
+                FileOperation::Init { flags: fio::OpenFlags::RIGHT_READABLE },
+                FileOperation::ReadAt { offset: 0, count: 5 },
+            ]
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_clone_more_rights_denied() {
+        let env = init_mock_file(Box::new(always_succeed_callback), fio::OpenFlags::RIGHT_READABLE);
+        assert_eq!(
+            clone_status(
+                &env.proxy,
+                fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE
+            )
+            .await,
+            zx::Status::ACCESS_DENIED
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_clone_same_rights_with_specific_right_rejected() {
+        let env = init_mock_file(
+            Box::new(always_succeed_callback),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        );
+        assert_eq!(
+            clone_status(
+                &env.proxy,
+                fio::OpenFlags::CLONE_SAME_RIGHTS | fio::OpenFlags::RIGHT_READABLE
+            )
+            .await,
+            zx::Status::INVALID_ARGS
+        );
+
+        let events = env.file.operations.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![FileOperation::Init {
+                flags: fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE
+            }]
+        );
+    }
+
     #[fasync::run_singlethreaded(test)]
     async fn test_close_succeeds() {
         let env = init_mock_file(Box::new(always_succeed_callback), fio::OpenFlags::RIGHT_READABLE);
@@ -789,6 +1113,32 @@ mod tests {
         );
     }
 
+    #[fasync::run_singlethreaded(test)]
+    async fn test_close_reports_error_after_write() {
+        // A file that validates the written content when the connection is closed.
+        let env = init_mock_file(
+            Box::new(|op: &FileOperation| match op {
+                FileOperation::Close => zx::Status::INVALID_ARGS,
+                _ => zx::Status::OK,
+            }),
+            fio::OpenFlags::RIGHT_WRITABLE,
+        );
+        let _: u64 =
+            env.proxy.write(b"invalid").await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let status = env.proxy.close().await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(status, Err(zx::Status::INVALID_ARGS));
+
+        let events = env.file.operations.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                FileOperation::Init { flags: fio::OpenFlags::RIGHT_WRITABLE },
+                FileOperation::WriteAt { offset: 0, content: b"invalid".to_vec() },
+                FileOperation::Close,
+            ]
+        );
+    }
+
     #[fasync::run_singlethreaded(test)]
     async fn test_close_called_when_dropped() {
         let env = init_mock_file(Box::new(always_succeed_callback), fio::OpenFlags::RIGHT_READABLE);
@@ -817,6 +1167,33 @@ mod tests {
         };
     }
 
+    #[fasync::run_singlethreaded(test)]
+    async fn test_describe_reflects_current_state() {
+        let env = init_mock_file(
+            Box::new(always_succeed_callback),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE,
+        );
+        match env.proxy.take_event_stream().try_next().await.unwrap() {
+            Some(fio::FileEvent::OnOpen_ { s, info }) => {
+                assert_eq!(zx::Status::from_raw(s), zx::Status::OK);
+                assert_matches!(
+                    info.as_deref(),
+                    Some(fio::NodeInfo::File(fio::FileObject { event: None, stream: None }))
+                );
+            }
+            e => panic!("Expected OnOpen event, got {:?}", e),
+        }
+
+        // The file gains an event after the connection was opened.
+        *env.file.describe_event.lock().unwrap() = Some(zx::Event::create().unwrap());
+
+        let info = env.proxy.describe().await.unwrap();
+        assert_matches!(
+            info,
+            fio::NodeInfo::File(fio::FileObject { event: Some(_), stream: None })
+        );
+    }
+
     #[fasync::run_singlethreaded(test)]
     async fn test_getattr() {
         let env = init_mock_file(Box::new(always_succeed_callback), fio::OpenFlags::empty());
@@ -861,6 +1238,56 @@ mod tests {
         );
     }
 
+    #[fasync::run_singlethreaded(test)]
+    async fn test_read_at_zero_count() {
+        let env = init_mock_file(Box::new(always_succeed_callback), fio::OpenFlags::RIGHT_READABLE);
+        let data = env.proxy.read_at(0, 10).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        assert_eq!(data, Vec::<u8>::new());
+        let data = env.proxy.read(0).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        assert_eq!(data, Vec::<u8>::new());
+
+        let events = env.file.operations.lock().unwrap();
+        assert_eq!(*events, vec![FileOperation::Init { flags: fio::OpenFlags::RIGHT_READABLE }]);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_getbuffer_sharing_modes() {
+        let env = init_mock_file(
+            Box::new(always_succeed_callback),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        );
+        let flag_combinations = [
+            fio::VmoFlags::READ,
+            fio::VmoFlags::READ | fio::VmoFlags::SHARED_BUFFER,
+            fio::VmoFlags::READ | fio::VmoFlags::PRIVATE_CLONE,
+            fio::VmoFlags::READ | fio::VmoFlags::WRITE | fio::VmoFlags::PRIVATE_CLONE,
+            fio::VmoFlags::READ | fio::VmoFlags::WRITE | fio::VmoFlags::SHARED_BUFFER,
+        ];
+        for flags in flag_combinations {
+            let result =
+                env.proxy.get_backing_memory(flags).await.unwrap().map_err(zx::Status::from_raw);
+            assert_eq!(result, Err(zx::Status::NOT_SUPPORTED));
+        }
+
+        // Inconsistent sharing modes are rejected before reaching the file.
+        let result = env
+            .proxy
+            .get_backing_memory(
+                fio::VmoFlags::READ | fio::VmoFlags::PRIVATE_CLONE | fio::VmoFlags::SHARED_BUFFER,
+            )
+            .await
+            .unwrap()
+            .map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::INVALID_ARGS));
+
+        let events = env.file.operations.lock().unwrap();
+        let mut expected = vec![FileOperation::Init {
+            flags: fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        }];
+        expected.extend(flag_combinations.iter().map(|&flags| FileOperation::GetBuffer { flags }));
+        assert_eq!(*events, expected);
+    }
+
     #[fasync::run_singlethreaded(test)]
     async fn test_getbuffer_no_perms() {
         let env = init_mock_file(Box::new(always_succeed_callback), fio::OpenFlags::empty());
@@ -1262,4 +1689,308 @@ mod tests {
             unreachable!();
         }
     }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_exclusive_open() {
+        let file = Arc::new(MockFile {
+            exclusive: Some(ExclusiveOpen::new()),
+            ..MockFile::with_callback(Box::new(always_succeed_callback))
+        });
+        let scope = ExecutionScope::new();
+        let open = || {
+            open_mock_file(&scope, &file, fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE)
+        };
+        async fn open_status(proxy: &fio::FileProxy) -> zx::Status {
+            match proxy.take_event_stream().try_next().await.unwrap() {
+                Some(fio::FileEvent::OnOpen_ { s, .. }) => zx::Status::from_raw(s),
+                e => panic!("Expected OnOpen event, got {:?}", e),
+            }
+        }
+
+        let first = open();
+        assert_eq!(open_status(&first).await, zx::Status::OK);
+
+        let second = open();
+        assert_eq!(open_status(&second).await, zx::Status::ALREADY_BOUND);
+
+        let () = first.close().await.unwrap().map_err(zx::Status::from_raw).unwrap();
+
+        let third = open();
+        assert_eq!(open_status(&third).await, zx::Status::OK);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_throughput() {
+        let file = Arc::new(MockFile {
+            throughput: Some(ThroughputRecorder::new()),
+            ..MockFile::with_callback(Box::new(always_succeed_callback))
+        });
+        let scope = ExecutionScope::new();
+        let open = || {
+            open_mock_file(
+                &scope,
+                &file,
+                fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            )
+        };
+
+        let first = open();
+        let _: Vec<u8> = first.read(6).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let _: Vec<u8> =
+            first.read_at(100, 10).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let _: u64 = first.write(&[1, 2, 3]).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+
+        let second = open();
+        let _: u64 =
+            second.write_at(&[1; 20], 5).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        // Failed requests are not accounted for.
+        let result = second.read(fio::MAX_BUF + 1).await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::OUT_OF_RANGE));
+
+        let strip_time = |s: ThroughputSnapshot| (s.reads, s.bytes_read, s.writes, s.bytes_written);
+        let snapshot = || strip_time(file.throughput.as_ref().unwrap().snapshot());
+        assert_eq!(snapshot(), (2, 106, 2, 23));
+
+        // Numbers of closed connections are kept.
+        let () = first.close().await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let () = second.close().await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        assert_eq!(snapshot(), (2, 106, 2, 23));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_write_touches_modified() {
+        let env = init_mock_file(
+            Box::new(always_succeed_callback),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        );
+
+        let _: Vec<u8> = env.proxy.read(6).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        assert_eq!(env.file.touches.load(Ordering::Relaxed), 0);
+
+        let _: u64 =
+            env.proxy.write(&[1, 2, 3]).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        assert_eq!(env.file.touches.load(Ordering::Relaxed), 1);
+
+        // Failed writes do not touch the file.
+        let result =
+            env.proxy.write_at(&[1], u64::MAX).await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::OUT_OF_RANGE));
+        assert_eq!(env.file.touches.load(Ordering::Relaxed), 1);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_get_attributes_only_requested() {
+        let env = init_mock_file(Box::new(always_succeed_callback), fio::OpenFlags::RIGHT_READABLE);
+
+        let (mutable_attributes, immutable_attributes) = env
+            .proxy
+            .get_attributes(fio::NodeAttributesQuery::CONTENT_SIZE)
+            .await
+            .unwrap()
+            .map_err(zx::Status::from_raw)
+            .unwrap();
+
+        assert_eq!(mutable_attributes, fio::MutableNodeAttributes::EMPTY);
+        assert_eq!(
+            immutable_attributes,
+            fio::ImmutableNodeAttributes {
+                content_size: Some(*MOCK_FILE_SIZE),
+                ..fio::ImmutableNodeAttributes::EMPTY
+            }
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_record_io() {
+        let file = MockFile::new(Box::new(always_succeed_callback));
+        let proxy = open_mock_file(
+            &ExecutionScope::new(),
+            &file,
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        );
+
+        let _: Vec<u8> = proxy.read(6).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let _: Vec<u8> =
+            proxy.read_at(100, 10).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let _: u64 = proxy.write(&[1, 2, 3]).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        let _: u64 =
+            proxy.write_at(&[1; 20], 5).await.unwrap().map_err(zx::Status::from_raw).unwrap();
+        // Failed requests are not reported.
+        let result = proxy.read(fio::MAX_BUF + 1).await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::OUT_OF_RANGE));
+
+        assert_eq!(*file.io_totals.lock().unwrap(), (16, 23));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_debug_info_tracks_seek() {
+        let file = MockFile::new(Box::new(always_succeed_callback));
+        let (proxy, mut connection) =
+            mock_connection(&file, fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE);
+        assert_eq!(connection.debug_info(), (0, fio::OpenFlags::RIGHT_READABLE));
+
+        for (count, expected_seek) in [(5, 5), (7, 12), (0, 12)] {
+            let read = proxy.read(count);
+            let request = connection.requests.next().await.unwrap().unwrap();
+            assert!(matches!(connection.handle_request(request).await, Ok(ConnectionState::Alive)));
+            let _: Vec<u8> = read.await.unwrap().map_err(zx::Status::from_raw).unwrap();
+            assert_eq!(connection.debug_info(), (expected_seek, fio::OpenFlags::RIGHT_READABLE));
+        }
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_unknown_ordinal_closes_with_not_supported() {
+        let (client, server) = zx::Channel::create().unwrap();
+        MockFile::new(Box::new(always_succeed_callback)).open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            0,
+            Path::dot(),
+            ServerEnd::new(server),
+        );
+
+        // A one-way message header: zero transaction id, the V2 wire format flag, the FIDL magic
+        // number, and an ordinal no fuchsia.io method uses.
+        let mut message = vec![0, 0, 0, 0, 2, 0, 0, 1];
+        message.extend_from_slice(&0x0123_4567_89ab_cdefu64.to_le_bytes());
+        client.write(&message, &mut vec![]).unwrap();
+
+        let proxy = fio::FileProxy::new(fasync::Channel::from_channel(client).unwrap());
+        assert_matches!(
+            proxy.take_event_stream().next().await,
+            Some(Err(fidl::Error::ClientChannelClosed { status: zx::Status::NOT_SUPPORTED, .. }))
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_no_sparse_writes() {
+        let file = MockFile::new(Box::new(always_succeed_callback));
+        let scope = ExecutionScope::new();
+        let (proxy, server_end) = fidl::endpoints::create_proxy::<fio::FileMarker>().unwrap();
+        FileConnection::create_connection(
+            scope.clone(),
+            file.clone(),
+            fio::OpenFlags::RIGHT_WRITABLE,
+            server_end.into_channel().into(),
+            true,
+            true,
+            false,
+            true,
+        );
+
+        // Writing past the end of the file would leave a hole.
+        let result = proxy
+            .write_at(b"gap", *MOCK_FILE_SIZE + 1)
+            .await
+            .unwrap()
+            .map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::OUT_OF_RANGE));
+
+        let result =
+            proxy.write_at(b"next", *MOCK_FILE_SIZE).await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(result, Ok(4));
+
+        // Clones keep the option.
+        let (clone, server_end) = fidl::endpoints::create_proxy::<fio::FileMarker>().unwrap();
+        proxy.clone(fio::OpenFlags::CLONE_SAME_RIGHTS, server_end.into_channel().into()).unwrap();
+        let result = clone
+            .write_at(b"gap", *MOCK_FILE_SIZE + 10)
+            .await
+            .unwrap()
+            .map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::OUT_OF_RANGE));
+
+        // Other connections to the same file may still write past the end.
+        let other = open_mock_file(&scope, &file, fio::OpenFlags::RIGHT_WRITABLE);
+        let result = other
+            .write_at(b"gap", *MOCK_FILE_SIZE + 10)
+            .await
+            .unwrap()
+            .map_err(zx::Status::from_raw);
+        assert_eq!(result, Ok(3));
+
+        let writes: Vec<_> = file
+            .operations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|op| match op {
+                FileOperation::WriteAt { offset, .. } => Some(*offset),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(writes, vec![*MOCK_FILE_SIZE, *MOCK_FILE_SIZE + 10]);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_write_at_offset_overflow() {
+        let file = MockFile::new(Box::new(always_succeed_callback));
+        let proxy = open_mock_file(&ExecutionScope::new(), &file, fio::OpenFlags::RIGHT_WRITABLE);
+
+        let result =
+            proxy.write_at(b"data", u64::MAX - 1).await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(result, Err(zx::Status::OUT_OF_RANGE));
+
+        // Ending exactly at `u64::MAX` does not overflow.
+        let result =
+            proxy.write_at(b"data", u64::MAX - 4).await.unwrap().map_err(zx::Status::from_raw);
+        assert_eq!(result, Ok(4));
+
+        let events = file.operations.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                FileOperation::Init { flags: fio::OpenFlags::RIGHT_WRITABLE },
+                FileOperation::WriteAt { offset: u64::MAX - 4, content: b"data".to_vec() },
+            ]
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_write_at_reporting() {
+        let file = MockFile::new(Box::new(always_succeed_callback));
+        let (_proxy, mut connection) = mock_connection(&file, fio::OpenFlags::RIGHT_WRITABLE);
+
+        // Writing inside the file does not change the size.
+        assert_eq!(connection.write_at_reporting(10, b"inside").await, Ok((6, *MOCK_FILE_SIZE)));
+
+        // Writing past the end extends it.
+        let offset = *MOCK_FILE_SIZE + 10;
+        assert_eq!(connection.write_at_reporting(offset, b"past").await, Ok((4, offset + 4)));
+
+        let events = file.operations.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                FileOperation::GetSize,
+                FileOperation::WriteAt { offset: 10, content: b"inside".to_vec() },
+                FileOperation::GetSize,
+                FileOperation::WriteAt { offset, content: b"past".to_vec() },
+                FileOperation::GetSize,
+            ]
+        );
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn test_verify_range() {
+        let file = Arc::new(MockFile {
+            tampered_block: Some(2),
+            ..MockFile::with_callback(Box::new(always_succeed_callback))
+        });
+        let proxy = open_mock_file(&ExecutionScope::new(), &file, fio::OpenFlags::RIGHT_READABLE);
+
+        // Blocks before the tampered one verify fine.
+        let data = proxy.read_at(2 * MOCK_FILE_BLOCK_SIZE, 0).await.unwrap();
+        assert_eq!(data, Ok((0..2 * MOCK_FILE_BLOCK_SIZE as u8).collect::<Vec<u8>>()));
+
+        // Any read that includes the tampered block is rejected.
+        let result = proxy.read_at(10, 2 * MOCK_FILE_BLOCK_SIZE + 5).await.unwrap();
+        assert_eq!(result.map_err(zx::Status::from_raw), Err(zx::Status::IO_DATA_INTEGRITY));
+        let result = proxy.read_at(100, 0).await.unwrap();
+        assert_eq!(result.map_err(zx::Status::from_raw), Err(zx::Status::IO_DATA_INTEGRITY));
+
+        // And the blocks after it are fine again.
+        let data = proxy.read_at(4, 3 * MOCK_FILE_BLOCK_SIZE).await.unwrap();
+        assert_eq!(data, Ok(vec![48, 49, 50, 51]));
+    }
 }
@@ -9,27 +9,53 @@ use {
 };
 
 /// This struct is a RAII wrapper around a file that will call close() on it unless the
-/// succeed() function is called.
+/// succeed() function is called.  It also releases the exclusive-open state of the file, if it was
+/// acquired with [`OpenFile::acquire_exclusive`].
 pub struct OpenFile<T: 'static + File> {
     file: Option<Arc<T>>,
     scope: ExecutionScope,
+    holds_exclusive: bool,
 }
 
 impl<T: 'static + File> OpenFile<T> {
     pub fn new(file: Arc<T>, scope: ExecutionScope) -> Self {
-        Self { file: Some(file), scope }
+        Self { file: Some(file), scope, holds_exclusive: false }
+    }
+
+    /// If the file only allows one connection at a time, marks it as open by this connection.
+    /// Fails with `ZX_ERR_ALREADY_BOUND` if another connection holds the file open.
+    pub fn acquire_exclusive(&mut self) -> Result<(), Status> {
+        let file = self.file.as_ref().ok_or(Status::BAD_HANDLE)?;
+        if let Some(exclusive) = file.exclusive() {
+            exclusive.acquire()?;
+            self.holds_exclusive = true;
+        }
+        Ok(())
     }
 
     /// Explicitly close the file.
     pub async fn close(&mut self) -> Result<(), Status> {
         let file = self.file.take().ok_or(Status::BAD_HANDLE)?;
-        file.close().await
+        let res = file.close().await;
+        self.release_exclusive(&file);
+        res
+    }
+
+    fn release_exclusive(&mut self, file: &T) {
+        if std::mem::replace(&mut self.holds_exclusive, false) {
+            if let Some(exclusive) = file.exclusive() {
+                exclusive.release();
+            }
+        }
     }
 }
 
 impl<T: 'static + File> Drop for OpenFile<T> {
     fn drop(&mut self) {
         if let Some(file) = self.file.take() {
+            // Release synchronously, so a new connection can be opened as soon as this one is gone,
+            // even if the close() below is still pending.
+            self.release_exclusive(&file);
             let _ = self.scope.spawn_with_shutdown(|shutdown| async move {
                 let _ = file.close().await;
                 std::mem::drop(shutdown);
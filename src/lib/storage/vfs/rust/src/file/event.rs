@@ -0,0 +1,115 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A file wrapper that gives clients an event, which the server signals to tell them about the
+//! state of the file.  See [`with_event`].
+
+use crate::file::{File, FileWrapper, WrapperHooks};
+
+use {
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::{self as zx, HandleBased, Status},
+    std::sync::Arc,
+};
+
+/// Wraps `inner`, so that the `FileObject` sent to clients in `OnOpen` and in response to
+/// `Describe` carries an event.  The server sets and clears signals on the event with
+/// [`WithEvent::signal`], for example `ZX_USER_SIGNAL_0` once data is ready to be read, and clients
+/// wait on their handle to the event.  Clients can not signal the event themselves.
+///
+/// Connections can be granted the `RIGHT_*` flags in `rights`, see [`FileWrapper::new`].  Fails if
+/// the event can not be created.
+pub fn with_event<T: File>(
+    inner: Arc<T>,
+    rights: fio::OpenFlags,
+) -> Result<Arc<WithEvent<T>>, Status> {
+    let event = zx::Event::create()?;
+    Ok(FileWrapper::new(inner, FileEvent { event }, rights))
+}
+
+/// A file that hands out an event to its clients.  See [`with_event`].
+pub type WithEvent<T> = FileWrapper<T, FileEvent>;
+
+/// The event of a [`WithEvent`] file.
+pub struct FileEvent {
+    event: zx::Event,
+}
+
+impl<T: File> WithEvent<T> {
+    /// Clears the signals in `clear_mask` and then sets the signals in `set_mask` on the event
+    /// given to the clients.  Only the user signals can be changed.
+    pub fn signal(&self, clear_mask: zx::Signals, set_mask: zx::Signals) -> Result<(), Status> {
+        self.hooks().event.signal_handle(clear_mask, set_mask)
+    }
+}
+
+impl<T: File> WrapperHooks<T> for FileEvent {
+    fn event(&self, _inner: &T) -> Result<Option<zx::Event>, Status> {
+        // Clients only wait on the event, the server is the one signalling it.
+        self.event.duplicate_handle(zx::Rights::BASIC).map(Some)
+    }
+
+    fn describe(
+        &self,
+        inner: &T,
+        connection_flags: fio::OpenFlags,
+    ) -> Result<fio::NodeInfo, Status> {
+        match inner.describe(connection_flags)? {
+            fio::NodeInfo::File(fio::FileObject { stream, .. }) => {
+                Ok(fio::NodeInfo::File(fio::FileObject { event: self.event(inner)?, stream }))
+            }
+            info => Ok(info),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::with_event;
+
+    use crate::{
+        directory::entry::DirectoryEntry, execution_scope::ExecutionScope, file::mem_file,
+        path::Path,
+    };
+
+    use {
+        assert_matches::assert_matches,
+        fidl::endpoints::create_proxy,
+        fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::{self as zx, AsHandleRef},
+    };
+
+    #[fasync::run_singlethreaded(test)]
+    async fn client_observes_signal() {
+        let file =
+            with_event(mem_file(b"Content".to_vec(), 100), fio::OpenFlags::RIGHT_READABLE).unwrap();
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        file.clone().open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+
+        let event = match proxy.describe().await.unwrap() {
+            fio::NodeInfo::File(fio::FileObject { event: Some(event), .. }) => event,
+            info => panic!("Expected a file with an event, got {:?}", info),
+        };
+        assert_matches!(
+            event.wait_handle(zx::Signals::USER_0, zx::Time::INFINITE_PAST),
+            Err(zx::Status::TIMED_OUT)
+        );
+
+        file.signal(zx::Signals::NONE, zx::Signals::USER_0).unwrap();
+        let observed = fasync::OnSignals::new(&event, zx::Signals::USER_0).await.unwrap();
+        assert!(observed.contains(zx::Signals::USER_0));
+
+        // The client can not change the signals.
+        assert_eq!(
+            event.signal_handle(zx::Signals::USER_0, zx::Signals::NONE),
+            Err(zx::Status::ACCESS_DENIED)
+        );
+    }
+}
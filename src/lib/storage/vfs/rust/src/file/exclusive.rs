@@ -0,0 +1,63 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Support for files that only allow one connection at a time.
+
+use {
+    fuchsia_zircon::Status,
+    std::sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Tracks whether a file that only allows one open connection at a time is currently open.  Files
+/// that want exclusive-open semantics should hold an instance of this type and return it from
+/// [`crate::file::File::exclusive`].  The file connection then takes care of marking the file as
+/// open for the lifetime of the connection.
+#[derive(Debug, Default)]
+pub struct ExclusiveOpen {
+    open: AtomicBool,
+}
+
+impl ExclusiveOpen {
+    /// Creates a new instance for a file that is not open.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if there is a connection currently holding the file open.
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::Acquire)
+    }
+
+    /// Marks the file as open.  Fails with `ZX_ERR_ALREADY_BOUND` if the file is already open.
+    pub(in crate::file) fn acquire(&self) -> Result<(), Status> {
+        self.open
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| ())
+            .map_err(|_| Status::ALREADY_BOUND)
+    }
+
+    /// Marks the file as no longer open, allowing the next [`Self::acquire`] call to succeed.
+    pub(in crate::file) fn release(&self) {
+        self.open.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::ExclusiveOpen, fuchsia_zircon::Status};
+
+    #[test]
+    fn acquire_release() {
+        let exclusive = ExclusiveOpen::new();
+        assert!(!exclusive.is_open());
+
+        assert_eq!(exclusive.acquire(), Ok(()));
+        assert!(exclusive.is_open());
+        assert_eq!(exclusive.acquire(), Err(Status::ALREADY_BOUND));
+
+        exclusive.release();
+        assert!(!exclusive.is_open());
+        assert_eq!(exclusive.acquire(), Ok(()));
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Read-only files whose content is produced by a closure.  See [`read_only_fn`].
+
+use crate::{
+    directory::entry::{DirectoryEntry, EntryInfo},
+    execution_scope::ExecutionScope,
+    file::vmo::asynchronous::{read_only, InitVmoResult, NewVmo},
+    path::Path,
+};
+
+use {
+    fidl::endpoints::ServerEnd, fidl_fuchsia_io as fio, fuchsia_zircon::Vmo,
+    futures::future::BoxFuture, std::sync::Arc,
+};
+
+/// Creates a read-only file whose content is the string returned by `f`, encoded as UTF-8.
+/// Useful for debug files that format some state on demand.
+///
+/// `f` is called every time a connection that can read the file is opened, once the open has
+/// succeeded, and the content is captured at that point: all the reads made through a connection,
+/// and through its clones, observe the same content, even if the state `f` formats changes while
+/// the connection is open.  Opens that fail, and `OPEN_FLAG_NODE_REFERENCE` connections, do not
+/// call `f`.
+pub fn read_only_fn<F>(f: F) -> Arc<ReadOnlyFn<F>>
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    Arc::new(ReadOnlyFn { f })
+}
+
+/// A read-only file with the content produced by a closure.  See [`read_only_fn`].
+pub struct ReadOnlyFn<F>
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    f: F,
+}
+
+impl<F> DirectoryEntry for ReadOnlyFn<F>
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        // Node references do not read the content.
+        let node_reference = flags.intersects(fio::OpenFlags::NODE_REFERENCE);
+        // Every open gets its own file, so that the content is produced for every connection.  The
+        // connection only initializes the file once the path and `flags` are validated.
+        read_only(move || -> BoxFuture<'static, InitVmoResult> {
+            let content = if node_reference { String::new() } else { (self.f)() };
+            Box::pin(async move {
+                let size = content.len() as u64;
+                let vmo = Vmo::create(size)?;
+                vmo.write(content.as_bytes(), 0)?;
+                vmo.set_content_size(&size)?;
+                Ok(NewVmo { vmo, size, capacity: size })
+            })
+        })
+        .open(scope, flags, mode, path, server_end);
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::File)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_only_fn;
+
+    // Macros are exported into the root of the crate.
+    use crate::{assert_close, assert_read};
+
+    use crate::{directory::entry::DirectoryEntry, execution_scope::ExecutionScope, path::Path};
+
+    use {
+        fidl::endpoints::create_proxy,
+        fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::Status,
+        futures::StreamExt as _,
+        std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    #[fasync::run_singlethreaded(test)]
+    async fn content_captured_per_connection() {
+        let counter = AtomicUsize::new(0);
+        let file =
+            read_only_fn(move || format!("Open #{}", counter.fetch_add(1, Ordering::Relaxed)));
+
+        for expected in ["Open #0", "Open #1"] {
+            let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+            file.clone().open(
+                ExecutionScope::new(),
+                fio::OpenFlags::RIGHT_READABLE,
+                0,
+                Path::dot(),
+                server_end.into_channel().into(),
+            );
+            assert_read!(proxy, expected);
+            assert_close!(proxy);
+        }
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn content_not_produced_for_failed_or_node_reference_opens() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let file = read_only_fn({
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::Relaxed);
+                "Content".to_string()
+            }
+        });
+
+        for (flags, path, expected) in [
+            (fio::OpenFlags::RIGHT_WRITABLE, Path::dot(), Status::ACCESS_DENIED),
+            (
+                fio::OpenFlags::RIGHT_READABLE,
+                Path::validate_and_split("nested").unwrap(),
+                Status::NOT_DIR,
+            ),
+            (fio::OpenFlags::NODE_REFERENCE, Path::dot(), Status::OK),
+        ] {
+            let (proxy, server_end) = create_proxy::<fio::NodeMarker>().unwrap();
+            file.clone().open(
+                ExecutionScope::new(),
+                flags | fio::OpenFlags::DESCRIBE,
+                0,
+                path,
+                server_end,
+            );
+            match proxy.take_event_stream().next().await {
+                Some(Ok(fio::NodeEvent::OnOpen_ { s, .. })) => {
+                    assert_eq!(Status::from_raw(s), expected)
+                }
+                event => panic!("Unexpected event: {:?}", event),
+            }
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        file.open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+        assert_read!(proxy, "Content");
+        assert_close!(proxy);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
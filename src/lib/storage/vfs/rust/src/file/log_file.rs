@@ -0,0 +1,230 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Append-only files that retain a bounded window of the most recently written bytes.  See
+//! [`log_file`].
+
+use crate::{
+    common::rights_to_posix_mode_bits,
+    directory::entry::{DirectoryEntry, EntryInfo},
+    execution_scope::ExecutionScope,
+    file::{common::open_file, File},
+    path::Path,
+};
+
+use {
+    async_trait::async_trait,
+    fidl::endpoints::ServerEnd,
+    fidl_fuchsia_io as fio,
+    fidl_fuchsia_mem::Buffer,
+    fuchsia_zircon::Status,
+    std::{
+        collections::VecDeque,
+        convert::TryFrom,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Creates a readable and writable file that acts as a ring buffer of `capacity` bytes.
+///
+/// Every write is appended to the end of the file, regardless of the connection seek position or
+/// the offset of a `WriteAt` request.  When the file holds `capacity` bytes, the oldest bytes are
+/// dropped to make room for the new ones, so a single write larger than `capacity` keeps only its
+/// last `capacity` bytes.  Writes always report the whole content as written.
+///
+/// Reads see the retained window: offset 0 is the oldest byte still held by the file, and the file
+/// size is the length of the window.  The file can not be truncated, and it does not provide a
+/// VMO, so `Truncate` and `GetBuffer` fail with `ZX_ERR_NOT_SUPPORTED`.
+pub fn log_file(capacity: u64) -> Arc<LogFile> {
+    let capacity = usize::try_from(capacity).unwrap_or(usize::MAX);
+    Arc::new(LogFile { window: Mutex::new(VecDeque::new()), capacity })
+}
+
+/// A file holding the tail of everything written to it.  See [`log_file`].
+pub struct LogFile {
+    window: Mutex<VecDeque<u8>>,
+    capacity: usize,
+}
+
+impl LogFile {
+    /// Returns a copy of the bytes currently retained by the file, oldest first.
+    pub fn content(&self) -> Vec<u8> {
+        self.window.lock().unwrap().iter().copied().collect()
+    }
+
+    fn push(&self, content: &[u8]) -> u64 {
+        let mut window = self.window.lock().unwrap();
+        let content = &content[content.len().saturating_sub(self.capacity)..];
+        let overflow = (window.len() + content.len()).saturating_sub(self.capacity);
+        window.drain(..overflow);
+        window.extend(content);
+        window.len() as u64
+    }
+}
+
+impl DirectoryEntry for LogFile {
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        _mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        let rights = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE;
+        open_file(self, scope, flags, path, server_end, rights);
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::File)
+    }
+
+    fn into_file(self: Arc<Self>) -> Option<Arc<dyn File>> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl File for LogFile {
+    async fn open(&self, _flags: fio::OpenFlags) -> Result<(), Status> {
+        Ok(())
+    }
+
+    async fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<u64, Status> {
+        let window = self.window.lock().unwrap();
+        let start = match usize::try_from(offset) {
+            Ok(start) if start < window.len() => start,
+            _ => return Ok(0),
+        };
+        let mut len = 0;
+        for (dst, src) in buffer.iter_mut().zip(window.range(start..)) {
+            *dst = *src;
+            len += 1;
+        }
+        Ok(len)
+    }
+
+    async fn write_at(&self, _offset: u64, content: &[u8]) -> Result<u64, Status> {
+        self.push(content);
+        Ok(content.len() as u64)
+    }
+
+    async fn append(&self, content: &[u8]) -> Result<(u64, u64), Status> {
+        let size = self.push(content);
+        Ok((content.len() as u64, size))
+    }
+
+    async fn truncate(&self, _length: u64) -> Result<(), Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
+    async fn get_buffer(&self, _flags: fio::VmoFlags) -> Result<Buffer, Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
+    async fn get_size(&self) -> Result<u64, Status> {
+        Ok(self.window.lock().unwrap().len() as u64)
+    }
+
+    async fn get_attrs(&self) -> Result<fio::NodeAttributes, Status> {
+        Ok(fio::NodeAttributes {
+            mode: fio::MODE_TYPE_FILE
+                | rights_to_posix_mode_bits(/*r*/ true, /*w*/ true, /*x*/ false),
+            id: fio::INO_UNKNOWN,
+            content_size: self.window.lock().unwrap().len() as u64,
+            storage_size: self.capacity as u64,
+            link_count: 1,
+            creation_time: 0,
+            modification_time: 0,
+        })
+    }
+
+    async fn set_attrs(
+        &self,
+        _flags: fio::NodeAttributeFlags,
+        _attrs: fio::NodeAttributes,
+    ) -> Result<(), Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
+    async fn close(&self) -> Result<(), Status> {
+        Ok(())
+    }
+
+    async fn sync(&self) -> Result<(), Status> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{log_file, LogFile};
+
+    // Macros are exported into the root of the crate.
+    use crate::{assert_close, assert_read, assert_read_at, assert_truncate_err, assert_write};
+
+    use crate::{directory::entry::DirectoryEntry, execution_scope::ExecutionScope, path::Path};
+
+    use {
+        fidl::endpoints::create_proxy, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::Status, std::sync::Arc,
+    };
+
+    fn open(file: &Arc<LogFile>, flags: fio::OpenFlags) -> fio::FileProxy {
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        file.clone().open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE | flags,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+        proxy
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn append_past_capacity_keeps_tail() {
+        let file = log_file(8);
+        let proxy = open(&file, fio::OpenFlags::APPEND);
+
+        assert_write!(proxy, "Hello");
+        assert_write!(proxy, ", world");
+        assert_close!(proxy);
+        assert_eq!(file.content(), b"o, world".to_vec());
+
+        let proxy = open(&file, fio::OpenFlags::empty());
+        assert_read!(proxy, "o, world");
+        assert_read_at!(proxy, 3, "world");
+        assert_read_at!(proxy, 8, "");
+        assert_close!(proxy);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn write_larger_than_capacity_keeps_last_bytes() {
+        let file = log_file(4);
+        let proxy = open(&file, fio::OpenFlags::empty());
+
+        assert_write!(proxy, "ab");
+        assert_write!(proxy, "0123456789");
+        assert_close!(proxy);
+
+        let proxy = open(&file, fio::OpenFlags::empty());
+        assert_read!(proxy, "6789");
+        assert_close!(proxy);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn write_at_appends() {
+        let file = log_file(16);
+        let proxy = open(&file, fio::OpenFlags::empty());
+
+        assert_write!(proxy, "first ");
+        let written =
+            proxy.write_at(b"second", 0).await.unwrap().map_err(Status::from_raw).unwrap();
+        assert_eq!(written, 6);
+        assert_truncate_err!(proxy, 0, Status::NOT_SUPPORTED);
+        assert_close!(proxy);
+        assert_eq!(file.content(), b"first second".to_vec());
+    }
+}
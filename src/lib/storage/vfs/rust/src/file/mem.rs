@@ -0,0 +1,253 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Growable files that keep their content in memory.  See [`mem_file`].
+
+use crate::{
+    common::rights_to_posix_mode_bits,
+    directory::entry::{DirectoryEntry, EntryInfo},
+    execution_scope::ExecutionScope,
+    file::{common::open_file, File},
+    path::Path,
+};
+
+use {
+    async_trait::async_trait,
+    fidl::endpoints::ServerEnd,
+    fidl_fuchsia_io as fio,
+    fidl_fuchsia_mem::Buffer,
+    fuchsia_zircon::Status,
+    std::{
+        convert::TryFrom,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Creates a readable and writable file holding `content` in memory.
+///
+/// The file can grow up to `capacity` bytes, or up to the length of `content` if it is larger.
+/// Writes are truncated at the capacity, and fail with `ZX_ERR_NO_SPACE` if not a single byte can
+/// be written.  Writes starting past the capacity, and truncates to a length above it, fail with
+/// `ZX_ERR_OUT_OF_RANGE`, matching the VMO backed files.
+///
+/// Connections to the file observe each other's changes immediately.  The file does not provide a
+/// VMO, so `GetBuffer` fails with `ZX_ERR_NOT_SUPPORTED`.
+pub fn mem_file(content: Vec<u8>, capacity: u64) -> Arc<MemFile> {
+    Arc::new(MemFile { content: Mutex::new(content), capacity })
+}
+
+/// A file with the content held in memory.  See [`mem_file`].
+pub struct MemFile {
+    content: Mutex<Vec<u8>>,
+    capacity: u64,
+}
+
+impl MemFile {
+    /// Returns a copy of the current content of the file.
+    pub fn content(&self) -> Vec<u8> {
+        self.content.lock().unwrap().clone()
+    }
+
+    fn effective_capacity(&self, content: &[u8]) -> u64 {
+        std::cmp::max(self.capacity, content.len() as u64)
+    }
+}
+
+impl DirectoryEntry for MemFile {
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        _mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        let rights = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE;
+        open_file(self, scope, flags, path, server_end, rights);
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::File)
+    }
+
+    fn into_file(self: Arc<Self>) -> Option<Arc<dyn File>> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl File for MemFile {
+    async fn open(&self, _flags: fio::OpenFlags) -> Result<(), Status> {
+        Ok(())
+    }
+
+    async fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<u64, Status> {
+        let content = self.content.lock().unwrap();
+        let start = match usize::try_from(offset) {
+            Ok(start) if start < content.len() => start,
+            _ => return Ok(0),
+        };
+        let len = std::cmp::min(buffer.len(), content.len() - start);
+        buffer[..len].copy_from_slice(&content[start..start + len]);
+        Ok(len as u64)
+    }
+
+    async fn write_at(&self, offset: u64, content: &[u8]) -> Result<u64, Status> {
+        let mut data = self.content.lock().unwrap();
+        let available = match self.effective_capacity(&data).checked_sub(offset) {
+            None => return Err(Status::OUT_OF_RANGE),
+            Some(0) if !content.is_empty() => return Err(Status::NO_SPACE),
+            Some(available) => available,
+        };
+        let len = std::cmp::min(content.len() as u64, available) as usize;
+        let start = offset as usize;
+        if start + len > data.len() {
+            data.resize(start + len, 0);
+        }
+        data[start..start + len].copy_from_slice(&content[..len]);
+        Ok(len as u64)
+    }
+
+    async fn append(&self, content: &[u8]) -> Result<(u64, u64), Status> {
+        let mut data = self.content.lock().unwrap();
+        let available = self.effective_capacity(&data) - data.len() as u64;
+        if available == 0 && !content.is_empty() {
+            return Err(Status::NO_SPACE);
+        }
+        let len = std::cmp::min(content.len() as u64, available) as usize;
+        data.extend_from_slice(&content[..len]);
+        Ok((len as u64, data.len() as u64))
+    }
+
+    async fn truncate(&self, length: u64) -> Result<(), Status> {
+        let mut data = self.content.lock().unwrap();
+        if length > self.effective_capacity(&data) {
+            return Err(Status::OUT_OF_RANGE);
+        }
+        data.resize(length as usize, 0);
+        Ok(())
+    }
+
+    async fn get_buffer(&self, _flags: fio::VmoFlags) -> Result<Buffer, Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
+    async fn get_size(&self) -> Result<u64, Status> {
+        Ok(self.content.lock().unwrap().len() as u64)
+    }
+
+    async fn get_attrs(&self) -> Result<fio::NodeAttributes, Status> {
+        let content = self.content.lock().unwrap();
+        Ok(fio::NodeAttributes {
+            mode: fio::MODE_TYPE_FILE
+                | rights_to_posix_mode_bits(/*r*/ true, /*w*/ true, /*x*/ false),
+            id: fio::INO_UNKNOWN,
+            content_size: content.len() as u64,
+            storage_size: self.effective_capacity(&content),
+            link_count: 1,
+            creation_time: 0,
+            modification_time: 0,
+        })
+    }
+
+    async fn set_attrs(
+        &self,
+        _flags: fio::NodeAttributeFlags,
+        _attrs: fio::NodeAttributes,
+    ) -> Result<(), Status> {
+        Err(Status::NOT_SUPPORTED)
+    }
+
+    async fn close(&self) -> Result<(), Status> {
+        Ok(())
+    }
+
+    async fn sync(&self) -> Result<(), Status> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mem_file, MemFile};
+
+    // Macros are exported into the root of the crate.
+    use crate::{
+        assert_close, assert_read, assert_read_at, assert_seek, assert_truncate,
+        assert_truncate_err, assert_write, assert_write_err,
+    };
+
+    use crate::{directory::entry::DirectoryEntry, execution_scope::ExecutionScope, path::Path};
+
+    use {
+        fidl::endpoints::create_proxy, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::Status, std::sync::Arc,
+    };
+
+    fn open(file: &Arc<MemFile>) -> fio::FileProxy {
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        file.clone().open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+        proxy
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn write_then_read() {
+        let file = mem_file(b"Hello".to_vec(), 100);
+        let proxy = open(&file);
+
+        assert_read!(proxy, "Hello");
+        assert_write!(proxy, ", world");
+        assert_seek!(proxy, 0, Start);
+        assert_read!(proxy, "Hello, world");
+        assert_close!(proxy);
+
+        let proxy = open(&file);
+        assert_read!(proxy, "Hello, world");
+        assert_close!(proxy);
+        assert_eq!(file.content(), b"Hello, world".to_vec());
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn truncate_grows_with_zeros() {
+        let file = mem_file(b"abc".to_vec(), 10);
+        let proxy = open(&file);
+
+        assert_truncate!(proxy, 6);
+        assert_read!(proxy, "abc\0\0\0");
+        assert_truncate_err!(proxy, 11, Status::OUT_OF_RANGE);
+        assert_truncate!(proxy, 1);
+        assert_close!(proxy);
+        assert_eq!(file.content(), b"a".to_vec());
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn read_past_end_is_short() {
+        let file = mem_file(b"Content".to_vec(), 0);
+        let proxy = open(&file);
+
+        let content = proxy.read_at(100, 4).await.unwrap().map_err(Status::from_raw).unwrap();
+        assert_eq!(content, b"ent".to_vec());
+        assert_read_at!(proxy, 7, "");
+        assert_read_at!(proxy, 1000, "");
+        assert_close!(proxy);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn writes_are_limited_by_capacity() {
+        let file = mem_file(Vec::new(), 4);
+        let proxy = open(&file);
+
+        let written = proxy.write(b"abcdef").await.unwrap().map_err(Status::from_raw).unwrap();
+        assert_eq!(written, 4);
+        assert_write_err!(proxy, "g", Status::NO_SPACE);
+        assert_close!(proxy);
+        assert_eq!(file.content(), b"abcd".to_vec());
+    }
+}
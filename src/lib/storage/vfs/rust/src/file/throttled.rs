@@ -0,0 +1,137 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A file wrapper that delays reads and writes, to test clients against slow devices.  See
+//! [`throttled`].
+
+use crate::file::{File, FileWrapper, WrapperHooks};
+
+use {
+    async_trait::async_trait,
+    fidl_fuchsia_io as fio, fuchsia_async as fasync,
+    fuchsia_zircon::{self as zx, Status},
+    std::sync::Arc,
+};
+
+/// Wraps `inner`, so that every `read_at`, `write_at` and `append` call waits for `delay` before
+/// being forwarded to `inner`.  All the other operations are forwarded immediately.
+///
+/// The delay is measured with an [`fasync::Timer`] on the executor running the connection, so
+/// tests using an executor with fake time control when the delayed operations complete.
+///
+/// Connections can be granted the `RIGHT_*` flags in `rights`, see [`FileWrapper::new`].
+pub fn throttled<T: File>(
+    inner: Arc<T>,
+    delay: zx::Duration,
+    rights: fio::OpenFlags,
+) -> Arc<Throttled<T>> {
+    FileWrapper::new(inner, Delay { delay }, rights)
+}
+
+/// A file that delays the I/O operations of another file.  See [`throttled`].
+pub type Throttled<T> = FileWrapper<T, Delay>;
+
+/// The delay of a [`Throttled`] file.
+pub struct Delay {
+    delay: zx::Duration,
+}
+
+impl Delay {
+    async fn wait(&self) {
+        fasync::Timer::new(fasync::Time::after(self.delay)).await;
+    }
+}
+
+#[async_trait]
+impl<T: File> WrapperHooks<T> for Delay {
+    async fn read_at(&self, inner: &T, offset: u64, buffer: &mut [u8]) -> Result<u64, Status> {
+        self.wait().await;
+        inner.read_at(offset, buffer).await
+    }
+
+    async fn write_at(&self, inner: &T, offset: u64, content: &[u8]) -> Result<u64, Status> {
+        self.wait().await;
+        inner.write_at(offset, content).await
+    }
+
+    async fn append(&self, inner: &T, content: &[u8]) -> Result<(u64, u64), Status> {
+        self.wait().await;
+        inner.append(content).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::throttled;
+
+    use crate::{
+        assert_event, directory::entry::DirectoryEntry, execution_scope::ExecutionScope,
+        file::mem_file, path::Path,
+    };
+
+    use {
+        fidl::endpoints::create_proxy, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon as zx, std::task::Poll,
+    };
+
+    #[test]
+    fn read_waits_for_delay() {
+        let mut exec = fasync::TestExecutor::new_with_fake_time().unwrap();
+        exec.set_fake_time(fasync::Time::from_nanos(0));
+
+        let file = throttled(
+            mem_file(b"Content".to_vec(), 100),
+            zx::Duration::from_seconds(1),
+            fio::OpenFlags::RIGHT_READABLE,
+        );
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        file.open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+
+        let mut read = proxy.read_at(4, 0);
+        assert!(exec.run_until_stalled(&mut read).is_pending());
+
+        exec.set_fake_time(fasync::Time::from_nanos(999_999_999));
+        assert!(!exec.wake_expired_timers());
+        assert!(exec.run_until_stalled(&mut read).is_pending());
+
+        exec.set_fake_time(fasync::Time::from_nanos(1_000_000_000));
+        assert!(exec.wake_expired_timers());
+        match exec.run_until_stalled(&mut read) {
+            Poll::Ready(result) => {
+                assert_eq!(result.unwrap().map_err(zx::Status::from_raw), Ok(b"Cont".to_vec()))
+            }
+            Poll::Pending => panic!("read did not complete after the delay"),
+        }
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn rights_limit_connections() {
+        let file = throttled(
+            mem_file(b"Content".to_vec(), 100),
+            zx::Duration::from_seconds(0),
+            fio::OpenFlags::RIGHT_READABLE,
+        );
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        file.open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE
+                | fio::OpenFlags::RIGHT_WRITABLE
+                | fio::OpenFlags::DESCRIBE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+
+        assert_event!(proxy, fio::FileEvent::OnOpen_ { s, info }, {
+            assert_eq!(zx::Status::from_raw(s), zx::Status::ACCESS_DENIED);
+            assert_eq!(info, None);
+        });
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! In-process accounting of the data transferred over file connections.  This complements the
+//! trace spans emitted by the file connection with numbers that can be queried without a trace
+//! capture.
+
+use {
+    fuchsia_zircon as zx,
+    std::sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Collects throughput numbers for all the connections to a file.  Files that want their
+/// connections to be accounted for should hold an instance of this type and return it from
+/// [`crate::file::File::throughput`].
+///
+/// Every open connection gets a separate accumulator.  When a connection is closed, its numbers
+/// are added to a running total and its accumulator is dropped, so the recorder only grows with
+/// the number of connections that are open at the same time.  [`Self::snapshot`] returns the
+/// numbers of all the connections, open and closed, combined.
+#[derive(Debug, Default)]
+pub struct ThroughputRecorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+#[derive(Debug, Default)]
+struct RecorderState {
+    /// Numbers of the connections that were closed.
+    closed: ThroughputSnapshot,
+    /// Accumulators of the connections that are still open.
+    open: Vec<Arc<Counters>>,
+}
+
+impl ThroughputRecorder {
+    /// Creates a new recorder with no connections.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the numbers accumulated by all the connections to the file.
+    pub fn snapshot(&self) -> ThroughputSnapshot {
+        let state = self.state.lock().unwrap();
+        state.open.iter().fold(state.closed, |total, counters| total.add(&counters.snapshot()))
+    }
+
+    /// Creates an accumulator for a new connection.  The numbers are moved into the running total
+    /// when the accumulator is dropped.
+    pub(in crate::file) fn new_connection(&self) -> ConnectionThroughput {
+        let counters = Arc::new(Counters::default());
+        self.state.lock().unwrap().open.push(counters.clone());
+        ConnectionThroughput { counters, recorder: self.state.clone() }
+    }
+}
+
+/// Numbers accumulated by the connections to a file.  See [`ThroughputRecorder::snapshot`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThroughputSnapshot {
+    /// Number of successful `Read` and `ReadAt` requests.
+    pub reads: u64,
+    /// Total number of bytes returned by the successful read requests.
+    pub bytes_read: u64,
+    /// Total time spent processing the successful read requests.
+    pub read_time: zx::Duration,
+    /// Number of successful `Write` and `WriteAt` requests.
+    pub writes: u64,
+    /// Total number of bytes accepted by the successful write requests.
+    pub bytes_written: u64,
+    /// Total time spent processing the successful write requests.
+    pub write_time: zx::Duration,
+}
+
+impl ThroughputSnapshot {
+    fn add(self, other: &Self) -> Self {
+        Self {
+            reads: self.reads + other.reads,
+            bytes_read: self.bytes_read + other.bytes_read,
+            read_time: self.read_time + other.read_time,
+            writes: self.writes + other.writes,
+            bytes_written: self.bytes_written + other.bytes_written,
+            write_time: self.write_time + other.write_time,
+        }
+    }
+}
+
+/// Accumulator updated by a single file connection.  Dropping it moves its numbers into the
+/// running total of the [`ThroughputRecorder`] that created it.
+#[derive(Debug)]
+pub(in crate::file) struct ConnectionThroughput {
+    counters: Arc<Counters>,
+    recorder: Arc<Mutex<RecorderState>>,
+}
+
+impl ConnectionThroughput {
+    pub(in crate::file) fn record_read(&self, bytes: u64, duration: zx::Duration) {
+        let counters = &self.counters;
+        counters.reads.fetch_add(1, Ordering::Relaxed);
+        counters.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        counters.read_nanos.fetch_add(duration.into_nanos(), Ordering::Relaxed);
+    }
+
+    pub(in crate::file) fn record_write(&self, bytes: u64, duration: zx::Duration) {
+        let counters = &self.counters;
+        counters.writes.fetch_add(1, Ordering::Relaxed);
+        counters.bytes_written.fetch_add(bytes, Ordering::Relaxed);
+        counters.write_nanos.fetch_add(duration.into_nanos(), Ordering::Relaxed);
+    }
+}
+
+impl Drop for ConnectionThroughput {
+    fn drop(&mut self) {
+        let mut state = self.recorder.lock().unwrap();
+        state.open.retain(|counters| !Arc::ptr_eq(counters, &self.counters));
+        state.closed = state.closed.add(&self.counters.snapshot());
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    reads: AtomicU64,
+    bytes_read: AtomicU64,
+    read_nanos: AtomicI64,
+    writes: AtomicU64,
+    bytes_written: AtomicU64,
+    write_nanos: AtomicI64,
+}
+
+impl Counters {
+    fn snapshot(&self) -> ThroughputSnapshot {
+        ThroughputSnapshot {
+            reads: self.reads.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            read_time: zx::Duration::from_nanos(self.read_nanos.load(Ordering::Relaxed)),
+            writes: self.writes.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            write_time: zx::Duration::from_nanos(self.write_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ThroughputRecorder, ThroughputSnapshot};
+
+    use fuchsia_zircon as zx;
+
+    #[test]
+    fn closed_connections_are_folded_into_the_total() {
+        let recorder = ThroughputRecorder::new();
+
+        let first = recorder.new_connection();
+        first.record_read(10, zx::Duration::from_nanos(1));
+        let second = recorder.new_connection();
+        second.record_write(20, zx::Duration::from_nanos(2));
+
+        drop(first);
+        assert_eq!(recorder.state.lock().unwrap().open.len(), 1);
+
+        let expected = ThroughputSnapshot {
+            reads: 1,
+            bytes_read: 10,
+            read_time: zx::Duration::from_nanos(1),
+            writes: 1,
+            bytes_written: 20,
+            write_time: zx::Duration::from_nanos(2),
+        };
+        assert_eq!(recorder.snapshot(), expected);
+
+        drop(second);
+        assert_eq!(recorder.state.lock().unwrap().open.len(), 0);
+        assert_eq!(recorder.snapshot(), expected);
+    }
+}
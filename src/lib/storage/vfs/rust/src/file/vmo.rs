@@ -97,7 +97,7 @@ impl DirectoryEntry for ReadOnlyVmoFile {
             return;
         }
 
-        VmoFileConnection::create_connection(scope.clone(), self, flags, server_end);
+        VmoFileConnection::create_connection(scope.clone(), self, flags, server_end, false);
     }
 
     fn entry_info(&self) -> EntryInfo {
@@ -30,10 +30,17 @@ use crate::{
 use {
     fidl::endpoints::ServerEnd,
     fidl_fuchsia_io as fio,
-    fuchsia_zircon::{Status, Vmo, VmoOptions},
+    fuchsia_zircon::{HandleBased as _, Rights, Status, Vmo, VmoOptions},
     futures::future::BoxFuture,
     futures::lock::{Mutex, MutexLockFuture},
-    std::{future::Future, sync::Arc},
+    once_cell::sync::OnceCell,
+    std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+    },
 };
 
 /// `init_vmo` callback returns an instance of this struct to describe the VMO it has generated, as
@@ -98,6 +105,57 @@ where
     VmoFile::new(init_vmo, true, false, true)
 }
 
+/// Creates a new read-exec-only `VmoFile` that serves the content of the specified `vmo`.  This is
+/// a shortcut for [`read_exec`] for the common case of serving a binary that is already in a VMO.
+/// `vmo` must have both ZX_RIGHT_READ and ZX_RIGHT_EXECUTE, and its content size is used as the
+/// file size.
+///
+/// Every time the file is initialized, it is backed by a duplicate of the `vmo` handle, so
+/// `GetBackingMemory` calls with `VmoFlags::EXECUTE` will return VMOs that can be mapped as
+/// executable.
+pub fn read_exec_from_vmo(
+    vmo: Vmo,
+) -> Arc<
+    VmoFile<
+        impl Fn() -> BoxFuture<'static, InitVmoResult> + Send + Sync + 'static,
+        BoxFuture<'static, InitVmoResult>,
+    >,
+> {
+    read_exec(move || -> BoxFuture<'static, InitVmoResult> {
+        let vmo = vmo.duplicate_handle(Rights::SAME_RIGHTS);
+        Box::pin(async move {
+            let vmo = vmo?;
+            let size = vmo.get_content_size()?;
+            Ok(NewVmo { vmo, size, capacity: size })
+        })
+    })
+}
+
+/// Creates a new read-only `VmoFile` that serves the content of the specified `vmo`, without
+/// copying it.  Useful when the content is already in a VMO, such as a memory-mapped asset, and
+/// all the connections should share it.  The content size of `vmo` is used as the file size, not
+/// the size of the VMO itself.
+///
+/// Every time the file is initialized, it is backed by a duplicate of the `vmo` handle.  As the
+/// file is read-only, connections never write into it, and only hand out read-only handles.
+pub fn vmo_read_only(
+    vmo: Vmo,
+) -> Arc<
+    VmoFile<
+        impl Fn() -> BoxFuture<'static, InitVmoResult> + Send + Sync + 'static,
+        BoxFuture<'static, InitVmoResult>,
+    >,
+> {
+    read_only(move || -> BoxFuture<'static, InitVmoResult> {
+        let vmo = vmo.duplicate_handle(Rights::SAME_RIGHTS);
+        Box::pin(async move {
+            let vmo = vmo?;
+            let size = vmo.get_content_size()?;
+            Ok(NewVmo { vmo, size, capacity: size })
+        })
+    })
+}
+
 fn init_vmo<'a>(content: Arc<[u8]>) -> impl Fn() -> BoxFuture<'a, InitVmoResult> + Send + Sync {
     move || {
         // In "production" code we would instead wrap `content` in a smart pointer to be able to
@@ -130,6 +188,74 @@ where
     read_only(init_vmo(content.clone()))
 }
 
+/// Content of read-only files that is embedded in the binary, such as with `include_bytes!`.  Use
+/// with [`read_only_static_vmo`].
+///
+/// The VMO holding the content is created when the first file backed by a `StaticVmo` is opened,
+/// and is shared by all the files and connections after that, so the content is copied only once
+/// for the lifetime of the process.  Meant to be stored in a `static`:
+///
+/// ```ignore
+/// static ASSET: StaticVmo = StaticVmo::new(include_bytes!("asset.bin"));
+///
+/// let file = read_only_static_vmo(&ASSET);
+/// ```
+pub struct StaticVmo {
+    bytes: &'static [u8],
+    vmo: OnceCell<Vmo>,
+    /// Number of VMOs created, to check that the VMO is shared.
+    #[cfg(test)]
+    created: std::sync::atomic::AtomicUsize,
+}
+
+impl StaticVmo {
+    /// Wraps `bytes`, without copying them.
+    pub const fn new(bytes: &'static [u8]) -> Self {
+        StaticVmo {
+            bytes,
+            vmo: OnceCell::new(),
+            #[cfg(test)]
+            created: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns a handle to the shared VMO, creating it if this is the first call.
+    fn vmo(&self) -> Result<Vmo, Status> {
+        let vmo = self.vmo.get_or_try_init(|| {
+            #[cfg(test)]
+            self.created.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+            let size = self.bytes.len() as u64;
+            let vmo = Vmo::create(size)?;
+            vmo.write(self.bytes, 0)?;
+            vmo.set_content_size(&size)?;
+            Ok(vmo)
+        })?;
+        vmo.duplicate_handle(Rights::SAME_RIGHTS)
+    }
+}
+
+/// Creates a new read-only `VmoFile` which serves the static content of `content`.  Unlike
+/// [`read_only_static`], the content is not copied when the file is created, and all the files
+/// created from the same `content` share a single VMO.  See [`StaticVmo`].
+pub fn read_only_static_vmo(
+    content: &'static StaticVmo,
+) -> Arc<
+    VmoFile<
+        impl Fn() -> BoxFuture<'static, InitVmoResult> + Send + Sync + 'static,
+        BoxFuture<'static, InitVmoResult>,
+    >,
+> {
+    read_only(move || -> BoxFuture<'static, InitVmoResult> {
+        let vmo = content.vmo();
+        Box::pin(async move {
+            let vmo = vmo?;
+            let size = content.bytes.len() as u64;
+            Ok(NewVmo { vmo, size, capacity: size })
+        })
+    })
+}
+
 /// Create a new read-only `VmoFile` which servers a constant content.  The difference with
 /// `read_only_static` is that this function takes a run time values that it will own, while
 /// `read_only_static` requires a reference to something with a static lifetime.
@@ -208,6 +334,50 @@ where
     VmoFile::new(init_vmo, true, true, false)
 }
 
+/// Creates a new read-write `VmoFile` whose content is kept in a single VMO shared by all the
+/// connections.  The VMO is created with the `initial` content when the file is first opened, and
+/// is kept for the lifetime of the file, so the content survives the last connection being closed.
+/// Writes made through one connection are immediately visible to all the other connections, as
+/// well as in the VMOs returned by `GetBackingMemory` without `VmoFlags::PRIVATE_CLONE`.
+///
+/// The file can grow up to `capacity` bytes.  Writes that start past the capacity fail with
+/// `ZX_ERR_OUT_OF_RANGE`, while writes that cross it are truncated.
+pub fn vmo_read_write(
+    initial: Vec<u8>,
+    capacity: u64,
+) -> Arc<
+    VmoFile<
+        impl Fn() -> BoxFuture<'static, InitVmoResult> + Send + Sync + 'static,
+        BoxFuture<'static, InitVmoResult>,
+    >,
+> {
+    let shared = std::sync::Mutex::new(None);
+    read_write(move || -> BoxFuture<'static, InitVmoResult> {
+        let vmo = shared_vmo(&shared, &initial);
+        Box::pin(async move {
+            let vmo = vmo?;
+            let size = vmo.get_content_size()?;
+            Ok(NewVmo { vmo, size, capacity })
+        })
+    })
+}
+
+/// Returns a handle to the VMO stored in `shared`, creating it with the `initial` content if this
+/// is the first call.
+fn shared_vmo(shared: &std::sync::Mutex<Option<Vmo>>, initial: &[u8]) -> Result<Vmo, Status> {
+    let mut shared = shared.lock().unwrap();
+    if shared.is_none() {
+        let size = initial.len() as u64;
+        let vmo = Vmo::create_with_opts(VmoOptions::RESIZABLE, size)?;
+        if size > 0 {
+            vmo.write(initial, 0)?;
+        }
+        vmo.set_content_size(&size)?;
+        *shared = Some(vmo);
+    }
+    shared.as_ref().unwrap().duplicate_handle(Rights::SAME_RIGHTS)
+}
+
 /// Implementation of an asynchronous VMO-backed file in a virtual file system. This is created by
 /// passing async `init_vmo` callback to the exported constructor functions.
 ///
@@ -231,8 +401,8 @@ where
     executable: bool,
 
     /// Specifies the inode for this file. If you don't care or don't know, INO_UNKNOWN can be
-    /// used.
-    inode: u64,
+    /// used.  Replaced by the number assigned by the parent, if the parent assigns inodes.
+    inode: AtomicU64,
 
     // File connections share state with the file itself.
     // TODO: It should be `pub(in super::connection)` but the compiler claims, `super` does not
@@ -305,10 +475,29 @@ where
             readable,
             writable,
             executable,
-            inode,
+            inode: AtomicU64::new(inode),
             state: Mutex::new(VmoFileState::Uninitialized),
         })
     }
+
+    /// Opens a read-only connection to this file that captures a copy-on-write snapshot of the
+    /// file content when it is opened.  All the reads, as well as the VMOs returned by `Describe`
+    /// and `GetBackingMemory`, are served from that snapshot, so the connection is isolated from
+    /// the writes made through other connections, and observes the size the file had when it was
+    /// opened.  Connections cloned from this one share the same snapshot.
+    ///
+    /// Fails with `ZX_ERR_INVALID_ARGS` if `flags` include `OpenFlags::RIGHT_WRITABLE`, and with
+    /// `ZX_ERR_NOT_SUPPORTED` if the VMO returned by `init_vmo` does not support snapshots, as is
+    /// the case for pager-backed VMOs.  `NODE_REFERENCE` connections do not read the content, and
+    /// are opened without a snapshot.
+    pub fn open_snapshot(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        VmoFileConnection::create_connection(scope.clone(), self, flags, server_end, true);
+    }
 }
 
 impl<InitVmo, InitVmoFuture> VmoFileInterface for VmoFile<InitVmo, InitVmoFuture>
@@ -337,7 +526,7 @@ where
     }
 
     fn get_inode(&self) -> u64 {
-        return self.inode;
+        return self.inode.load(Ordering::Relaxed);
     }
 }
 
@@ -359,10 +548,14 @@ where
             return;
         }
 
-        VmoFileConnection::create_connection(scope.clone(), self, flags, server_end);
+        VmoFileConnection::create_connection(scope.clone(), self, flags, server_end, false);
     }
 
     fn entry_info(&self) -> EntryInfo {
-        EntryInfo::new(self.inode, fio::DirentType::File)
+        EntryInfo::new(self.get_inode(), fio::DirentType::File)
+    }
+
+    fn set_assigned_inode(&self, inode: u64) {
+        self.inode.store(inode, Ordering::Relaxed);
     }
 }
@@ -4,7 +4,10 @@
 
 //! Tests for the asynchronous files.
 
-use super::{read_only, read_only_const, read_only_static, read_write, NewVmo};
+use super::{
+    read_exec_from_vmo, read_only, read_only_const, read_only_static, read_only_static_vmo,
+    read_write, vmo_read_only, vmo_read_write, NewVmo, StaticVmo,
+};
 
 // Macros are exported into the root of the crate.
 use crate::{
@@ -33,8 +36,11 @@ use {
     fidl::endpoints::create_proxy,
     fidl_fuchsia_io as fio,
     fuchsia_async::TestExecutor,
-    fuchsia_zircon::{sys::ZX_OK, Status, Vmo},
-    futures::{channel::oneshot, future::join},
+    fuchsia_zircon::{self as zx, sys::ZX_OK, Rights, Status, Vmo, VmoOptions},
+    futures::{
+        channel::oneshot,
+        future::{join, join_all},
+    },
     libc::{S_IRUSR, S_IWUSR},
     std::sync::{
         atomic::{AtomicUsize, Ordering},
@@ -475,6 +481,103 @@ fn write_after_seek_beyond_capacity_fills_gap_with_zeroes() {
     );
 }
 
+#[test]
+fn write_beyond_capacity() {
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        read_write(simple_init_vmo_resizable_with_capacity(b"Content", 10)),
+        |proxy| async move {
+            assert_seek!(proxy, 0, End, Ok(7));
+
+            // Only the bytes that fit within the capacity are accepted.
+            let written =
+                proxy.write(b"12345").await.expect("write failed").map_err(Status::from_raw);
+            assert_eq!(written, Ok(3));
+            assert_seek!(proxy, 0, Current, Ok(10));
+
+            // Nothing else fits.
+            assert_write_err!(proxy, "4", Status::NO_SPACE);
+            assert_write_at_err!(proxy, 10, "4", Status::NO_SPACE);
+            assert_write_at_err!(proxy, 11, "4", Status::OUT_OF_RANGE);
+
+            let written =
+                proxy.write_at(b"6789", 8).await.expect("write failed").map_err(Status::from_raw);
+            assert_eq!(written, Ok(2));
+
+            assert_truncate_err!(proxy, 11, Status::OUT_OF_RANGE);
+
+            // Validate contents.
+            assert_seek!(proxy, 0, Start);
+            assert_read!(proxy, "Content167");
+            assert_close!(proxy);
+        },
+    );
+}
+
+#[fuchsia_async::run(4, test)]
+async fn concurrent_growth_no_torn_reads() {
+    const CHUNK_SIZE: usize = 100;
+    const CHUNK_COUNT: usize = 50;
+    const READERS: usize = 4;
+
+    // Start with an empty VMO, so that every write needs to grow it.
+    let server = read_write(|| async move {
+        let vmo = Vmo::create_with_opts(VmoOptions::RESIZABLE, 0)?;
+        Ok(NewVmo { vmo, size: 0, capacity: (CHUNK_SIZE * CHUNK_COUNT) as u64 })
+    });
+    let scope = ExecutionScope::new();
+    let open = || {
+        let (proxy, server_end) =
+            create_proxy::<fio::FileMarker>().expect("Failed to create connection endpoints");
+        server.clone().open(
+            scope.clone(),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+        proxy
+    };
+    let chunk_byte = |chunk: usize| (chunk % 255 + 1) as u8;
+
+    let writer = open();
+    let write = async move {
+        for chunk in 0..CHUNK_COUNT {
+            let written = writer
+                .write(&[chunk_byte(chunk); CHUNK_SIZE])
+                .await
+                .expect("write failed")
+                .map_err(Status::from_raw);
+            assert_eq!(written, Ok(CHUNK_SIZE as u64));
+        }
+    };
+
+    let reads = join_all((0..READERS).map(|_| {
+        let reader = open();
+        async move {
+            loop {
+                let content = reader
+                    .read_at(fio::MAX_BUF, 0)
+                    .await
+                    .expect("read_at failed")
+                    .map_err(Status::from_raw)
+                    .expect("read_at error");
+                // Every write is applied as a whole, and the size must never run ahead of the
+                // content.
+                assert_eq!(content.len() % CHUNK_SIZE, 0, "Torn size: {}", content.len());
+                for (i, byte) in content.iter().enumerate() {
+                    assert_eq!(*byte, chunk_byte(i / CHUNK_SIZE), "Torn content at {}", i);
+                }
+                if content.len() == CHUNK_SIZE * CHUNK_COUNT {
+                    break;
+                }
+            }
+        }
+    }));
+
+    let ((), _) = join(write, reads).await;
+}
+
 #[test]
 fn seek_valid_positions() {
     run_server_client(
@@ -738,6 +841,135 @@ fn clone_inherit_access() {
     );
 }
 
+#[test]
+fn open_snapshot_isolated_from_writes() {
+    let exec = TestExecutor::new().expect("Executor creation failed");
+    let scope = ExecutionScope::new();
+    let file = read_write(simple_init_vmo_resizable(b"Initial content"));
+
+    run_client(exec, || async move {
+        let (writer, server_end) =
+            create_proxy::<fio::FileMarker>().expect("Failed to create connection endpoints");
+        file.clone().open(
+            scope.clone(),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+
+        let (reader, server_end) =
+            create_proxy::<fio::FileMarker>().expect("Failed to create connection endpoints");
+        file.clone().open_snapshot(
+            scope.clone(),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE,
+            server_end.into_channel().into(),
+        );
+        assert_event!(reader, fio::FileEvent::OnOpen_ { s, info: _ }, {
+            assert_eq!(s, ZX_OK);
+        });
+
+        assert_write!(writer, "Updated");
+        assert_write!(writer, " and extended content");
+
+        assert_read!(reader, "Initial content");
+        assert_seek!(reader, 0, End, Ok(15));
+        assert_read_at!(reader, 8, "content");
+        let (status, attrs) = reader.get_attr().await.expect("get_attr failed");
+        assert_eq!(Status::from_raw(status), Status::OK);
+        assert_eq!(attrs.content_size, 15);
+
+        // Clones share the snapshot of the connection they are cloned from.
+        let reader_clone = clone_get_vmo_file_proxy_assert_ok!(
+            &reader,
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE
+        );
+        assert_read!(reader_clone, "Initial content");
+
+        // Regular connections observe the shared content.
+        let (late_reader, server_end) =
+            create_proxy::<fio::FileMarker>().expect("Failed to create connection endpoints");
+        file.clone().open(
+            scope.clone(),
+            fio::OpenFlags::RIGHT_READABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+        assert_read!(late_reader, "Updated and extended content");
+
+        assert_close!(late_reader);
+        assert_close!(reader_clone);
+        assert_close!(reader);
+        assert_close!(writer);
+    });
+}
+
+#[test]
+fn open_snapshot_rejects_writable_connections() {
+    run_client(TestExecutor::new().expect("Executor creation failed"), || async move {
+        let file = read_write(simple_init_vmo_resizable(b"Content"));
+        let (proxy, server_end) =
+            create_proxy::<fio::FileMarker>().expect("Failed to create connection endpoints");
+        file.open_snapshot(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE
+                | fio::OpenFlags::RIGHT_WRITABLE
+                | fio::OpenFlags::DESCRIBE,
+            server_end.into_channel().into(),
+        );
+
+        assert_event!(proxy, fio::FileEvent::OnOpen_ { s, info }, {
+            assert_eq!(Status::from_raw(s), Status::INVALID_ARGS);
+            assert_eq!(info, None);
+        });
+    });
+}
+
+#[test]
+fn vmo_read_write_connections_share_content() {
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        vmo_read_write(b"Initial".to_vec(), 100),
+        |first| async move {
+            let second = clone_get_vmo_file_proxy_assert_ok!(
+                &first,
+                fio::OpenFlags::RIGHT_READABLE
+                    | fio::OpenFlags::RIGHT_WRITABLE
+                    | fio::OpenFlags::DESCRIBE
+            );
+
+            assert_write!(first, "Hello");
+            assert_read!(second, "Hello");
+
+            assert_write_at!(second, 5, " world");
+            assert_read_at!(first, 0, "Hello world");
+
+            // The backing memory is the shared VMO, not a copy.
+            let buffer = assert_get_buffer!(first, fio::VmoFlags::READ);
+            assert_vmo_content!(&buffer.vmo, b"Hello world");
+            assert_write_at!(second, 0, "Jello");
+            assert_vmo_content!(&buffer.vmo, b"Jello world");
+
+            assert_close!(second);
+            assert_close!(first);
+        },
+    );
+}
+
+#[test]
+fn vmo_read_write_write_past_capacity() {
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        vmo_read_write(b"Content".to_vec(), 10),
+        |proxy| async move {
+            assert_write_at_err!(proxy, 11, "Overflow", Status::OUT_OF_RANGE);
+            assert_read!(proxy, "Content");
+            assert_close!(proxy);
+        },
+    );
+}
+
 #[test]
 fn get_attr_read_only() {
     run_server_client(
@@ -750,7 +982,7 @@ fn get_attr_read_only() {
                     mode: fio::MODE_TYPE_FILE | S_IRUSR,
                     id: fio::INO_UNKNOWN,
                     content_size: 7,
-                    storage_size: 100,
+                    storage_size: zx::system_get_page_size() as u64,
                     link_count: 1,
                     creation_time: 0,
                     modification_time: 0,
@@ -761,6 +993,94 @@ fn get_attr_read_only() {
     );
 }
 
+#[test]
+fn get_attr_storage_size_is_vmo_size() {
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE,
+        read_only_static(b"0123456789"),
+        |proxy| async move {
+            let (status, attrs) = proxy.get_attr().await.expect("get_attr failed");
+            assert_eq!(Status::from_raw(status), Status::OK);
+            assert_eq!(attrs.content_size, 10);
+            assert_eq!(attrs.storage_size, zx::system_get_page_size() as u64);
+            assert_close!(proxy);
+        },
+    );
+}
+
+#[test]
+fn get_attributes_not_supported() {
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE,
+        read_only_static(b"Content"),
+        |proxy| async move {
+            let result = proxy
+                .get_attributes(fio::NodeAttributesQuery::CONTENT_SIZE)
+                .await
+                .expect("get_attributes failed")
+                .map_err(Status::from_raw);
+            assert_eq!(result.err(), Some(Status::NOT_SUPPORTED));
+
+            // The connection is still usable.
+            assert_read!(proxy, "Content");
+            assert_close!(proxy);
+        },
+    );
+}
+
+#[test]
+fn read_only_static_vmo_creates_vmo_once() {
+    static CONTENT: StaticVmo = StaticVmo::new(b"Embedded asset");
+
+    let exec = TestExecutor::new().expect("Executor creation failed");
+    let scope = ExecutionScope::new();
+
+    run_client(exec, || async move {
+        // Separate files created from the same content share the VMO.
+        for server in [read_only_static_vmo(&CONTENT), read_only_static_vmo(&CONTENT)] {
+            let (proxy, server_end) =
+                create_proxy::<fio::FileMarker>().expect("Failed to create connection endpoints");
+            server.open(
+                scope.clone(),
+                fio::OpenFlags::RIGHT_READABLE,
+                0,
+                Path::dot(),
+                server_end.into_channel().into(),
+            );
+
+            assert_read!(proxy, "Embedded asset");
+            assert_close!(proxy);
+        }
+
+        assert_eq!(CONTENT.created.load(Ordering::Relaxed), 1);
+    });
+}
+
+#[test]
+fn vmo_read_only_uses_content_size() {
+    let vmo = Vmo::create(4096).expect("create failed");
+    let content = b"Shared content";
+    vmo.write(content, 0).expect("write failed");
+    vmo.set_content_size(&(content.len() as u64)).expect("set_content_size failed");
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, vmo_read_only(vmo), |proxy| async move {
+        let (status, attrs) = proxy.get_attr().await.expect("get_attr failed");
+        assert_eq!(Status::from_raw(status), Status::OK);
+        assert_eq!(attrs.content_size, content.len() as u64);
+
+        assert_read!(proxy, "Shared content");
+        // The rest of the VMO is not part of the file.
+        let rest = proxy
+            .read(100)
+            .await
+            .expect("read failed")
+            .map_err(Status::from_raw)
+            .expect("read error");
+        assert!(rest.is_empty());
+        assert_close!(proxy);
+    });
+}
+
 #[test]
 fn get_attr_read_only_with_inode() {
     run_server_client(
@@ -773,7 +1093,7 @@ fn get_attr_read_only_with_inode() {
                     mode: fio::MODE_TYPE_FILE | S_IRUSR,
                     id: 12345, // Custom inode was specified for this file.
                     content_size: 7,
-                    storage_size: 100,
+                    storage_size: zx::system_get_page_size() as u64,
                     link_count: 1,
                     creation_time: 0,
                     modification_time: 0,
@@ -796,7 +1116,7 @@ fn get_attr_read_write() {
                     mode: fio::MODE_TYPE_FILE | S_IWUSR | S_IRUSR,
                     id: fio::INO_UNKNOWN,
                     content_size: 7,
-                    storage_size: 100,
+                    storage_size: zx::system_get_page_size() as u64,
                     link_count: 1,
                     creation_time: 0,
                     modification_time: 0,
@@ -1025,6 +1345,55 @@ fn get_buffer_read_only() {
     );
 }
 
+/// Returns a VMO that has ZX_RIGHT_EXECUTE.  The dynamic linker is present in every package, and
+/// the package directory allows executable access.
+fn executable_vmo() -> Vmo {
+    let file = fdio::open_fd(
+        "/pkg/lib/ld.so.1",
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_EXECUTABLE,
+    )
+    .expect("open_fd failed");
+    fdio::get_vmo_exec_from_file(&file).expect("get_vmo_exec_from_file failed")
+}
+
+#[test]
+fn get_buffer_read_exec() {
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_EXECUTABLE,
+        read_exec_from_vmo(executable_vmo()),
+        |proxy| async move {
+            let buffer = assert_get_buffer!(proxy, fio::VmoFlags::READ | fio::VmoFlags::EXECUTE);
+            let rights = buffer.vmo.basic_info().expect("basic_info failed").rights;
+            assert!(rights.contains(Rights::READ | Rights::EXECUTE));
+
+            let buffer = assert_get_buffer!(
+                proxy,
+                fio::VmoFlags::READ | fio::VmoFlags::EXECUTE | fio::VmoFlags::PRIVATE_CLONE
+            );
+            let rights = buffer.vmo.basic_info().expect("basic_info failed").rights;
+            assert!(rights.contains(Rights::READ | Rights::EXECUTE));
+
+            assert_close!(proxy);
+        },
+    );
+}
+
+#[test]
+fn get_buffer_exec_from_read_only() {
+    run_server_client(
+        fio::OpenFlags::RIGHT_READABLE,
+        simple_read_only(b"Read only test"),
+        |proxy| async move {
+            assert_get_buffer_err!(
+                proxy,
+                fio::VmoFlags::READ | fio::VmoFlags::EXECUTE,
+                Status::ACCESS_DENIED
+            );
+            assert_close!(proxy);
+        },
+    );
+}
+
 #[test]
 fn get_buffer_private_is_resizable() {
     run_server_client(
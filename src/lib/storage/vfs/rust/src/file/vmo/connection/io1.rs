@@ -29,6 +29,7 @@ use {
         AsHandleRef, HandleBased,
     },
     futures::{lock::MutexGuard, stream::StreamExt},
+    log::warn,
     static_assertions::assert_eq_size,
     std::{convert::TryInto, sync::Arc},
 };
@@ -76,6 +77,29 @@ pub struct VmoFileConnection {
     // Should we need to port to a 128 bit platform, there are static assertions in the code that
     // would fail.
     seek: u64,
+
+    /// Content of the file captured when this connection, or the connection it was cloned from,
+    /// was opened with a snapshot.  See [`VmoFile::open_snapshot()`].
+    ///
+    /// [`VmoFile::open_snapshot()`]: crate::file::vmo::asynchronous::VmoFile::open_snapshot
+    snapshot: Option<Arc<Snapshot>>,
+}
+
+/// A copy-on-write snapshot of the file content, isolated from the writes made through other
+/// connections.
+struct Snapshot {
+    vmo: zx::Vmo,
+    size: u64,
+}
+
+/// Where a new connection reads the file content from.
+enum Content {
+    /// The content shared by all the connections to the file.
+    Shared,
+    /// A snapshot of the shared content, taken when the connection is opened.
+    SnapshotOnOpen,
+    /// A snapshot inherited from the connection this one is cloned from.
+    Snapshot(Arc<Snapshot>),
 }
 
 /// Return type for [`handle_request()`] functions.
@@ -97,13 +121,28 @@ impl VmoFileConnection {
     ///
     /// Per connection buffer is initialized using the `init_vmo` closure, as part of the
     /// connection initialization.
+    ///
+    /// If `snapshot_on_open` is true, the connection reads from a snapshot of the file content
+    /// taken as it is opened.  Such connections can not be writable.
     pub(in crate::file::vmo) fn create_connection(
         scope: ExecutionScope,
         file: Arc<dyn VmoFileInterface>,
         flags: fio::OpenFlags,
         server_end: ServerEnd<fio::NodeMarker>,
+        snapshot_on_open: bool,
     ) {
-        let task = Self::create_connection_task(scope.clone(), file, flags, server_end);
+        let content = if snapshot_on_open { Content::SnapshotOnOpen } else { Content::Shared };
+        Self::spawn_connection(scope, file, flags, server_end, content);
+    }
+
+    fn spawn_connection(
+        scope: ExecutionScope,
+        file: Arc<dyn VmoFileInterface>,
+        flags: fio::OpenFlags,
+        server_end: ServerEnd<fio::NodeMarker>,
+        content: Content,
+    ) {
+        let task = Self::create_connection_task(scope.clone(), file, flags, server_end, content);
         // If we failed to send the task to the executor, it is probably shut down or is in the
         // process of shutting down (this is the only error state currently).  So there is nothing
         // for us to do, but to ignore the open.  `server_end` will be closed when the object will
@@ -116,6 +155,7 @@ impl VmoFileConnection {
         file: Arc<dyn VmoFileInterface>,
         flags: fio::OpenFlags,
         server_end: ServerEnd<fio::NodeMarker>,
+        content: Content,
     ) {
         let flags = match new_connection_validate_flags(
             flags,
@@ -131,7 +171,21 @@ impl VmoFileConnection {
             }
         };
 
-        let server_end = {
+        if flags.intersects(fio::OpenFlags::RIGHT_WRITABLE) && !matches!(content, Content::Shared) {
+            send_on_open_with_error(flags, server_end, zx::Status::INVALID_ARGS);
+            return;
+        }
+
+        // Held until the connection is closed.
+        let _slot = match scope.acquire_connection() {
+            Ok(slot) => slot,
+            Err(status) => {
+                send_on_open_with_error(flags, server_end, status);
+                return;
+            }
+        };
+
+        let (server_end, snapshot) = {
             let (mut state, server_end) =
                 match Self::ensure_vmo(file.clone(), file.state().await, server_end).await {
                     Ok(res) => res,
@@ -150,18 +204,42 @@ impl VmoFileConnection {
                 debug_assert!(seek == 0);
             }
 
-            match &mut *state {
+            let snapshot = match &mut *state {
                 VmoFileState::Uninitialized => {
                     debug_assert!(false, "`ensure_vmo` did not initialize the state.");
                     send_on_open_with_error(flags, server_end, zx::Status::INTERNAL);
                     return;
                 }
-                VmoFileState::Initialized { connection_count, .. } => {
+                VmoFileState::Initialized { vmo, size, connection_count, .. } => {
+                    let snapshot = match content {
+                        Content::Shared => None,
+                        // Node references do not read the content.
+                        Content::SnapshotOnOpen
+                            if flags.intersects(fio::OpenFlags::NODE_REFERENCE) =>
+                        {
+                            None
+                        }
+                        Content::SnapshotOnOpen => {
+                            // NO_WRITE preserves ZX_RIGHT_EXECUTE for executable files.
+                            let options =
+                                zx::VmoChildOptions::SNAPSHOT | zx::VmoChildOptions::NO_WRITE;
+                            match vmo.create_child(options, 0, *size) {
+                                Ok(vmo) => Some(Arc::new(Snapshot { vmo, size: *size })),
+                                Err(status) => {
+                                    send_on_open_with_error(flags, server_end, status);
+                                    return;
+                                }
+                            }
+                        }
+                        Content::Snapshot(snapshot) => Some(snapshot),
+                    };
+
                     *connection_count += 1;
+                    snapshot
                 }
-            }
+            };
 
-            server_end
+            (server_end, snapshot)
         };
 
         let (requests, control_handle) = match server_end.into_stream_and_control_handle() {
@@ -174,7 +252,7 @@ impl VmoFileConnection {
         };
 
         let mut connection =
-            VmoFileConnection { scope: scope.clone(), file, requests, flags, seek: 0 };
+            VmoFileConnection { scope: scope.clone(), file, requests, flags, seek: 0, snapshot };
 
         if flags.intersects(fio::OpenFlags::DESCRIBE) {
             match connection.get_node_info().await {
@@ -344,29 +422,33 @@ impl VmoFileConnection {
             || self.flags.intersects(fio::OpenFlags::RIGHT_WRITABLE)
         {
             Ok(fio::NodeInfo::File(fio::FileObject { event: None, stream: None }))
+        } else if let Some(snapshot) = &self.snapshot {
+            Ok(fio::NodeInfo::Vmofile(self.vmofile_for(&snapshot.vmo, snapshot.size)))
         } else {
             let vmofile = update_initialized_state! {
                 match &*self.file.state().await;
                 error: "get_node_info" => Err(zx::Status::INTERNAL);
-                { vmo, size, .. } => {
-                    // Since the VMO rights may exceed those of the connection, we need to ensure
-                    // the duplicated handle's rights are not greater than those of the connection.
-                    let mut new_rights = vmo.basic_info().unwrap().rights;
-                    // We already checked above that the connection is not writable. We also remove
-                    // SET_PROPERTY as this would also allow size changes.
-                    new_rights.remove(zx::Rights::WRITE | zx::Rights::SET_PROPERTY);
-                    if !self.flags.intersects(fio::OpenFlags::RIGHT_EXECUTABLE) {
-                        new_rights.remove(zx::Rights::EXECUTE);
-                    }
-                    let vmo = vmo.duplicate_handle(new_rights).unwrap();
-
-                    Ok(fio::Vmofile {vmo, offset: 0, length: *size})
-                }
+                { vmo, size, .. } => Ok(self.vmofile_for(vmo, *size))
             }?;
             Ok(fio::NodeInfo::Vmofile(vmofile))
         }
     }
 
+    fn vmofile_for(&self, vmo: &zx::Vmo, size: u64) -> fio::Vmofile {
+        // Since the VMO rights may exceed those of the connection, we need to ensure the
+        // duplicated handle's rights are not greater than those of the connection.
+        let mut new_rights = vmo.basic_info().unwrap().rights;
+        // We already checked that the connection is not writable. We also remove SET_PROPERTY as
+        // this would also allow size changes.
+        new_rights.remove(zx::Rights::WRITE | zx::Rights::SET_PROPERTY);
+        if !self.flags.intersects(fio::OpenFlags::RIGHT_EXECUTABLE) {
+            new_rights.remove(zx::Rights::EXECUTE);
+        }
+        let vmo = vmo.duplicate_handle(new_rights).unwrap();
+
+        fio::Vmofile { vmo, offset: 0, length: size }
+    }
+
     /// Handle a [`FileRequest`]. This function is responsible for handing all the file operations
     /// that operate on the connection-specific buffer.
     async fn handle_request(&mut self, req: fio::FileRequest) -> Result<ConnectionState, Error> {
@@ -374,9 +456,8 @@ impl VmoFileConnection {
             fio::FileRequest::Clone { flags, object, control_handle: _ } => {
                 self.handle_clone(self.flags, flags, object);
             }
-            fio::FileRequest::Reopen { options, object_request, control_handle: _ } => {
-                let _ = object_request;
-                todo!("https://fxbug.dev/77623: options={:?}", options);
+            fio::FileRequest::Reopen { options: _, object_request: _, control_handle: _ } => {
+                return Ok(self.close_unsupported("VmoFile::Reopen"));
             }
             fio::FileRequest::Close { responder } => {
                 // We are going to close the connection anyways, so there is no way to handle this
@@ -395,9 +476,8 @@ impl VmoFileConnection {
                     responder.control_handle().shutdown_with_epitaph(status);
                 }
             },
-            fio::FileRequest::Describe2 { query, responder } => {
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: query={:?}", query);
+            fio::FileRequest::Describe2 { query: _, responder: _ } => {
+                return Ok(self.close_unsupported("VmoFile::Describe2"));
             }
             fio::FileRequest::Sync { responder } => {
                 // VMOs are always in sync.
@@ -413,13 +493,11 @@ impl VmoFileConnection {
                 // it is not supported at the moment.
                 responder.send(ZX_ERR_NOT_SUPPORTED)?;
             }
-            fio::FileRequest::GetAttributes { query, responder } => {
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: query={:?}", query);
+            fio::FileRequest::GetAttributes { query: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
             }
-            fio::FileRequest::UpdateAttributes { attributes, responder } => {
-                let _ = responder;
-                todo!("https://fxbug.dev/77623: attributes={:?}", attributes);
+            fio::FileRequest::UpdateAttributes { attributes: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
             }
             fio::FileRequest::Read { count, responder } => {
                 let result = self.handle_read(count).await;
@@ -475,6 +553,15 @@ impl VmoFileConnection {
         Ok(ConnectionState::Alive)
     }
 
+    /// Closes the connection with a `ZX_ERR_NOT_SUPPORTED` epitaph, for requests this connection
+    /// does not implement and that can not report an error in their response.  `method` identifies
+    /// the request in the log.
+    fn close_unsupported(&self, method: &str) -> ConnectionState {
+        warn!("{} is not supported, closing the VMO file connection", method);
+        self.requests.control_handle().shutdown_with_epitaph(zx::Status::NOT_SUPPORTED);
+        ConnectionState::Dropped
+    }
+
     fn handle_clone(
         &mut self,
         parent_flags: fio::OpenFlags,
@@ -489,7 +576,15 @@ impl VmoFileConnection {
             }
         };
 
-        Self::create_connection(self.scope.clone(), self.file.clone(), flags, server_end);
+        if let Some(observer) = self.scope.clone_observer() {
+            observer(flags);
+        }
+
+        let content = match &self.snapshot {
+            Some(snapshot) => Content::Snapshot(snapshot.clone()),
+            None => Content::Shared,
+        };
+        Self::spawn_connection(self.scope.clone(), self.file.clone(), flags, server_end, content);
     }
 
     async fn handle_close(&mut self) -> Result<(), zx::Status> {
@@ -508,14 +603,23 @@ impl VmoFileConnection {
     }
 
     async fn handle_get_attr(&mut self) -> (zx::Status, fio::NodeAttributes) {
-        let result = update_initialized_state! {
-            match *self.file.state().await;
-            error: "handle_get_attr" => Err(zx::Status::INTERNAL);
-            { size, capacity, .. } => Ok((size, capacity))
+        let result = match &self.snapshot {
+            Some(snapshot) => snapshot.vmo.get_size().map(|vmo_size| (snapshot.size, vmo_size)),
+            None => update_initialized_state! {
+                match *self.file.state().await;
+                error: "handle_get_attr" => Err(zx::Status::INTERNAL);
+                { size, vmo_size, .. } => Ok((size, vmo_size))
+            },
         };
 
-        let (status, size, capacity) = match result {
-            Ok((size, capacity)) => (zx::Status::OK, size, capacity),
+        // `size` is the logical length of the file, kept in sync with the content size property of
+        // the VMO, while the storage is the memory actually allocated for the VMO.
+        let (status, size, storage_size) = match result {
+            Ok((size, vmo_size)) => {
+                let page_size = zx::system_get_page_size() as u64;
+                let storage_size = (vmo_size + page_size - 1) / page_size * page_size;
+                (zx::Status::OK, size, storage_size)
+            }
             Err(status) => (status, 0, 0),
         };
 
@@ -530,7 +634,7 @@ impl VmoFileConnection {
                     ),
                 id: self.file.get_inode(),
                 content_size: size,
-                storage_size: capacity,
+                storage_size,
                 link_count: 1,
                 creation_time: 0,
                 modification_time: 0,
@@ -550,23 +654,29 @@ impl VmoFileConnection {
             return Err(zx::Status::BAD_HANDLE);
         }
 
+        if let Some(snapshot) = &self.snapshot {
+            return Self::read_vmo(&snapshot.vmo, snapshot.size, offset, count);
+        }
+
         update_initialized_state! {
             match &*self.file.state().await;
             error: "handle_read_at" => return Err(zx::Status::INTERNAL);
-            { vmo, size, .. } => {
-                match size.checked_sub(offset) {
-                    None => Ok(Vec::new()),
-                    Some(rem) => {
-                        let count = core::cmp::min(count, rem);
+            { vmo, size, .. } => Self::read_vmo(vmo, *size, offset, count)
+        }
+    }
 
-                        assert_eq_size!(usize, u64);
-                        let count = count.try_into().unwrap();
+    fn read_vmo(vmo: &zx::Vmo, size: u64, offset: u64, count: u64) -> Result<Vec<u8>, zx::Status> {
+        match size.checked_sub(offset) {
+            None => Ok(Vec::new()),
+            Some(rem) => {
+                let count = core::cmp::min(count, rem);
 
-                        let mut buffer = vec![0; count];
-                        vmo.read(&mut buffer, offset)?;
-                        Ok(buffer)
-                    }
-                }
+                assert_eq_size!(usize, u64);
+                let count = count.try_into().unwrap();
+
+                let mut buffer = vec![0; count];
+                vmo.read(&mut buffer, offset)?;
+                Ok(buffer)
             }
         }
     }
@@ -593,6 +703,9 @@ impl VmoFileConnection {
                 let capacity = core::cmp::max(*size, *capacity);
                 match capacity.checked_sub(offset) {
                     None => return Err(zx::Status::OUT_OF_RANGE),
+                    // Writes are truncated at the capacity, but if not even a single byte can be
+                    // written, the client needs to know the file is full.
+                    Some(0) if !content.is_empty() => return Err(zx::Status::NO_SPACE),
                     Some(capacity) => {
                         assert_eq_size!(usize, u64);
                         let capacity = capacity.try_into().unwrap();
@@ -603,6 +716,8 @@ impl VmoFileConnection {
 
                         let len = content.len().try_into().unwrap();
                         let end = offset + len;
+                        // The state lock is held until the content is written, so concurrent reads
+                        // never observe the new size together with the old content.
                         if end > *size {
                             if end > *vmo_size {
                                 vmo.set_size(end)?;
@@ -637,25 +752,32 @@ impl VmoFileConnection {
             return Err(zx::Status::BAD_HANDLE);
         }
 
-        update_initialized_state! {
-            match *self.file.state().await;
-            error: "handle_seek" => return Err(zx::Status::INTERNAL);
-            { size, .. } => {
-                // There is an undocumented constraint that the seek offset can never exceed 63
-                // bits. See https://fxbug.dev/100754.
-                let origin: i64 = match origin {
-                    fio::SeekOrigin::Start => 0,
-                    fio::SeekOrigin::Current => self.seek,
-                    fio::SeekOrigin::End => size,
-                }.try_into().unwrap();
-                match origin.checked_add(offset) {
-                    None => Err(zx::Status::OUT_OF_RANGE),
-                    Some(offset) => {
-                        let offset = offset.try_into().map_err(|std::num::TryFromIntError { .. }| zx::Status::OUT_OF_RANGE)?;
-                        self.seek = offset;
-                        Ok(offset)
-                    }
-                }
+        let size = match &self.snapshot {
+            Some(snapshot) => snapshot.size,
+            None => update_initialized_state! {
+                match *self.file.state().await;
+                error: "handle_seek" => return Err(zx::Status::INTERNAL);
+                { size, .. } => size
+            },
+        };
+
+        // There is an undocumented constraint that the seek offset can never exceed 63 bits. See
+        // https://fxbug.dev/100754.
+        let origin: i64 = match origin {
+            fio::SeekOrigin::Start => 0,
+            fio::SeekOrigin::Current => self.seek,
+            fio::SeekOrigin::End => size,
+        }
+        .try_into()
+        .unwrap();
+        match origin.checked_add(offset) {
+            None => Err(zx::Status::OUT_OF_RANGE),
+            Some(offset) => {
+                let offset = offset
+                    .try_into()
+                    .map_err(|std::num::TryFromIntError { .. }| zx::Status::OUT_OF_RANGE)?;
+                self.seek = offset;
+                Ok(offset)
             }
         }
     }
@@ -684,6 +806,17 @@ impl VmoFileConnection {
             return Err(zx::Status::NOT_SUPPORTED);
         }
 
+        // The content size of a snapshot is set when it is created, and never changes.
+        if let Some(snapshot) = &self.snapshot {
+            let vmo_rights = vmo_flags_to_rights(flags);
+            let vmo = if flags.contains(fio::VmoFlags::PRIVATE_CLONE) {
+                Self::get_as_private(&snapshot.vmo, vmo_rights, snapshot.size)
+            } else {
+                Self::get_as_shared(&snapshot.vmo, vmo_rights)
+            }?;
+            return Ok(Buffer { vmo, size: snapshot.size });
+        }
+
         update_initialized_state! {
             match &*self.file.state().await;
             error: "handle_get_buffer" => Err(zx::Status::INTERNAL);
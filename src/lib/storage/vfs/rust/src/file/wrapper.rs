@@ -0,0 +1,208 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A file that forwards its operations to another file, changing some of them.  See
+//! [`FileWrapper`].
+
+use crate::{
+    directory::entry::{DirectoryEntry, EntryInfo},
+    execution_scope::ExecutionScope,
+    file::{common::open_file, ExclusiveOpen, File, ThroughputRecorder},
+    path::Path,
+};
+
+use {
+    async_trait::async_trait,
+    fidl::endpoints::ServerEnd,
+    fidl_fuchsia_io as fio,
+    fidl_fuchsia_mem::Buffer,
+    fuchsia_zircon::{self as zx, Status},
+    std::sync::Arc,
+};
+
+/// The operations of a [`FileWrapper`] that may behave differently from the wrapped file.  Every
+/// method is given the wrapped file as `inner`, and forwards the operation to it, unless
+/// overridden.
+#[async_trait]
+pub trait WrapperHooks<T: File>: Send + Sync + 'static {
+    /// Handles [`File::read_at`].
+    async fn read_at(&self, inner: &T, offset: u64, buffer: &mut [u8]) -> Result<u64, Status> {
+        inner.read_at(offset, buffer).await
+    }
+
+    /// Handles [`File::verify_range`].
+    async fn verify_range(
+        &self,
+        inner: &T,
+        offset: u64,
+        length: u64,
+        data: &[u8],
+    ) -> Result<(), Status> {
+        inner.verify_range(offset, length, data).await
+    }
+
+    /// Handles [`File::write_at`].
+    async fn write_at(&self, inner: &T, offset: u64, content: &[u8]) -> Result<u64, Status> {
+        inner.write_at(offset, content).await
+    }
+
+    /// Handles [`File::append`].
+    async fn append(&self, inner: &T, content: &[u8]) -> Result<(u64, u64), Status> {
+        inner.append(content).await
+    }
+
+    /// Handles [`File::event`].
+    fn event(&self, inner: &T) -> Result<Option<zx::Event>, Status> {
+        inner.event()
+    }
+
+    /// Handles [`File::describe`].
+    fn describe(
+        &self,
+        inner: &T,
+        connection_flags: fio::OpenFlags,
+    ) -> Result<fio::NodeInfo, Status> {
+        inner.describe(connection_flags)
+    }
+}
+
+/// A file that forwards all its operations to another file, except for the ones overridden by its
+/// [`WrapperHooks`].  See [`FileWrapper::new`].
+pub struct FileWrapper<T: File, H: WrapperHooks<T>> {
+    inner: Arc<T>,
+    hooks: H,
+    rights: fio::OpenFlags,
+}
+
+impl<T: File, H: WrapperHooks<T>> FileWrapper<T, H> {
+    /// Wraps `inner`, with `hooks` handling the operations they override.
+    ///
+    /// Connections to the wrapper can only be granted the `RIGHT_*` flags present in `rights`.
+    /// `inner` is still asked to accept the connection flags via [`File::open`], but it is not
+    /// consulted about the rights, so `rights` should not exceed the ones `inner` supports.
+    pub fn new(inner: Arc<T>, hooks: H, rights: fio::OpenFlags) -> Arc<Self> {
+        Arc::new(FileWrapper { inner, hooks, rights })
+    }
+
+    /// Returns the hooks of this wrapper.
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+}
+
+impl<T: File, H: WrapperHooks<T>> DirectoryEntry for FileWrapper<T, H> {
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        _mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        let rights = self.rights;
+        open_file(self, scope, flags, path, server_end, rights);
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        self.inner.entry_info()
+    }
+
+    fn set_assigned_inode(&self, inode: u64) {
+        self.inner.set_assigned_inode(inode)
+    }
+
+    fn into_file(self: Arc<Self>) -> Option<Arc<dyn File>> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl<T: File, H: WrapperHooks<T>> File for FileWrapper<T, H> {
+    async fn open(&self, flags: fio::OpenFlags) -> Result<(), Status> {
+        self.inner.open(flags).await
+    }
+
+    async fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<u64, Status> {
+        self.hooks.read_at(&self.inner, offset, buffer).await
+    }
+
+    async fn verify_range(&self, offset: u64, length: u64, data: &[u8]) -> Result<(), Status> {
+        self.hooks.verify_range(&self.inner, offset, length, data).await
+    }
+
+    async fn write_at(&self, offset: u64, content: &[u8]) -> Result<u64, Status> {
+        self.hooks.write_at(&self.inner, offset, content).await
+    }
+
+    async fn append(&self, content: &[u8]) -> Result<(u64, u64), Status> {
+        self.hooks.append(&self.inner, content).await
+    }
+
+    async fn truncate(&self, length: u64) -> Result<(), Status> {
+        self.inner.truncate(length).await
+    }
+
+    async fn get_buffer(&self, flags: fio::VmoFlags) -> Result<Buffer, Status> {
+        self.inner.get_buffer(flags).await
+    }
+
+    async fn get_size(&self) -> Result<u64, Status> {
+        self.inner.get_size().await
+    }
+
+    async fn get_attrs(&self) -> Result<fio::NodeAttributes, Status> {
+        self.inner.get_attrs().await
+    }
+
+    async fn get_attributes(
+        &self,
+        requested: fio::NodeAttributesQuery,
+    ) -> Result<fio::NodeAttributes2, Status> {
+        self.inner.get_attributes(requested).await
+    }
+
+    async fn set_attrs(
+        &self,
+        flags: fio::NodeAttributeFlags,
+        attrs: fio::NodeAttributes,
+    ) -> Result<(), Status> {
+        self.inner.set_attrs(flags, attrs).await
+    }
+
+    async fn close(&self) -> Result<(), Status> {
+        self.inner.close().await
+    }
+
+    async fn sync(&self) -> Result<(), Status> {
+        self.inner.sync().await
+    }
+
+    fn query_filesystem(&self) -> Result<fio::FilesystemInfo, Status> {
+        self.inner.query_filesystem()
+    }
+
+    fn exclusive(&self) -> Option<&ExclusiveOpen> {
+        self.inner.exclusive()
+    }
+
+    fn throughput(&self) -> Option<&ThroughputRecorder> {
+        self.inner.throughput()
+    }
+
+    fn record_io(&self, bytes_read: u64, bytes_written: u64) {
+        self.inner.record_io(bytes_read, bytes_written)
+    }
+
+    fn touch_modified(&self) {
+        self.inner.touch_modified()
+    }
+
+    fn event(&self) -> Result<Option<zx::Event>, Status> {
+        self.hooks.event(&self.inner)
+    }
+
+    fn describe(&self, connection_flags: fio::OpenFlags) -> Result<fio::NodeInfo, Status> {
+        self.hooks.describe(&self.inner, connection_flags)
+    }
+}
@@ -12,7 +12,8 @@ use {
 pub(crate) mod simple;
 
 /// An implementation of a FilesystemRename represents a filesystem that supports
-/// renaming nodes inside it.
+/// renaming nodes inside it.  Renaming a node into a directory that belongs to a different
+/// filesystem fails with `ZX_ERR_NOT_SUPPORTED`.
 #[async_trait]
 pub trait FilesystemRename: Sync + Send {
     async fn rename(
@@ -4,26 +4,70 @@
 
 use {
     super::{Filesystem, FilesystemRename},
-    crate::{directory::helper::DirectlyMutable, path::Path},
+    crate::{
+        directory::{entry::DirectoryEntry, helper::DirectlyMutable},
+        path::Path,
+    },
     async_trait::async_trait,
     fuchsia_zircon::Status,
-    std::{any::Any, marker::PhantomData, sync::Arc},
+    std::{
+        any::Any,
+        marker::PhantomData,
+        sync::{Arc, Mutex},
+    },
 };
 
-pub struct SimpleFilesystem<T: DirectlyMutable + 'static> {
+/// A callback that can veto a rename, called with the source and the destination names.  See
+/// [`SimpleFilesystem::new_with_validator`].
+pub type RenameValidator = Arc<dyn Fn(&str, &str) -> Result<(), Status> + Send + Sync>;
+
+/// A directory that [`SimpleFilesystem`] can move entries between.
+pub(crate) trait SimpleDirectory: DirectlyMutable + Sized + 'static {
+    /// Returns the directory this directory was most recently added to, if it is still alive.
+    fn parent(&self) -> Option<Arc<Self>>;
+
+    /// Returns the filesystem this directory was created with.
+    fn filesystem(&self) -> &SimpleFilesystem<Self>;
+}
+
+pub(crate) struct SimpleFilesystem<T: SimpleDirectory> {
     directory_type: PhantomData<T>,
+    validator: Option<RenameValidator>,
+    // Serializes the renames done through this filesystem that move entries between directories,
+    // so that the ancestors of the destination directory do not change while a rename checks that
+    // it does not create a cycle.
+    rename_lock: Mutex<()>,
 }
 
-impl<T: DirectlyMutable + 'static> SimpleFilesystem<T> {
+impl<T: SimpleDirectory> SimpleFilesystem<T> {
     pub fn new() -> Self {
-        SimpleFilesystem { directory_type: PhantomData }
+        SimpleFilesystem {
+            directory_type: PhantomData,
+            validator: None,
+            rename_lock: Mutex::new(()),
+        }
+    }
+
+    /// Same as [`Self::new`], but every rename from or into a directory using this filesystem, or
+    /// any directory added to it that does not have a validator of its own, is first passed to
+    /// `validator`, with the source and the destination names.  If `validator` returns an error,
+    /// nothing is moved and the error is returned to the client.
+    pub fn new_with_validator<F>(validator: F) -> Self
+    where
+        F: Fn(&str, &str) -> Result<(), Status> + Send + Sync + 'static,
+    {
+        SimpleFilesystem {
+            directory_type: PhantomData,
+            validator: Some(Arc::new(validator)),
+            rename_lock: Mutex::new(()),
+        }
     }
 }
 
 #[async_trait]
 impl<T> FilesystemRename for SimpleFilesystem<T>
 where
-    T: DirectlyMutable + 'static,
+    T: SimpleDirectory,
 {
     async fn rename(
         &self,
@@ -32,8 +76,25 @@ where
         dst_dir: Arc<Any + Sync + Send + 'static>,
         dst: Path,
     ) -> Result<(), Status> {
-        let src_parent = src_dir.downcast::<T>().map_err(|_| Status::INVALID_ARGS)?;
-        let dst_parent = dst_dir.downcast::<T>().map_err(|_| Status::INVALID_ARGS)?;
+        // A directory of a different type belongs to a different filesystem, and entries can not be
+        // moved across filesystems.
+        let src_parent = src_dir.downcast::<T>().map_err(|_| Status::NOT_SUPPORTED)?;
+        let dst_parent = dst_dir.downcast::<T>().map_err(|_| Status::NOT_SUPPORTED)?;
+
+        let src_ancestors = ancestors(&src_parent);
+        let dst_ancestors = ancestors(&dst_parent);
+
+        // Both the source and the destination directories need to accept the rename.  The
+        // validator is only called once when both directories inherit the same one.
+        let src_validator = inherited_validator(&src_ancestors);
+        let dst_validator = inherited_validator(&dst_ancestors).filter(|dst_validator| {
+            !src_validator
+                .as_ref()
+                .map_or(false, |src_validator| Arc::ptr_eq(src_validator, dst_validator))
+        });
+        for validator in src_validator.iter().chain(dst_validator.iter()) {
+            validator(src.as_ref(), dst.as_ref())?;
+        }
 
         // We need to lock directories using the same global order, otherwise we risk a deadlock. We
         // will use directory objects memory location to establish global order for the locks.  It
@@ -43,34 +104,94 @@ where
         let src_order = src_parent.as_ref() as *const dyn DirectlyMutable as *const usize as usize;
         let dst_order = dst_parent.as_ref() as *const dyn DirectlyMutable as *const usize as usize;
 
+        if src_order == dst_order {
+            return src_parent.rename_within(src.into_string(), dst.into_string());
+        }
+
+        // Moving a directory into itself or into one of its descendants would detach the whole
+        // subtree from the filesystem, creating a cycle.  The check is done once the entry to move
+        // is locked, and `rename_lock` ensures that the ancestors of the destination can not be
+        // moved concurrently through this filesystem.
+        let _rename_lock = self.rename_lock.lock().unwrap();
+
         if src_order < dst_order {
             // We must ensure that we have checked the global order for the locks for
             // `src_parent` and `dst_parent` and we are calling `rename_from` as `src_parent` has a
             // smaller memory address than the `dst_parent`.
             src_parent.clone().rename_from(
                 src.into_string(),
-                Box::new(move |entry| dst_parent.add_entry_impl(dst.into_string(), entry, true)),
+                Box::new(move |entry| {
+                    reject_cycle(&entry, &dst_parent)?;
+                    dst_parent.add_entry_impl(dst.into_string(), entry, true)
+                }),
             )
-        } else if src_order == dst_order {
-            src_parent.rename_within(src.into_string(), dst.into_string())
         } else {
             // We must ensure that we have checked the global order for the locks for
             // `src_parent` and `dst_parent` and we are calling `rename_to` as `dst_parent` has a
-            // smaller memory address than the `src_parent`.
+            // smaller memory address than the `src_parent`.  The entry is removed from
+            // `src_parent` using `rename_from`, so that it can be checked before it is removed.
+            let dst_dir = dst_parent.clone();
             dst_parent.rename_to(
                 dst.into_string(),
-                Box::new(move || match src_parent.remove_entry_impl(src.into_string(), false)? {
-                    None => Err(Status::NOT_FOUND),
-                    Some(entry) => Ok(entry),
+                Box::new(move || {
+                    let moved = Arc::new(Mutex::new(None));
+                    let slot = moved.clone();
+                    src_parent.rename_from(
+                        src.into_string(),
+                        Box::new(move |entry| {
+                            reject_cycle(&entry, &dst_dir)?;
+                            *slot.lock().unwrap() = Some(entry);
+                            Ok(())
+                        }),
+                    )?;
+                    let entry = moved.lock().unwrap().take();
+                    entry.ok_or(Status::NOT_FOUND)
                 }),
             )
         }
     }
 }
 
+/// Returns `dir` followed by the directories it was added to, up to the root of its tree.  The same
+/// directory may be added into itself, directly or through other directories, so the walk stops at
+/// the first directory that was already visited.
+fn ancestors<T: SimpleDirectory>(dir: &Arc<T>) -> Vec<Arc<T>> {
+    let mut ancestors = vec![dir.clone()];
+    while let Some(parent) = ancestors.last().unwrap().parent() {
+        if ancestors.iter().any(|ancestor| Arc::ptr_eq(ancestor, &parent)) {
+            break;
+        }
+        ancestors.push(parent);
+    }
+    ancestors
+}
+
+/// Returns the validator of the closest directory in `ancestors`, as returned by [`ancestors()`],
+/// that has one.
+fn inherited_validator<T: SimpleDirectory>(ancestors: &[Arc<T>]) -> Option<RenameValidator> {
+    ancestors.iter().find_map(|ancestor| ancestor.filesystem().validator.clone())
+}
+
+/// Fails with `ZX_ERR_INVALID_ARGS` if `entry` is `dir` or one of its ancestors, as moving it into
+/// `dir` would create a cycle.  A directory that was added into several places is only checked
+/// against the place it was most recently added to.
+fn reject_cycle<T: SimpleDirectory>(
+    entry: &Arc<dyn DirectoryEntry>,
+    dir: &Arc<T>,
+) -> Result<(), Status> {
+    let moved = match entry.clone().into_any().downcast::<T>() {
+        Ok(moved) => moved,
+        Err(_) => return Ok(()),
+    };
+    if ancestors(dir).iter().any(|ancestor| Arc::ptr_eq(ancestor, &moved)) {
+        return Err(Status::INVALID_ARGS);
+    }
+    Ok(())
+}
+
 impl<T> Filesystem for SimpleFilesystem<T>
 where
-    T: DirectlyMutable + 'static,
+    T: SimpleDirectory,
 {
     /// Returns the size of the Filesystem's block device.  This is the granularity at which I/O is
     /// performed.
@@ -78,3 +199,146 @@ where
         1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::SimpleFilesystem;
+
+    use crate::{
+        directory::{helper::DirectlyMutable, immutable, mutable},
+        file::vmo::asynchronous::read_only_static,
+        filesystem::FilesystemRename,
+        path::Path,
+    };
+
+    use {fuchsia_async as fasync, fuchsia_zircon::Status, std::sync::Arc};
+
+    #[fasync::run_singlethreaded(test)]
+    async fn rename_across_filesystems_not_supported() {
+        let src = mutable::simple();
+        src.add_entry("file", read_only_static(b"Content")).unwrap();
+        let dst = immutable::simple();
+
+        let fs = SimpleFilesystem::<mutable::Simple>::new();
+        let result = fs
+            .rename(
+                src.clone(),
+                Path::validate_and_split("file").unwrap(),
+                dst.clone(),
+                Path::validate_and_split("file").unwrap(),
+            )
+            .await;
+        assert_eq!(result, Err(Status::NOT_SUPPORTED));
+
+        // Nothing was moved.
+        assert!(src.get_entry("file").is_ok());
+        assert_eq!(dst.get_entry("file").err(), Some(Status::NOT_FOUND));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn rename_between_separate_directories() {
+        // Mutable directories held by an immutable root, as built by `pseudo_directory!`, are not
+        // recorded as each other's relatives, but entries can still be moved between them.
+        let src = mutable::simple();
+        src.add_entry("file", read_only_static(b"Content")).unwrap();
+        let dst = mutable::simple();
+        let root = immutable::simple();
+        root.add_entry("src", src.clone()).unwrap();
+        root.add_entry("dst", dst.clone()).unwrap();
+
+        let result = src
+            .get_filesystem()
+            .rename(
+                src.clone(),
+                Path::validate_and_split("file").unwrap(),
+                dst.clone(),
+                Path::validate_and_split("moved").unwrap(),
+            )
+            .await;
+        assert_eq!(result, Ok(()));
+        assert_eq!(src.get_entry("file").err(), Some(Status::NOT_FOUND));
+        assert!(dst.get_entry("moved").is_ok());
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn rename_validator() {
+        let dir = mutable::simple_with_rename_validator(|_src, dst| {
+            if dst.starts_with('.') {
+                Err(Status::ACCESS_DENIED)
+            } else {
+                Ok(())
+            }
+        });
+        dir.add_entry("file", read_only_static(b"Content")).unwrap();
+
+        let rename = |src: &str, dst: &str| {
+            dir.get_filesystem().rename(
+                dir.clone(),
+                Path::validate_and_split(src).unwrap(),
+                dir.clone(),
+                Path::validate_and_split(dst).unwrap(),
+            )
+        };
+
+        assert_eq!(rename("file", ".hidden").await, Err(Status::ACCESS_DENIED));
+        assert!(dir.get_entry("file").is_ok());
+        assert_eq!(dir.get_entry(".hidden").err(), Some(Status::NOT_FOUND));
+
+        assert_eq!(rename("file", "visible").await, Ok(()));
+        assert_eq!(dir.get_entry("file").err(), Some(Status::NOT_FOUND));
+        assert!(dir.get_entry("visible").is_ok());
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn rename_into_own_subtree_rejected() {
+        let root = mutable::simple();
+        let a = mutable::simple();
+        let b = mutable::simple();
+        a.add_entry("b", b.clone()).unwrap();
+        root.add_entry("a", a.clone()).unwrap();
+
+        let fs = SimpleFilesystem::<mutable::Simple>::new();
+        for dst_parent in [a.clone(), b.clone()] {
+            let result = fs
+                .rename(
+                    root.clone(),
+                    Path::validate_and_split("a").unwrap(),
+                    dst_parent,
+                    Path::validate_and_split("a").unwrap(),
+                )
+                .await;
+            assert_eq!(result, Err(Status::INVALID_ARGS));
+        }
+
+        // Nothing was moved.
+        assert!(root.get_entry("a").is_ok());
+        assert!(a.get_entry("b").is_ok());
+        assert_eq!(a.get_entry("a").err(), Some(Status::NOT_FOUND));
+        assert_eq!(b.get_entry("a").err(), Some(Status::NOT_FOUND));
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn rename_into_moved_subtree_rejected() {
+        let root = mutable::simple();
+        let a = mutable::simple();
+        let b = mutable::simple();
+        root.add_entry("a", a.clone()).unwrap();
+        root.add_entry("b", b.clone()).unwrap();
+
+        let fs = SimpleFilesystem::<mutable::Simple>::new();
+        let rename = |dst_parent: &Arc<mutable::Simple>, name: &str| {
+            fs.rename(
+                root.clone(),
+                Path::validate_and_split(name).unwrap(),
+                dst_parent.clone(),
+                Path::validate_and_split(name).unwrap(),
+            )
+        };
+
+        // `b` is now a descendant of `a`, so `a` can not be moved into it.
+        assert_eq!(rename(&a, "b").await, Ok(()));
+        assert_eq!(rename(&b, "a").await, Err(Status::INVALID_ARGS));
+        assert!(root.get_entry("a").is_ok());
+        assert!(a.get_entry("b").is_ok());
+    }
+}
@@ -15,13 +15,16 @@ pub mod common;
 
 pub mod execution_scope;
 pub mod path;
+pub mod quiescing;
 pub mod registry;
+pub mod serve;
 
 pub mod directory;
 pub mod file;
 pub mod filesystem;
 pub mod remote;
 pub mod service;
+pub mod symlink;
 
 pub mod tree_builder;
 
@@ -41,6 +44,13 @@ pub mod pseudo_directory;
 ///
 /// separated by commas, with an optional trailing comma.
 ///
+/// Entries that are only known at runtime can be spliced in using a rule of the following form:
+///
+///   `..` <something that implements IntoIterator<Item = (String, Arc<dyn DirectoryEntry>)>>
+///
+/// Every pair produced by the iterator is added to the directory, in the same way as the other
+/// entries.
+///
 /// It generates a nested pseudo directory, using [`directory::immutable::simple()`] then adding
 /// all the specified entries in it, by calling
 /// [`crate::directory::helper::DirectlyMutable::add_entry`].
@@ -146,6 +146,48 @@ impl Path {
         }
     }
 
+    /// Returns the rest of this path without its last component, or `None` if there are no
+    /// components left.  The parent of a single component path is ".".
+    pub fn parent(&self) -> Option<Path> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let end = if self.is_dir { self.inner.len() - 1 } else { self.inner.len() };
+        let rest = &self.inner[self.next..end];
+        match rest.rfind('/') {
+            None => Some(Self::dot()),
+            Some(i) => Some(Path { is_dir: false, inner: rest[..i].to_string(), next: 0 }),
+        }
+    }
+
+    /// Returns the rest of this path with `component` appended to it.  `component` is checked the
+    /// same way [`Self::validate_and_split()`] checks the components of a path: an empty
+    /// component, ".", "..", or a component containing a '/' fail with `ZX_ERR_INVALID_ARGS`,
+    /// while a component longer than `MAX_FILENAME`, or a resulting path longer than `MAX_PATH`,
+    /// fail with `ZX_ERR_BAD_PATH`.
+    pub fn join(&self, component: &str) -> Result<Path, Status> {
+        if component.is_empty() || component == "." || component == ".." || component.contains('/')
+        {
+            return Err(Status::INVALID_ARGS);
+        }
+        if component.len() as u64 > fio::MAX_FILENAME {
+            return Err(Status::BAD_PATH);
+        }
+
+        if self.is_empty() {
+            return Self::validate_and_split(component);
+        }
+
+        let rest = &self.inner[self.next..];
+        let joined = if self.is_dir {
+            format!("{}{}", rest, component)
+        } else {
+            format!("{}/{}", rest, component)
+        };
+        Self::validate_and_split(joined)
+    }
+
     /// Converts this `Path` into a `String` holding the rest of the path.  Note that if there are
     /// no more components, this will return an empty string, which is *not* a valid path for
     /// fuchsia.io.
@@ -651,4 +693,49 @@ mod tests {
         path.next();
         assert_eq!(path.as_ref(), ".");
     }
+
+    #[test]
+    fn parent() {
+        assert_eq!(Path::dot().parent(), None);
+        assert_eq!(path("a").parent(), Some(Path::dot()));
+        assert_eq!(path("a/").parent(), Some(Path::dot()));
+        assert_eq!(path("a/b/c").parent(), Some(path("a/b")));
+        assert_eq!(path("/a/b/c/").parent(), Some(path("a/b")));
+
+        // Only the rest of the path is considered.
+        let mut path = path("a/b/c");
+        path.next();
+        assert_eq!(path.parent(), Some(Path::validate_and_split("b").unwrap()));
+        path.next();
+        path.next();
+        assert_eq!(path.parent(), None);
+    }
+
+    #[test]
+    fn join() {
+        assert_eq!(Path::dot().join("a"), Ok(path("a")));
+        assert_eq!(path("a/b").join("c"), Ok(path("a/b/c")));
+        assert_eq!(path("/a/b/").join("c"), Ok(path("a/b/c")));
+
+        let joined = path("a").join("b").unwrap();
+        assert!(!joined.is_dir());
+
+        // Only the rest of the path is considered.
+        let mut path = path("a/b");
+        path.next();
+        assert_eq!(path.join("c"), Ok(Path::validate_and_split("b/c").unwrap()));
+    }
+
+    #[test]
+    fn join_invalid_component() {
+        let base = path("a");
+        assert_eq!(base.join(""), Err(Status::INVALID_ARGS));
+        assert_eq!(base.join("."), Err(Status::INVALID_ARGS));
+        assert_eq!(base.join(".."), Err(Status::INVALID_ARGS));
+        assert_eq!(base.join("b/c"), Err(Status::INVALID_ARGS));
+        assert_eq!(Path::dot().join("."), Err(Status::INVALID_ARGS));
+
+        let too_long = "a".repeat(fio::MAX_FILENAME as usize + 1);
+        assert_eq!(base.join(&too_long), Err(Status::BAD_PATH));
+    }
 }
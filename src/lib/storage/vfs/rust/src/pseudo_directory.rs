@@ -18,6 +18,7 @@ pub fn unwrap_add_entry_span(entry: &str, location: &str, res: Result<(), Status
     let text;
     let error_text = match status {
         Status::ALREADY_EXISTS => "Duplicate entry name.",
+        Status::BAD_PATH => "Entry name is longer than MAX_FILENAME, or contains a '/'.",
         _ => {
             text = format!("`add_entry` failed with an unexpected status: {}", status);
             &text
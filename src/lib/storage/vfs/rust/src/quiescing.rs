@@ -0,0 +1,116 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! A node that stops accepting new connections once it is being shut down, while the existing
+//! connections keep running.  See [`quiescing`] for details.
+
+use crate::{
+    common::send_on_open_with_error,
+    directory::entry::{DirectoryEntry, EntryInfo},
+    execution_scope::ExecutionScope,
+    path::Path,
+};
+
+use {
+    fidl::endpoints::ServerEnd,
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status,
+    std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Wraps `inner`, forwarding all the open requests to it, until [`Quiescing::begin_shutdown()`] is
+/// called.  After that, opens fail with `ZX_ERR_UNAVAILABLE`, while the connections that are
+/// already open continue to be served until they are closed.
+///
+/// Only the opens that go through this node are rejected.  If `inner` is a directory, opens sent
+/// over the connections to it that are already open still reach its entries.
+pub fn quiescing(inner: Arc<dyn DirectoryEntry>) -> Arc<Quiescing> {
+    Arc::new(Quiescing { inner, shutting_down: AtomicBool::new(false) })
+}
+
+/// Node that rejects new connections once it is being shut down.  See [`quiescing`].
+pub struct Quiescing {
+    inner: Arc<dyn DirectoryEntry>,
+    shutting_down: AtomicBool,
+}
+
+impl Quiescing {
+    /// Makes all the subsequent opens fail with `ZX_ERR_UNAVAILABLE`.  The existing connections are
+    /// not affected.  There is no way to resume accepting connections.
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true after [`Quiescing::begin_shutdown()`] has been called.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+    }
+}
+
+impl DirectoryEntry for Quiescing {
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        if self.is_shutting_down() {
+            send_on_open_with_error(flags, server_end, Status::UNAVAILABLE);
+            return;
+        }
+
+        self.inner.clone().open(scope, flags, mode, path, server_end);
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        self.inner.entry_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::quiescing;
+
+    // Macros are exported into the root of the crate.
+    use crate::{
+        assert_close, assert_event, assert_read, assert_seek, open_as_file_assert_err,
+        open_get_proxy_assert, open_get_vmo_file_proxy_assert_ok,
+    };
+
+    use crate::{
+        directory::test_utils::run_server_client, file::vmo::asynchronous::read_only_static,
+    };
+
+    use {fidl_fuchsia_io as fio, fuchsia_zircon::Status, vfs_macros::pseudo_directory};
+
+    #[test]
+    fn shutdown_rejects_new_opens() {
+        let file = quiescing(read_only_static(b"Content"));
+        let root = pseudo_directory! {
+            "file" => file.clone(),
+        };
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+
+            let existing = open_get_vmo_file_proxy_assert_ok!(&root, flags, "file");
+
+            file.begin_shutdown();
+            open_as_file_assert_err!(&root, flags, "file", Status::UNAVAILABLE);
+
+            // The connection opened before the shutdown started is still served.
+            assert_read!(existing, "Content");
+            assert_seek!(existing, 0, Start);
+            assert_read!(existing, "Content");
+            assert_close!(existing);
+
+            assert_close!(root);
+        });
+    }
+}
@@ -15,9 +15,13 @@ use crate::{
 };
 
 use {
-    fidl::{self, endpoints::ServerEnd},
-    fidl_fuchsia_io as fio, fuchsia_zircon as zx,
-    std::sync::Arc,
+    fidl::{
+        self,
+        endpoints::{Proxy, ServerEnd},
+    },
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::{self as zx, AsHandleRef},
+    std::sync::{Arc, Mutex},
 };
 
 /// The type for the callback function used to create new connections to the remote object. The
@@ -43,7 +47,7 @@ pub fn remote_boxed(open: RoutingFn) -> Arc<Remote> {
 
 /// Create a new [`Remote`] node that forwards open requests to the provided callback. This routing
 /// function is called once per open request. The dirent type is set as `DirentType::Unknown`. If
-/// the remote node is a known `DIRENT_TYPE_*` type, you may wish to use [`remote_boxed_with_type`]
+/// the remote node is a known `DIRENT_TYPE_*` type, you may wish to use [`remote_with_type`]
 /// instead.
 pub fn remote<Open>(open: Open) -> Arc<Remote>
 where
@@ -55,6 +59,18 @@ where
     remote_boxed(Box::new(open))
 }
 
+/// Create a new [`Remote`] node that forwards open requests to the provided callback, same as
+/// [`remote`], but the node will be reported as `dirent_type` in the listings of its parent.
+pub fn remote_with_type<Open>(open: Open, dirent_type: fio::DirentType) -> Arc<Remote>
+where
+    Open: Fn(ExecutionScope, fio::OpenFlags, u32, Path, ServerEnd<fio::NodeMarker>)
+        + Send
+        + Sync
+        + 'static,
+{
+    remote_boxed_with_type(Box::new(open), dirent_type)
+}
+
 /// Create a new [`Remote`] node that forwards open requests to the provided [`DirectoryProxy`],
 /// effectively handing off the handling of any further requests to the remote fidl server.
 pub fn remote_dir(dir: fio::DirectoryProxy) -> Arc<Remote> {
@@ -66,6 +82,75 @@ pub fn remote_dir(dir: fio::DirectoryProxy) -> Arc<Remote> {
     )
 }
 
+/// Create a new [`Remote`] node that forwards open requests to the provided [`DirectoryProxy`],
+/// same as [`remote_dir`], but first removes from the open flags all the rights that are not in
+/// `allowed_rights`.  Useful to give a sandbox a less privileged view of a remote directory, for
+/// example a read-only one.
+///
+/// Only the rights are filtered, all the other flags, including `OPEN_FLAG_NODE_REFERENCE` and
+/// `OPEN_FLAG_DESCRIBE`, are forwarded unchanged.  The `OPEN_FLAG_POSIX_*` flags that would let the
+/// remote server grant a removed right are removed as well.
+pub fn remote_dir_with_rights_filter(
+    dir: fio::DirectoryProxy,
+    allowed_rights: fio::OpenFlags,
+) -> Arc<Remote> {
+    remote_boxed_with_type(
+        Box::new(move |_scope, flags, mode, path, server_end| {
+            let flags = filter_rights(flags, allowed_rights);
+            let _ = dir.open(flags, mode, path.as_ref(), server_end);
+        }),
+        fio::DirentType::Directory,
+    )
+}
+
+fn filter_rights(flags: fio::OpenFlags, allowed_rights: fio::OpenFlags) -> fio::OpenFlags {
+    let mut removed = fio::OPEN_RIGHTS & !allowed_rights;
+    if removed.contains(fio::OpenFlags::RIGHT_WRITABLE) {
+        removed |= fio::OpenFlags::POSIX_WRITABLE;
+    }
+    if removed.contains(fio::OpenFlags::RIGHT_EXECUTABLE) {
+        removed |= fio::OpenFlags::POSIX_EXECUTABLE;
+    }
+    // TODO(fxbug.dev/81185): Remove OPEN_FLAG_POSIX_DEPRECATED.
+    if removed.intersects(fio::OpenFlags::RIGHT_WRITABLE | fio::OpenFlags::RIGHT_EXECUTABLE) {
+        removed |= fio::OpenFlags::POSIX_DEPRECATED;
+    }
+    flags & !removed
+}
+
+/// Create a new [`Remote`] node that forwards open requests to a [`DirectoryProxy`] obtained from
+/// `connector`.  The connector is called on the first open request, and again whenever the
+/// previously obtained proxy is observed to be peer-closed, so a remote server that goes away is
+/// transparently reconnected to on the next open.
+///
+/// Note that the proxy is only checked for closure before an open request is forwarded.  If the
+/// remote server closes its end of the channel after that check, but before it has processed the
+/// request, that open request is lost: `server_end` is dropped and the client will observe its
+/// channel being closed.  Only the open requests that follow will use a freshly connected proxy.
+pub fn remote_dir_lazy<Connector>(connector: Connector) -> Arc<Remote>
+where
+    Connector: Fn() -> fio::DirectoryProxy + Send + Sync + 'static,
+{
+    let dir: Mutex<Option<fio::DirectoryProxy>> = Mutex::new(None);
+    remote_boxed_with_type(
+        Box::new(move |_scope, flags, mode, path, server_end| {
+            let mut dir = dir.lock().unwrap();
+            if dir.as_ref().map_or(true, is_peer_closed) {
+                *dir = Some(connector());
+            }
+            let _ = dir.as_ref().unwrap().open(flags, mode, path.as_ref(), server_end);
+        }),
+        fio::DirentType::Directory,
+    )
+}
+
+fn is_peer_closed(proxy: &fio::DirectoryProxy) -> bool {
+    proxy
+        .as_channel()
+        .wait_handle(zx::Signals::CHANNEL_PEER_CLOSED, zx::Time::INFINITE_PAST)
+        .is_ok()
+}
+
 /// Create a new [`Remote`] node that clones the given node when connected.
 pub fn remote_node(node: fio::NodeProxy) -> Arc<Remote> {
     remote_boxed(Box::new(move |_scope, flags, _mode, path, server_end| {
@@ -80,6 +165,11 @@ pub fn remote_node(node: fio::NodeProxy) -> Arc<Remote> {
 /// A Remote node is a node which forwards most open requests to another entity. The forwarding is
 /// done by calling a routing function of type [`RoutingFn`] provided at the time of construction.
 /// The remote node itself doesn't do any flag validation when forwarding the open call.
+///
+/// No local connection is created for the remote node, not even for `NODE_REFERENCE` opens, so
+/// any `Clone` requests on a connection obtained through a remote node are handled by the remote
+/// entity, without calling the routing function again.  In particular, `CLONE_SAME_RIGHTS`
+/// inherits the rights the remote granted to the original connection.
 pub struct Remote {
     open: RoutingFn,
     dirent_type: fio::DirentType,
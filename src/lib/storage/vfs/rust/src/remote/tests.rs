@@ -4,9 +4,14 @@
 
 //! Tests for the remote node.
 
-use super::remote_dir;
+use super::{
+    filter_rights, remote, remote_dir, remote_dir_lazy, remote_dir_with_rights_filter, Remote,
+};
 
-use crate::{assert_close, assert_read, assert_read_dirents, pseudo_directory};
+use crate::{
+    assert_close, assert_event, assert_read, assert_read_dirents,
+    clone_get_directory_proxy_assert_ok, clone_get_proxy_assert, pseudo_directory,
+};
 
 use crate::{
     directory::{
@@ -21,6 +26,10 @@ use crate::{
 use {
     fidl::{self, endpoints::ServerEnd},
     fidl_fuchsia_io as fio, fuchsia_async as fasync,
+    std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
 };
 
 fn set_up_remote(scope: ExecutionScope) -> fio::DirectoryProxy {
@@ -143,3 +152,197 @@ fn remote_dir_direct_connection_dir_contents() {
         assert_close!(proxy);
     })
 }
+
+#[test]
+fn remote_dir_lazy_reconnects_after_peer_closed() {
+    let exec = fasync::TestExecutor::new().expect("Executor creation failed");
+    let scope = ExecutionScope::new();
+
+    run_client(exec, || async move {
+        // The first connection is to a proxy that is not served by anyone.  We close the server
+        // end below to simulate the remote server going away.
+        let (first_proxy, first_server_end) =
+            fidl::endpoints::create_proxy::<fio::DirectoryMarker>().unwrap();
+        let first_proxy = Mutex::new(Some(first_proxy));
+        let connect_count = Arc::new(AtomicUsize::new(0));
+
+        let server = remote_dir_lazy({
+            let scope = scope.clone();
+            let connect_count = connect_count.clone();
+            move || {
+                connect_count.fetch_add(1, Ordering::Relaxed);
+                first_proxy.lock().unwrap().take().unwrap_or_else(|| set_up_remote(scope.clone()))
+            }
+        });
+
+        let open_a = |server: &Arc<Remote>| {
+            let (proxy, server_end) = fidl::endpoints::create_proxy::<fio::FileMarker>().unwrap();
+            server.clone().open(
+                scope.clone(),
+                fio::OpenFlags::RIGHT_READABLE,
+                fio::MODE_TYPE_FILE,
+                Path::validate_and_split("a").unwrap(),
+                server_end.into_channel().into(),
+            );
+            proxy
+        };
+
+        let _lost = open_a(&server);
+        assert_eq!(connect_count.load(Ordering::Relaxed), 1);
+
+        drop(first_server_end);
+
+        let proxy = open_a(&server);
+        assert_eq!(connect_count.load(Ordering::Relaxed), 2);
+        assert_read!(proxy, "a content");
+        assert_close!(proxy);
+
+        // The new connection is still alive, so it is reused.
+        let proxy = open_a(&server);
+        assert_eq!(connect_count.load(Ordering::Relaxed), 2);
+        assert_read!(proxy, "a content");
+        assert_close!(proxy);
+    })
+}
+
+#[test]
+fn filter_rights_keeps_other_flags() {
+    let flags = fio::OpenFlags::RIGHT_READABLE
+        | fio::OpenFlags::RIGHT_WRITABLE
+        | fio::OpenFlags::NODE_REFERENCE
+        | fio::OpenFlags::DESCRIBE;
+    assert_eq!(
+        filter_rights(flags, fio::OpenFlags::RIGHT_READABLE),
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::NODE_REFERENCE | fio::OpenFlags::DESCRIBE
+    );
+    assert_eq!(filter_rights(flags, fio::OPEN_RIGHTS), flags);
+    assert_eq!(
+        filter_rights(
+            fio::OpenFlags::RIGHT_READABLE
+                | fio::OpenFlags::POSIX_WRITABLE
+                | fio::OpenFlags::POSIX_EXECUTABLE,
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_EXECUTABLE
+        ),
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::POSIX_EXECUTABLE
+    );
+}
+
+#[test]
+fn remote_dir_with_rights_filter_strips_write() {
+    let exec = fasync::TestExecutor::new().expect("Executor creation failed");
+    let scope = ExecutionScope::new();
+
+    let remote_proxy = set_up_remote(scope.clone());
+    let server = remote_dir_with_rights_filter(remote_proxy, fio::OpenFlags::RIGHT_READABLE);
+
+    run_client(exec, || async move {
+        let (proxy, server_end) = fidl::endpoints::create_proxy::<fio::FileMarker>().unwrap();
+        let flags = fio::OpenFlags::RIGHT_READABLE
+            | fio::OpenFlags::RIGHT_WRITABLE
+            | fio::OpenFlags::DESCRIBE;
+        server.open(
+            scope,
+            flags,
+            0,
+            Path::validate_and_split("a").unwrap(),
+            server_end.into_channel().into(),
+        );
+
+        // `OPEN_FLAG_DESCRIBE` reaches the remote, and the connection it opens is read-only.
+        assert_event!(proxy, fio::FileEvent::OnOpen_ { s, info }, {
+            assert_eq!(s, 0);
+            assert!(info.is_some());
+        });
+        let (status, flags) = proxy.get_flags().await.unwrap();
+        assert_eq!(status, 0);
+        assert_eq!(flags, fio::OpenFlags::RIGHT_READABLE);
+        assert_read!(proxy, "a content");
+        assert_close!(proxy);
+    })
+}
+
+// Tests for cloning connections obtained through a remote node.  Clones should be served by the
+// remote rather than by a local stub, without going through the routing function again.
+
+type OpenLog = Arc<Mutex<Vec<fio::OpenFlags>>>;
+
+fn logging_remote(remote_proxy: fio::DirectoryProxy) -> (Arc<Remote>, OpenLog) {
+    let opens = OpenLog::default();
+    let server = remote({
+        let opens = opens.clone();
+        move |_scope, flags, mode, path, server_end| {
+            opens.lock().unwrap().push(flags);
+            let _ = remote_proxy.open(flags, mode, path.as_ref(), server_end);
+        }
+    });
+    (server, opens)
+}
+
+#[test]
+fn remote_clone_same_rights_reaches_remote() {
+    let exec = fasync::TestExecutor::new().expect("Executor creation failed");
+    let scope = ExecutionScope::new();
+
+    let (server, opens) = logging_remote(set_up_remote(scope.clone()));
+
+    run_client(exec, || async move {
+        let (proxy, server_end) = fidl::endpoints::create_proxy::<fio::DirectoryMarker>().unwrap();
+        server.open(
+            scope,
+            fio::OpenFlags::RIGHT_READABLE,
+            fio::MODE_TYPE_DIRECTORY,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+
+        let clone = clone_get_directory_proxy_assert_ok!(
+            &proxy,
+            fio::OpenFlags::DESCRIBE | fio::OpenFlags::CLONE_SAME_RIGHTS
+        );
+        assert_eq!(*opens.lock().unwrap(), vec![fio::OpenFlags::RIGHT_READABLE]);
+
+        for proxy in [&proxy, &clone] {
+            let (status, flags) = proxy.get_flags().await.unwrap();
+            assert_eq!(status, 0);
+            assert_eq!(flags, fio::OpenFlags::RIGHT_READABLE);
+
+            let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+            expected
+                .add(fio::DirentType::Directory, b".")
+                .add(fio::DirentType::File, b"a")
+                .add(fio::DirentType::Directory, b"dir");
+            assert_read_dirents!(proxy, 1000, expected.into_vec());
+        }
+
+        assert_close!(clone);
+        assert_close!(proxy);
+    })
+}
+
+#[test]
+fn remote_clone_node_reference_reaches_remote() {
+    let exec = fasync::TestExecutor::new().expect("Executor creation failed");
+    let scope = ExecutionScope::new();
+
+    let (server, opens) = logging_remote(set_up_remote(scope.clone()));
+
+    run_client(exec, || async move {
+        let (proxy, server_end) = fidl::endpoints::create_proxy::<fio::DirectoryMarker>().unwrap();
+        server.open(
+            scope,
+            fio::OpenFlags::NODE_REFERENCE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+
+        let clone = clone_get_directory_proxy_assert_ok!(
+            &proxy,
+            fio::OpenFlags::DESCRIBE | fio::OpenFlags::CLONE_SAME_RIGHTS
+        );
+        assert_eq!(*opens.lock().unwrap(), vec![fio::OpenFlags::NODE_REFERENCE]);
+
+        assert_close!(clone);
+        assert_close!(proxy);
+    })
+}
@@ -0,0 +1,167 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Helpers that connect a [`DirectoryEntry`] to a typed channel, without constructing the path
+//! and casting the server end by hand.
+
+use crate::{
+    common::send_on_open_with_error, directory::entry::DirectoryEntry,
+    execution_scope::ExecutionScope, path::Path,
+};
+
+use {fidl::endpoints::ServerEnd, fidl_fuchsia_io as fio, fuchsia_zircon::Status, std::sync::Arc};
+
+/// Serves the `entry` directory over `server_end`, running the connection in `scope`.  `flags`
+/// are the same as the flags of an `Open` request.
+///
+/// Fails with `ZX_ERR_NOT_DIR`, reported via `OnOpen` if `flags` include `OPEN_FLAG_DESCRIBE`, if
+/// `entry` reports itself as a file.  Entries of any other type, including the `Unknown` type
+/// remotes report, are opened, and it is up to them to reject the connection.
+pub fn serve_directory(
+    entry: Arc<dyn DirectoryEntry>,
+    scope: ExecutionScope,
+    flags: fio::OpenFlags,
+    server_end: ServerEnd<fio::DirectoryMarker>,
+) {
+    serve(
+        entry,
+        scope,
+        flags,
+        fio::DirentType::File,
+        Status::NOT_DIR,
+        server_end.into_channel().into(),
+    );
+}
+
+/// Serves the `entry` file over `server_end`, running the connection in `scope`.  `flags` are the
+/// same as the flags of an `Open` request.
+///
+/// Fails with `ZX_ERR_NOT_FILE`, reported via `OnOpen` if `flags` include `OPEN_FLAG_DESCRIBE`,
+/// if `entry` reports itself as a directory.  Entries of any other type, including the `Unknown`
+/// type remotes report, are opened, and it is up to them to reject the connection.
+pub fn serve_file(
+    entry: Arc<dyn DirectoryEntry>,
+    scope: ExecutionScope,
+    flags: fio::OpenFlags,
+    server_end: ServerEnd<fio::FileMarker>,
+) {
+    serve(
+        entry,
+        scope,
+        flags,
+        fio::DirentType::Directory,
+        Status::NOT_FILE,
+        server_end.into_channel().into(),
+    );
+}
+
+fn serve(
+    entry: Arc<dyn DirectoryEntry>,
+    scope: ExecutionScope,
+    flags: fio::OpenFlags,
+    mismatched_type: fio::DirentType,
+    mismatch: Status,
+    server_end: ServerEnd<fio::NodeMarker>,
+) {
+    if entry.entry_info().type_() == mismatched_type {
+        send_on_open_with_error(flags, server_end, mismatch);
+        return;
+    }
+
+    entry.open(scope, flags, 0, Path::dot(), server_end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{serve_directory, serve_file};
+
+    // Macros are exported into the root of the crate.
+    use crate::{assert_close, assert_event, assert_read, assert_read_dirents};
+
+    use crate::{
+        directory::{entry::DirectoryEntry, test_utils::DirentsSameInodeBuilder},
+        execution_scope::ExecutionScope,
+        file::vmo::asynchronous::read_only_static,
+        remote::remote,
+    };
+
+    use {
+        fidl::endpoints::create_proxy, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::Status, vfs_macros::pseudo_directory,
+    };
+
+    #[fasync::run_singlethreaded(test)]
+    async fn serve_directory_lists_entries() {
+        let root = pseudo_directory! {
+            "etc" => pseudo_directory! {},
+            "motd" => read_only_static(b"Hello"),
+        };
+
+        let (proxy, server_end) = create_proxy::<fio::DirectoryMarker>().unwrap();
+        serve_directory(root, ExecutionScope::new(), fio::OpenFlags::RIGHT_READABLE, server_end);
+
+        let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+        expected
+            .add(fio::DirentType::Directory, b".")
+            .add(fio::DirentType::Directory, b"etc")
+            .add(fio::DirentType::File, b"motd");
+        assert_read_dirents!(proxy, 1000, expected.into_vec());
+
+        assert_close!(proxy);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn serve_directory_through_remote() {
+        let root = pseudo_directory! {
+            "motd" => read_only_static(b"Hello"),
+        };
+        let remote_root = remote(move |scope, flags, mode, path, server_end| {
+            root.clone().open(scope, flags, mode, path, server_end)
+        });
+
+        let (proxy, server_end) = create_proxy::<fio::DirectoryMarker>().unwrap();
+        serve_directory(
+            remote_root,
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            server_end,
+        );
+
+        let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+        expected.add(fio::DirentType::Directory, b".").add(fio::DirentType::File, b"motd");
+        assert_read_dirents!(proxy, 1000, expected.into_vec());
+
+        assert_close!(proxy);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn serve_file_reads_content() {
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        serve_file(
+            read_only_static(b"Hello"),
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            server_end,
+        );
+
+        assert_read!(proxy, "Hello");
+        assert_close!(proxy);
+    }
+
+    #[fasync::run_singlethreaded(test)]
+    async fn serve_file_rejects_directory() {
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        serve_file(
+            pseudo_directory! {},
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE,
+            server_end,
+        );
+
+        assert_event!(proxy, fio::FileEvent::OnOpen_ { s, info }, {
+            assert_eq!(Status::from_raw(s), Status::NOT_FILE);
+            assert_eq!(info, None);
+        });
+    }
+}
@@ -69,7 +69,18 @@ pub fn endpoint<Open>(open: Open) -> Arc<Service>
 where
     Open: Fn(ExecutionScope, Channel) + Send + Sync + 'static,
 {
-    Arc::new(Service { open: Box::new(open) })
+    endpoint_with_inode(fio::INO_UNKNOWN, open)
+}
+
+/// Constructs a node in your file system that will host a service, identified by the specified
+/// `inode`.  The inode is reported in the directory listings and by the `GetAttr` calls on the
+/// connections opened with `OPEN_FLAG_NODE_REFERENCE`.  Otherwise, this is the same as
+/// [`endpoint`].
+pub fn endpoint_with_inode<Open>(inode: u64, open: Open) -> Arc<Service>
+where
+    Open: Fn(ExecutionScope, Channel) + Send + Sync + 'static,
+{
+    Arc::new(Service { inode, open: Box::new(open) })
 }
 
 /// Represents a node in the file system that hosts a service.  Opening a connection to this node
@@ -78,6 +89,7 @@ where
 ///
 /// Use [`host`] or [`endpoint`] to construct nodes of this type.
 pub struct Service {
+    inode: u64,
     open: Box<dyn Fn(ExecutionScope, Channel) + Send + Sync>,
 }
 
@@ -89,7 +101,7 @@ impl Service {
         mode: u32,
         server_end: ServerEnd<fio::NodeMarker>,
     ) {
-        Connection::create_connection(scope, flags, mode, server_end);
+        Connection::create_connection(scope, self.inode, flags, mode, server_end);
     }
 
     fn open_as_service(
@@ -153,6 +165,6 @@ impl DirectoryEntry for Service {
     }
 
     fn entry_info(&self) -> EntryInfo {
-        EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::Service)
+        EntryInfo::new(self.inode, fio::DirentType::Service)
     }
 }
@@ -27,6 +27,9 @@ pub struct Connection {
     /// use.
     scope: ExecutionScope,
 
+    /// Inode of the service node, reported by `GetAttr`.
+    inode: u64,
+
     /// Wraps a FIDL connection, providing messages coming from the client.
     requests: fio::FileRequestStream,
 }
@@ -47,11 +50,12 @@ impl Connection {
     /// `OnOpen` event if necessary.
     pub fn create_connection(
         scope: ExecutionScope,
+        inode: u64,
         flags: fio::OpenFlags,
         mode: u32,
         server_end: ServerEnd<fio::NodeMarker>,
     ) {
-        let task = Self::create_connection_task(scope.clone(), flags, mode, server_end);
+        let task = Self::create_connection_task(scope.clone(), inode, flags, mode, server_end);
         // If we failed to send the task to the executor, it is probably shut down or is in the
         // process of shutting down (this is the only error state currently).  So there is nothing
         // for us to do, but to ignore the open.  `server_end` will be closed when the object will
@@ -61,6 +65,7 @@ impl Connection {
 
     async fn create_connection_task(
         scope: ExecutionScope,
+        inode: u64,
         flags: fio::OpenFlags,
         mode: u32,
         server_end: ServerEnd<fio::NodeMarker>,
@@ -73,6 +78,15 @@ impl Connection {
             }
         };
 
+        // Held until the connection is closed.
+        let _slot = match scope.acquire_connection() {
+            Ok(slot) => slot,
+            Err(status) => {
+                send_on_open_with_error(flags, server_end, status);
+                return;
+            }
+        };
+
         let (requests, control_handle) =
             match ServerEnd::<fio::FileMarker>::new(server_end.into_channel())
                 .into_stream_and_control_handle()
@@ -93,7 +107,8 @@ impl Connection {
             }
         }
 
-        let handle_requests = Connection { scope: scope.clone(), requests }.handle_requests();
+        let handle_requests =
+            Connection { scope: scope.clone(), inode, requests }.handle_requests();
         handle_requests.await;
     }
 
@@ -158,7 +173,7 @@ impl Connection {
             fio::FileRequest::GetAttr { responder } => {
                 let mut attrs = fio::NodeAttributes {
                     mode: fio::MODE_TYPE_SERVICE | self.posix_protection_attributes(),
-                    id: fio::INO_UNKNOWN,
+                    id: self.inode,
                     content_size: 0,
                     storage_size: 0,
                     link_count: 1,
@@ -232,6 +247,10 @@ impl Connection {
             }
         };
 
-        Self::create_connection(self.scope.clone(), flags, 0, server_end);
+        if let Some(observer) = self.scope.clone_observer() {
+            observer(flags);
+        }
+
+        Self::create_connection(self.scope.clone(), self.inode, flags, 0, server_end);
     }
 }
@@ -5,7 +5,7 @@
 //! Tests for the service endpoint.
 
 // Make it easier for the nested modules to import the `endpoint` and `host` constructor.
-use super::{endpoint, host};
+use super::{endpoint, endpoint_with_inode, host};
 
 mod direct_connection;
 mod node_reference;
@@ -4,7 +4,7 @@
 
 //! Tests that connect to the service node itself.
 
-use super::endpoint;
+use super::{endpoint, endpoint_with_inode};
 
 // Macros are exported into the root of the crate.
 use crate::{
@@ -61,6 +61,29 @@ fn get_attr() {
     );
 }
 
+#[test]
+fn get_attr_with_inode() {
+    run_server_client(
+        fio::OpenFlags::NODE_REFERENCE,
+        endpoint_with_inode(42, |_scope, _channel| ()),
+        |proxy| async move {
+            assert_get_attr!(
+                proxy,
+                fio::NodeAttributes {
+                    mode: fio::MODE_TYPE_SERVICE | S_IRUSR | S_IWUSR,
+                    id: 42,
+                    content_size: 0,
+                    storage_size: 0,
+                    link_count: 1,
+                    creation_time: 0,
+                    modification_time: 0,
+                }
+            );
+            assert_close!(proxy);
+        },
+    );
+}
+
 #[test]
 fn describe() {
     let exec = TestExecutor::new().expect("TestExecutor creation failed");
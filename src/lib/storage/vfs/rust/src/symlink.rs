@@ -0,0 +1,72 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Implementation of a symbolic link node.
+
+mod connection;
+
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    common::send_on_open_with_error,
+    directory::entry::{DirectoryEntry, EntryInfo},
+    execution_scope::ExecutionScope,
+    path::Path,
+    symlink::connection::Connection,
+};
+
+use {fidl::endpoints::ServerEnd, fidl_fuchsia_io as fio, fuchsia_zircon::Status, std::sync::Arc};
+
+/// Constructs a node in your file system that is a symbolic link pointing to `target`.
+///
+/// The library does not follow symbolic links.  It is up to the client to read the target and to
+/// resolve it.  The only way to connect to a symbolic link is to open it with
+/// `OPEN_FLAG_NODE_REFERENCE`, and the target is then reported in the `Describe2` response.
+pub fn symlink(target: impl Into<String>) -> Arc<Symlink> {
+    Arc::new(Symlink { target: target.into() })
+}
+
+/// Represents a symbolic link in the file system.
+///
+/// Use [`symlink`] to construct nodes of this type.
+pub struct Symlink {
+    target: String,
+}
+
+impl Symlink {
+    /// Returns the path this symbolic link points to.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+impl DirectoryEntry for Symlink {
+    fn open(
+        self: Arc<Self>,
+        scope: ExecutionScope,
+        flags: fio::OpenFlags,
+        mode: u32,
+        path: Path,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        if !path.is_empty() {
+            send_on_open_with_error(flags, server_end, Status::NOT_DIR);
+            return;
+        }
+
+        if !flags.intersects(fio::OpenFlags::NODE_REFERENCE) {
+            // Symbolic links are resolved by the client, so there is no protocol we could speak
+            // over this connection.
+            send_on_open_with_error(flags, server_end, Status::NOT_SUPPORTED);
+            return;
+        }
+
+        Connection::create_connection(scope, self, flags, mode, server_end);
+    }
+
+    fn entry_info(&self) -> EntryInfo {
+        EntryInfo::new(fio::INO_UNKNOWN, fio::DirentType::Symlink)
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Implementation of a NODE_REFERENCE connection to a symbolic link.
+
+use crate::{
+    common::{inherit_rights_for_clone, send_on_open_with_error},
+    execution_scope::ExecutionScope,
+    symlink::Symlink,
+};
+
+use {
+    anyhow::Error,
+    fidl::endpoints::{RequestStream as _, ServerEnd},
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::{
+        sys::{ZX_ERR_ACCESS_DENIED, ZX_ERR_NOT_SUPPORTED, ZX_OK},
+        Status,
+    },
+    futures::stream::StreamExt,
+    libc::{S_IFLNK, S_IRUSR},
+    log::warn,
+    std::sync::Arc,
+};
+
+/// Represents a FIDL connection to a symbolic link.
+pub struct Connection {
+    /// Execution scope this connection and any async operations and connections it creates will
+    /// use.
+    scope: ExecutionScope,
+
+    /// Symbolic link this connection is attached to.
+    symlink: Arc<Symlink>,
+
+    /// Wraps a FIDL connection, providing messages coming from the client.
+    requests: fio::FileRequestStream,
+}
+
+/// Return type for [`Connection::handle_request()`].
+enum ConnectionState {
+    /// Connection is still alive.
+    Alive,
+    /// Connection have received Node::Close message, it was dropped by the peer, or an error had
+    /// occurred.
+    Closed,
+}
+
+/// Validates the flags for a new connection to a symbolic link.  Only NODE_REFERENCE connections
+/// are possible, so, similar to the services, all the rights are dropped and only
+/// `OPEN_FLAG_DESCRIBE` is preserved.
+///
+/// Changing this function can be dangerous!  Flags operations may have security implications.
+fn new_connection_validate_flags(
+    mut flags: fio::OpenFlags,
+    mode: u32,
+) -> Result<fio::OpenFlags, Status> {
+    if mode & !fio::MODE_PROTECTION_MASK != 0 {
+        if mode & fio::MODE_TYPE_MASK == fio::MODE_TYPE_DIRECTORY {
+            return Err(Status::NOT_DIR);
+        } else if mode & fio::MODE_TYPE_MASK == fio::MODE_TYPE_FILE {
+            return Err(Status::NOT_FILE);
+        } else {
+            return Err(Status::INVALID_ARGS);
+        };
+    }
+
+    if flags.intersects(fio::OpenFlags::DIRECTORY) {
+        return Err(Status::NOT_DIR);
+    }
+
+    // TODO(fxbug.dev/81185): Remove OPEN_FLAG_POSIX_DEPRECATED after all clients are updated.
+    flags &= !(fio::OpenFlags::NOT_DIRECTORY
+        | fio::OpenFlags::RIGHT_READABLE
+        | fio::OpenFlags::RIGHT_WRITABLE
+        | fio::OpenFlags::POSIX_DEPRECATED
+        | fio::OpenFlags::POSIX_WRITABLE
+        | fio::OpenFlags::POSIX_EXECUTABLE);
+
+    if flags.intersects(!fio::OPEN_FLAGS_ALLOWED_WITH_NODE_REFERENCE) {
+        return Err(Status::INVALID_ARGS);
+    }
+
+    Ok(flags & (fio::OpenFlags::NODE_REFERENCE | fio::OpenFlags::DESCRIBE))
+}
+
+impl Connection {
+    /// Initializes a NODE_REFERENCE connection to `symlink`, which will be running in the context
+    /// of the specified execution `scope`.  This function will also check the flags and will send
+    /// the `OnOpen` event if necessary.
+    pub fn create_connection(
+        scope: ExecutionScope,
+        symlink: Arc<Symlink>,
+        flags: fio::OpenFlags,
+        mode: u32,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        let task = Self::create_connection_task(scope.clone(), symlink, flags, mode, server_end);
+        // If we failed to send the task to the executor, it is probably shut down or is in the
+        // process of shutting down (this is the only error state currently).  So there is nothing
+        // for us to do, but to ignore the open.  `server_end` will be closed when the object will
+        // be dropped - there seems to be no error to report there.
+        let _ = scope.spawn(Box::pin(task));
+    }
+
+    async fn create_connection_task(
+        scope: ExecutionScope,
+        symlink: Arc<Symlink>,
+        flags: fio::OpenFlags,
+        mode: u32,
+        server_end: ServerEnd<fio::NodeMarker>,
+    ) {
+        let flags = match new_connection_validate_flags(flags, mode) {
+            Ok(updated) => updated,
+            Err(status) => {
+                send_on_open_with_error(flags, server_end, status);
+                return;
+            }
+        };
+
+        // Held until the connection is closed.
+        let _slot = match scope.acquire_connection() {
+            Ok(slot) => slot,
+            Err(status) => {
+                send_on_open_with_error(flags, server_end, status);
+                return;
+            }
+        };
+
+        let (requests, control_handle) =
+            match ServerEnd::<fio::FileMarker>::new(server_end.into_channel())
+                .into_stream_and_control_handle()
+            {
+                Ok((requests, control_handle)) => (requests, control_handle),
+                Err(_) => {
+                    // As we report all errors on `server_end`, if we failed to send an error over
+                    // this connection, there is nowhere to send the error to.
+                    return;
+                }
+            };
+
+        if flags.intersects(fio::OpenFlags::DESCRIBE) {
+            let mut info = Self::node_info();
+            match control_handle.send_on_open_(Status::OK.into_raw(), Some(&mut info)) {
+                Ok(()) => (),
+                Err(_) => return,
+            }
+        }
+
+        let handle_requests = Connection { scope, symlink, requests }.handle_requests();
+        handle_requests.await;
+    }
+
+    /// fuchsia.io/NodeInfo does not have a variant for symbolic links, so we report them as opaque
+    /// nodes, the same way services are reported.  Clients should use `Describe2` to get the
+    /// target.
+    fn node_info() -> fio::NodeInfo {
+        fio::NodeInfo::Service(fio::Service)
+    }
+
+    async fn handle_requests(mut self) {
+        while let Some(request_or_err) = self.requests.next().await {
+            match request_or_err {
+                Err(_) => {
+                    // FIDL level error, such as invalid message format and alike.  Close the
+                    // connection on any unexpected error.
+                    // TODO: Send an epitaph.
+                    break;
+                }
+                Ok(request) => {
+                    match self.handle_request(request).await {
+                        Ok(ConnectionState::Alive) => (),
+                        Ok(ConnectionState::Closed) | Err(_) => {
+                            // Err(_) means a protocol level error.  Close the connection on any
+                            // unexpected error.  TODO: Send an epitaph.
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handle a [`FileRequest`].  Only the `Node` part of the protocol is meaningful for a
+    /// symbolic link, all the other requests are rejected.
+    async fn handle_request(&mut self, req: fio::FileRequest) -> Result<ConnectionState, Error> {
+        match req {
+            fio::FileRequest::Clone { flags, object, control_handle: _ } => {
+                self.handle_clone(flags, object);
+            }
+            fio::FileRequest::Reopen { options: _, object_request: _, control_handle: _ } => {
+                return Ok(self.close_unsupported("Symlink::Reopen"));
+            }
+            fio::FileRequest::Close { responder } => {
+                responder.send(&mut Ok(()))?;
+                return Ok(ConnectionState::Closed);
+            }
+            fio::FileRequest::Describe { responder } => {
+                let mut info = Self::node_info();
+                responder.send(&mut info)?;
+            }
+            fio::FileRequest::Describe2 { query: _, responder } => {
+                let info = fio::ConnectionInfo {
+                    representation: Some(fio::Representation::Symlink(fio::SymlinkInfo {
+                        target: Some(self.symlink.target().as_bytes().to_vec()),
+                        ..fio::SymlinkInfo::EMPTY
+                    })),
+                    ..fio::ConnectionInfo::EMPTY
+                };
+                responder.send(info)?;
+            }
+            fio::FileRequest::Sync { responder } => {
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
+            }
+            fio::FileRequest::GetAttr { responder } => {
+                let mut attrs = fio::NodeAttributes {
+                    mode: S_IFLNK | S_IRUSR,
+                    id: fio::INO_UNKNOWN,
+                    content_size: self.symlink.target().len() as u64,
+                    storage_size: 0,
+                    link_count: 1,
+                    creation_time: 0,
+                    modification_time: 0,
+                };
+                responder.send(ZX_OK, &mut attrs)?;
+            }
+            fio::FileRequest::SetAttr { flags: _, attributes: _, responder } => {
+                responder.send(ZX_ERR_NOT_SUPPORTED)?;
+            }
+            fio::FileRequest::GetAttributes { query: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
+            }
+            fio::FileRequest::UpdateAttributes { attributes: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
+            }
+            fio::FileRequest::GetFlags { responder } => {
+                responder.send(ZX_OK, fio::OpenFlags::NODE_REFERENCE)?;
+            }
+            fio::FileRequest::SetFlags { flags: _, responder } => {
+                responder.send(ZX_ERR_NOT_SUPPORTED)?;
+            }
+            fio::FileRequest::AdvisoryLock { request: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
+            }
+            fio::FileRequest::Read { count: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_ACCESS_DENIED))?;
+            }
+            fio::FileRequest::ReadAt { offset: _, count: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_ACCESS_DENIED))?;
+            }
+            fio::FileRequest::WriteDeprecated { data: _, responder } => {
+                responder.send(ZX_ERR_ACCESS_DENIED, 0)?;
+            }
+            fio::FileRequest::Write { data: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_ACCESS_DENIED))?;
+            }
+            fio::FileRequest::WriteAt { offset: _, data: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_ACCESS_DENIED))?;
+            }
+            fio::FileRequest::Seek { origin: _, offset: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_ACCESS_DENIED))?;
+            }
+            fio::FileRequest::Resize { length: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_ACCESS_DENIED))?;
+            }
+            fio::FileRequest::GetBackingMemory { flags: _, responder } => {
+                responder.send(&mut Err(ZX_ERR_NOT_SUPPORTED))?;
+            }
+            fio::FileRequest::QueryFilesystem { responder } => {
+                responder.send(ZX_ERR_NOT_SUPPORTED, None)?;
+            }
+        }
+        Ok(ConnectionState::Alive)
+    }
+
+    /// Closes the connection with a `ZX_ERR_NOT_SUPPORTED` epitaph, for requests this connection
+    /// does not implement and that can not report an error in their response.  `method` identifies
+    /// the request in the log.
+    fn close_unsupported(&self, method: &str) -> ConnectionState {
+        warn!("{} is not supported, closing the symlink connection", method);
+        self.requests.control_handle().shutdown_with_epitaph(Status::NOT_SUPPORTED);
+        ConnectionState::Closed
+    }
+
+    fn handle_clone(&mut self, flags: fio::OpenFlags, server_end: ServerEnd<fio::NodeMarker>) {
+        let parent_flags = fio::OpenFlags::NODE_REFERENCE;
+        let flags = match inherit_rights_for_clone(parent_flags, flags) {
+            Ok(updated) => updated,
+            Err(status) => {
+                send_on_open_with_error(flags, server_end, status);
+                return;
+            }
+        };
+
+        if let Some(observer) = self.scope.clone_observer() {
+            observer(flags);
+        }
+
+        Self::create_connection(self.scope.clone(), self.symlink.clone(), flags, 0, server_end);
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Tests for the symbolic link node.
+
+use super::symlink;
+
+// Macros are exported into the root of the crate.
+use crate::{assert_close, assert_event, assert_get_attr, assert_read_dirents};
+
+use crate::{
+    directory::test_utils::{run_server_client, DirentsSameInodeBuilder},
+    test_utils::node::open_get_proxy,
+};
+
+use {
+    fidl_fuchsia_io as fio,
+    fuchsia_zircon::{sys::ZX_OK, Status},
+    libc::{S_IFLNK, S_IRUSR},
+    vfs_macros::pseudo_directory,
+};
+
+#[test]
+fn read_dirents_reports_symlink() {
+    let root = pseudo_directory! {
+        "link" => symlink("target/path"),
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let mut expected = DirentsSameInodeBuilder::new(fio::INO_UNKNOWN);
+        expected.add(fio::DirentType::Directory, b".").add(fio::DirentType::Symlink, b"link");
+
+        assert_read_dirents!(root, 1000, expected.into_vec());
+        assert_close!(root);
+    });
+}
+
+#[test]
+fn read_target() {
+    let root = pseudo_directory! {
+        "link" => symlink("target/path"),
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::NODE_REFERENCE | fio::OpenFlags::DESCRIBE;
+        let link = open_get_proxy::<fio::FileMarker>(&root, flags, 0, "link");
+        assert_event!(link, fio::FileEvent::OnOpen_ { s, info }, {
+            assert_eq!(s, ZX_OK);
+            assert_eq!(info, Some(Box::new(fio::NodeInfo::Service(fio::Service))));
+        });
+
+        let info = link
+            .describe2(fio::ConnectionInfoQuery::REPRESENTATION)
+            .await
+            .expect("describe2 failed");
+        assert_eq!(
+            info.representation,
+            Some(fio::Representation::Symlink(fio::SymlinkInfo {
+                target: Some(b"target/path".to_vec()),
+                ..fio::SymlinkInfo::EMPTY
+            }))
+        );
+
+        assert_get_attr!(
+            link,
+            fio::NodeAttributes {
+                mode: S_IFLNK | S_IRUSR,
+                id: fio::INO_UNKNOWN,
+                content_size: 11,
+                storage_size: 0,
+                link_count: 1,
+                creation_time: 0,
+                modification_time: 0,
+            }
+        );
+
+        assert_close!(link);
+        assert_close!(root);
+    });
+}
+
+#[test]
+fn open_without_node_reference() {
+    let root = pseudo_directory! {
+        "link" => symlink("target/path"),
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE;
+        let link = open_get_proxy::<fio::FileMarker>(&root, flags, 0, "link");
+        assert_event!(link, fio::FileEvent::OnOpen_ { s, info }, {
+            assert_eq!(Status::from_raw(s), Status::NOT_SUPPORTED);
+            assert_eq!(info, None);
+        });
+
+        assert_close!(root);
+    });
+}
+
+#[test]
+fn get_attributes_not_supported() {
+    let root = pseudo_directory! {
+        "link" => symlink("target/path"),
+    };
+
+    run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+        let flags = fio::OpenFlags::NODE_REFERENCE;
+        let link = open_get_proxy::<fio::FileMarker>(&root, flags, 0, "link");
+
+        let result = link
+            .get_attributes(fio::NodeAttributesQuery::CONTENT_SIZE)
+            .await
+            .expect("get_attributes failed")
+            .map_err(Status::from_raw);
+        assert_eq!(result.err(), Some(Status::NOT_SUPPORTED));
+
+        assert_close!(link);
+        assert_close!(root);
+    });
+}
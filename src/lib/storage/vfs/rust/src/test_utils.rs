@@ -5,9 +5,14 @@
 //! Utilities used by tests in both file and directory modules.
 
 pub mod assertions;
+#[cfg(test)]
+pub mod namespace;
 pub mod node;
 pub mod run;
 
+#[cfg(test)]
+pub use namespace::{install_in_namespace, NamespaceGuard};
+pub use node::open_and_describe;
 pub use run::{run_client, run_server_client, test_client, test_server_client, TestController};
 
 /// Returns a list of flag combinations to test. Returns a vector of the aggregate of
@@ -0,0 +1,115 @@
+// Copyright 2022 The Fuchsia Authors. All rights reserved.
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Utilities to make pseudo directories available in the process namespace, so the code under test
+//! can open them by path.
+//!
+//! Only built for the tests of this crate, as `fdio` is a test-only dependency.
+
+use crate::{directory::entry::DirectoryEntry, execution_scope::ExecutionScope, path::Path};
+
+use {
+    fidl::endpoints::create_endpoints, fidl_fuchsia_io as fio, fuchsia_zircon::Status,
+    std::sync::Arc,
+};
+
+/// Serves `dir` and binds it into the process namespace at `path`.  The directory is served in a
+/// new [`ExecutionScope`], so this function must be called from within a running executor, and
+/// the directory is only accessible while that executor is running.
+///
+/// The binding is removed and the scope is shut down when the returned guard is dropped.
+pub fn install_in_namespace(
+    dir: Arc<dyn DirectoryEntry>,
+    path: &str,
+) -> Result<NamespaceGuard, Status> {
+    let namespace = fdio::Namespace::installed()?;
+
+    let (client_end, server_end) =
+        create_endpoints::<fio::DirectoryMarker>().map_err(|_| Status::INTERNAL)?;
+    let scope = ExecutionScope::new();
+    dir.open(
+        scope.clone(),
+        fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::RIGHT_WRITABLE,
+        fio::MODE_TYPE_DIRECTORY,
+        Path::dot(),
+        server_end.into_channel().into(),
+    );
+
+    if let Err(status) = namespace.bind(path, client_end.into_channel()) {
+        scope.shutdown();
+        return Err(status);
+    }
+
+    Ok(NamespaceGuard { path: path.to_string(), scope })
+}
+
+/// Keeps a directory bound into the process namespace.  See [`install_in_namespace`].
+pub struct NamespaceGuard {
+    path: String,
+    scope: ExecutionScope,
+}
+
+impl NamespaceGuard {
+    /// Returns the namespace path the directory is bound at.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for NamespaceGuard {
+    fn drop(&mut self) {
+        // There is nothing we can do if the unbind fails, and the namespace entry will go away
+        // with the process anyways.
+        if let Ok(namespace) = fdio::Namespace::installed() {
+            let _ = namespace.unbind(&self.path);
+        }
+        self.scope.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::install_in_namespace;
+
+    use crate::file::vmo::asynchronous::read_only_static;
+
+    use {
+        fidl::endpoints::create_proxy, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::Status, vfs_macros::pseudo_directory,
+    };
+
+    #[fasync::run_singlethreaded(test)]
+    async fn open_file_from_namespace() {
+        let root = pseudo_directory! {
+            "etc" => pseudo_directory! {
+                "fstab" => read_only_static(b"/dev/fs /"),
+            },
+        };
+
+        let guard = install_in_namespace(root, "/test-namespace-guard").unwrap();
+        assert_eq!(guard.path(), "/test-namespace-guard");
+
+        let (proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        fdio::open(
+            "/test-namespace-guard/etc/fstab",
+            fio::OpenFlags::RIGHT_READABLE,
+            server_end.into_channel(),
+        )
+        .unwrap();
+        let content = proxy.read(100).await.unwrap().map_err(Status::from_raw).unwrap();
+        assert_eq!(content, b"/dev/fs /");
+
+        std::mem::drop(guard);
+
+        let (_proxy, server_end) = create_proxy::<fio::FileMarker>().unwrap();
+        assert_eq!(
+            fdio::open(
+                "/test-namespace-guard/etc/fstab",
+                fio::OpenFlags::RIGHT_READABLE,
+                server_end.into_channel(),
+            ),
+            Err(Status::NOT_FOUND)
+        );
+    }
+}
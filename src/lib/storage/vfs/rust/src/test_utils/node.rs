@@ -7,6 +7,8 @@
 use {
     fidl::endpoints::{create_proxy, ProtocolMarker, ServerEnd},
     fidl_fuchsia_io as fio,
+    fuchsia_zircon::Status,
+    futures::StreamExt,
 };
 
 pub fn open_get_proxy<M>(
@@ -28,6 +30,27 @@ where
     new_proxy
 }
 
+/// Opens `path` relative to `proxy` with `OPEN_FLAG_DESCRIBE` added to `flags`, and waits for the
+/// `OnOpen` event.  Returns the `NodeInfo` sent in the event, or the status the open failed with.
+/// A connection closed without an `OnOpen` event is reported as `ZX_ERR_PEER_CLOSED`.
+pub async fn open_and_describe(
+    proxy: &fio::DirectoryProxy,
+    path: &str,
+    flags: fio::OpenFlags,
+) -> Result<fio::NodeInfo, Status> {
+    let node = open_get_proxy::<fio::NodeMarker>(proxy, flags | fio::OpenFlags::DESCRIBE, 0, path);
+    match node.take_event_stream().next().await {
+        Some(Ok(fio::NodeEvent::OnOpen_ { s, info })) => {
+            Status::ok(s)?;
+            info.map(|info| *info).ok_or(Status::INTERNAL)
+        }
+        Some(Ok(_)) => Err(Status::INTERNAL),
+        Some(Err(fidl::Error::ClientChannelClosed { status, .. })) => Err(status),
+        Some(Err(_)) => Err(Status::INTERNAL),
+        None => Err(Status::PEER_CLOSED),
+    }
+}
+
 /// This trait repeats parts of the `NodeProxy` trait, and is implemented for `NodeProxy`,
 /// `FileProxy`, and `DirectoryProxy`, which all share the same API.  FIDL currently does not
 /// expose the API inheritance, so with this trait we have a workaround.  As soon as FIDL will
@@ -88,3 +111,46 @@ impl NodeProxyApi for fio::DirectoryProxy {
         Self::clone(self, flags, server_end)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::open_and_describe;
+
+    use crate::{
+        directory::entry::DirectoryEntry, execution_scope::ExecutionScope, file::mem_file,
+        path::Path,
+    };
+
+    use {
+        fidl::endpoints::create_proxy, fidl_fuchsia_io as fio, fuchsia_async as fasync,
+        fuchsia_zircon::Status, vfs_macros::pseudo_directory,
+    };
+
+    #[fasync::run_singlethreaded(test)]
+    async fn open_and_describe_file() {
+        let root = pseudo_directory! {
+            "file" => mem_file(b"Content".to_vec(), 100),
+        };
+        let (proxy, server_end) = create_proxy::<fio::DirectoryMarker>().unwrap();
+        root.open(
+            ExecutionScope::new(),
+            fio::OpenFlags::RIGHT_READABLE,
+            0,
+            Path::dot(),
+            server_end.into_channel().into(),
+        );
+
+        assert_eq!(
+            open_and_describe(&proxy, "file", fio::OpenFlags::RIGHT_READABLE).await,
+            Ok(fio::NodeInfo::File(fio::FileObject { event: None, stream: None }))
+        );
+        assert_eq!(
+            open_and_describe(&proxy, "missing", fio::OpenFlags::RIGHT_READABLE).await,
+            Err(Status::NOT_FOUND)
+        );
+        assert_eq!(
+            open_and_describe(&proxy, "file", fio::OpenFlags::RIGHT_WRITABLE).await,
+            Err(Status::ACCESS_DENIED)
+        );
+    }
+}
@@ -6,7 +6,7 @@
 
 use crate::{
     directory::{entry::DirectoryEntry, mutable::entry_constructor::EntryConstructor},
-    execution_scope::ExecutionScope,
+    execution_scope::{CloneObserver, ExecutionScope},
     path::Path,
     registry::{InodeRegistry, TokenRegistry},
 };
@@ -131,6 +131,8 @@ where
         token_registry: None,
         inode_registry: None,
         entry_constructor: None,
+        clone_observer: None,
+        max_connections: None,
     }
 }
 
@@ -158,6 +160,8 @@ where
         token_registry: None,
         inode_registry: None,
         entry_constructor: None,
+        clone_observer: None,
+        max_connections: None,
     }
 }
 
@@ -196,6 +200,8 @@ where
     token_registry: Option<Arc<dyn TokenRegistry + Send + Sync>>,
     inode_registry: Option<Arc<dyn InodeRegistry + Send + Sync>>,
     entry_constructor: Option<Arc<dyn EntryConstructor + Send + Sync>>,
+    clone_observer: Option<CloneObserver>,
+    max_connections: Option<usize>,
 }
 
 /// A helper that holds all the parameters necessary to run an async client-only test.
@@ -235,6 +241,8 @@ where
     field_setter!(token_registry, Arc<dyn TokenRegistry + Send + Sync>);
     field_setter!(inode_registry, Arc<dyn InodeRegistry + Send + Sync>);
     field_setter!(entry_constructor, Arc<dyn EntryConstructor + Send + Sync>);
+    field_setter!(clone_observer, CloneObserver);
+    field_setter!(max_connections, usize);
 
     /// Runs the test based on the parameters specified in the [`test_server_client`] and other
     /// method calls.
@@ -258,6 +266,14 @@ where
             Some(entry_constructor) => scope_builder.entry_constructor(entry_constructor),
             None => scope_builder,
         };
+        let scope_builder = match self.clone_observer {
+            Some(clone_observer) => scope_builder.clone_observer(clone_observer),
+            None => scope_builder,
+        };
+        let scope_builder = match self.max_connections {
+            Some(max_connections) => scope_builder.max_connections(max_connections),
+            None => scope_builder,
+        };
         self.server.open(
             scope_builder.new(),
             self.flags,
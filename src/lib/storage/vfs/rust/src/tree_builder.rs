@@ -101,6 +101,8 @@ impl TreeBuilder {
     /// Adds a [`DirectoryEntry`] at the specified path.  It can be either a file or a directory.
     /// In case it is a directory, this builder cannot add new child nodes inside of the added
     /// directory.  Any `entry` is treated as an opaque "leaf" as far as the builder is concerned.
+    /// In particular, a [`crate::remote::Remote`] node can be added to graft another directory
+    /// into the tree, with any missing intermediate directories created automatically.
     pub fn add_entry<'components, P: 'components, PathImpl>(
         &mut self,
         path: P,
@@ -197,6 +199,83 @@ impl TreeBuilder {
         }
     }
 
+    /// Adds a [`DirectoryEntry`] at the specified path, same as [`Self::add_entry()`], except
+    /// that any leaf or directory already present at this path is replaced with `entry`.  This is
+    /// useful when a base tree is composed first, and then some of the nodes are overridden.
+    /// Missing intermediate directories are created.
+    pub fn replace<'components, P: 'components, PathImpl>(
+        &mut self,
+        path: P,
+        entry: Arc<dyn DirectoryEntry>,
+    ) -> Result<(), Error>
+    where
+        P: Into<Path<'components, PathImpl>>,
+        PathImpl: AsRef<[&'components str]>,
+    {
+        let path = path.into();
+        let traversed = vec![];
+        let mut rest = path.iter();
+        match rest.next() {
+            None => Err(Error::EmptyPath),
+            Some(name) => self.add_path(
+                &path,
+                traversed,
+                name,
+                rest,
+                |entries, name, _full_path, _traversed| {
+                    let _: Option<TreeBuilder> =
+                        entries.insert(name.to_string(), TreeBuilder::Leaf(entry));
+                    Ok(())
+                },
+            ),
+        }
+    }
+
+    /// Removes a directory previously added with [`Self::add_empty_dir()`], or implicitly created
+    /// by any of the other methods.  The directory must exist and must be empty.  Leaf nodes can
+    /// not be removed, but can be overridden with [`Self::replace()`].
+    pub fn remove_empty_dir<'components, P: 'components, PathImpl>(
+        &mut self,
+        path: P,
+    ) -> Result<(), Error>
+    where
+        P: Into<Path<'components, PathImpl>>,
+        PathImpl: AsRef<[&'components str]>,
+    {
+        let path = path.into();
+        let mut rest = path.iter();
+        let mut name = match rest.next() {
+            None => return Err(Error::EmptyPath),
+            Some(name) => name,
+        };
+
+        let mut current = self;
+        for next_component in rest {
+            current = match current {
+                TreeBuilder::Directory(entries) => entries.get_mut(*name),
+                TreeBuilder::Leaf(_) => None,
+            }
+            .ok_or_else(|| Error::NotFound { path: path.to_string() })?;
+            name = next_component;
+        }
+
+        let entries = match current {
+            TreeBuilder::Directory(entries) => entries,
+            TreeBuilder::Leaf(_) => return Err(Error::NotFound { path: path.to_string() }),
+        };
+        match entries.get(*name) {
+            None => Err(Error::NotFound { path: path.to_string() }),
+            Some(TreeBuilder::Leaf(_)) => Err(Error::RemoveLeaf { path: path.to_string() }),
+            Some(TreeBuilder::Directory(children)) if !children.is_empty() => {
+                Err(Error::RemoveNonEmptyDirectory { path: path.to_string() })
+            }
+            Some(TreeBuilder::Directory(_)) => {
+                let _: Option<TreeBuilder> = entries.remove(*name);
+                Ok(())
+            }
+        }
+    }
+
     fn add_path<'path, 'components: 'path, PathImpl, Inserter>(
         &mut self,
         full_path: &'path Path<'components, PathImpl>,
@@ -362,6 +441,27 @@ pub enum Error {
         traversed
     )]
     EntryInsideLeaf { path: String, traversed: String },
+
+    #[error(
+        "Path does not exist.\n\
+                   Path: {}",
+        path
+    )]
+    NotFound { path: String },
+
+    #[error(
+        "Trying to remove a leaf with `remove_empty_dir`.\n\
+                   Path: {}",
+        path
+    )]
+    RemoveLeaf { path: String },
+
+    #[error(
+        "Trying to remove a directory that is not empty.\n\
+                   Path: {}",
+        path
+    )]
+    RemoveNonEmptyDirectory { path: String },
 }
 
 #[cfg(test)]
@@ -379,9 +479,14 @@ mod tests {
     use crate::{
         directory::{immutable::simple, test_utils::run_server_client},
         file::vmo::asynchronous::read_only_static,
+        remote::remote,
     };
 
-    use {fidl_fuchsia_io as fio, vfs_macros::pseudo_directory};
+    use {
+        fidl_fuchsia_io as fio,
+        futures::{channel::mpsc, StreamExt},
+        vfs_macros::pseudo_directory,
+    };
 
     #[test]
     fn vfs_with_custom_inodes() {
@@ -709,6 +814,139 @@ mod tests {
         });
     }
 
+    #[test]
+    fn replace_leaf() {
+        let mut tree = TreeBuilder::empty_dir();
+        tree.add_entry(&["etc", "fstab"], read_only_static(b"Base")).unwrap();
+        tree.add_entry(&["etc", "hosts"], read_only_static(b"Base")).unwrap();
+
+        tree.replace(&["etc", "fstab"], read_only_static(b"Override")).unwrap();
+        tree.replace(&["new", "file"], read_only_static(b"Created")).unwrap();
+
+        let root = tree.build();
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            assert_read_dirents_one_listing!(
+                root, 1000,
+                { DIRECTORY, b"." },
+                { DIRECTORY, b"etc" },
+                { DIRECTORY, b"new" },
+            );
+
+            open_as_vmo_file_assert_content!(
+                &root,
+                fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE,
+                "etc/fstab",
+                "Override"
+            );
+            open_as_vmo_file_assert_content!(
+                &root,
+                fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE,
+                "etc/hosts",
+                "Base"
+            );
+            open_as_vmo_file_assert_content!(
+                &root,
+                fio::OpenFlags::RIGHT_READABLE | fio::OpenFlags::DESCRIBE,
+                "new/file",
+                "Created"
+            );
+
+            assert_close!(root);
+        });
+    }
+
+    #[test]
+    fn remove_empty_dir() {
+        let mut tree = TreeBuilder::empty_dir();
+        tree.add_empty_dir(&["one", "two"]).unwrap();
+        tree.add_empty_dir(&["one", "three"]).unwrap();
+
+        tree.remove_empty_dir(&["one", "two"]).unwrap();
+
+        let root = tree.build();
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            assert_read_dirents_path_one_listing!(
+                &root, "one", 1000,
+                { DIRECTORY, b"." },
+                { DIRECTORY, b"three" },
+            );
+            assert_close!(root);
+        });
+    }
+
+    #[test]
+    fn error_remove_empty_dir() {
+        let mut tree = TreeBuilder::empty_dir();
+        tree.add_entry(&["top", "file"], read_only_static(b"Content")).unwrap();
+
+        assert_eq!(
+            tree.remove_empty_dir(&["top", "missing"]),
+            Err(Error::NotFound { path: "top/missing".to_string() })
+        );
+        assert_eq!(
+            tree.remove_empty_dir(&["top", "file", "nested"]),
+            Err(Error::NotFound { path: "top/file/nested".to_string() })
+        );
+        assert_eq!(
+            tree.remove_empty_dir(&["top", "file"]),
+            Err(Error::RemoveLeaf { path: "top/file".to_string() })
+        );
+        assert_eq!(
+            tree.remove_empty_dir("top"),
+            Err(Error::RemoveNonEmptyDirectory { path: "top".to_string() })
+        );
+    }
+
+    #[test]
+    fn remote_leaf() {
+        let (open_tx, mut open_rx) = mpsc::unbounded();
+
+        let mut tree = TreeBuilder::empty_dir();
+        tree.add_entry(
+            &["svc", "fuchsia.logger.LogSink"],
+            remote(move |_scope, _flags, _mode, path, _server_end| {
+                open_tx.unbounded_send(path.into_string()).unwrap();
+            }),
+        )
+        .unwrap();
+        tree.add_entry(&["svc", "fuchsia.other.Service"], read_only_static(b"Other")).unwrap();
+
+        assert_eq!(
+            tree.add_entry(&["svc", "fuchsia.logger.LogSink", "nested"], read_only_static(b"")),
+            Err(Error::EntryInsideLeaf {
+                path: "svc/fuchsia.logger.LogSink/nested".to_string(),
+                traversed: "svc/fuchsia.logger.LogSink".to_string()
+            })
+        );
+
+        let root = tree.build();
+
+        run_server_client(fio::OpenFlags::RIGHT_READABLE, root, |root| async move {
+            assert_read_dirents_path_one_listing!(
+                &root, "svc", 1000,
+                { DIRECTORY, b"." },
+                { UNKNOWN, b"fuchsia.logger.LogSink" },
+                { FILE, b"fuchsia.other.Service" },
+            );
+
+            let open = |path: &str| {
+                let (_proxy, server_end) =
+                    fidl::endpoints::create_proxy::<fio::NodeMarker>().unwrap();
+                root.open(fio::OpenFlags::RIGHT_READABLE, 0, path, server_end).unwrap();
+            };
+
+            open("svc/fuchsia.logger.LogSink");
+            assert_eq!(open_rx.next().await, Some("".to_string()));
+
+            open("svc/fuchsia.logger.LogSink/nested/path");
+            assert_eq!(open_rx.next().await, Some("nested/path".to_string()));
+
+            assert_close!(root);
+        });
+    }
+
     #[test]
     fn error_empty_path_in_add_entry() {
         let mut tree = TreeBuilder::empty_dir();